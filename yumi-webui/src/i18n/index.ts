@@ -52,6 +52,16 @@ const messages = {
     name_empty: 'Name cannot be empty',
     governor_exists: 'Governor already exists',
     path_exists: 'Path already exists',
+    runtime_status: 'Runtime Status',
+    scheduler_state: 'Scheduler',
+    state_on: 'Active',
+    state_off: 'Off (tunables not applied)',
+    state_dry_run: 'Dry-run',
+    state_boosting: 'Launch boost',
+    foreground_app: 'Foreground App',
+    mode_override: 'Temporary Mode',
+    cpu_temp: 'CPU Temperature',
+    config_error: 'Config error',
   },
   zh: {
     welcome: '欢迎使用 yumi 调度',
@@ -103,6 +113,16 @@ const messages = {
     name_empty: '名称不能为空',
     governor_exists: '调速器已存在',
     path_exists: 'path 已存在',
+    runtime_status: '运行状态',
+    scheduler_state: '调度',
+    state_on: '生效中',
+    state_off: '已关闭 (不写入调优)',
+    state_dry_run: '演练模式 (dry-run)',
+    state_boosting: '启动加速中',
+    foreground_app: '前台应用',
+    mode_override: '临时模式',
+    cpu_temp: 'CPU 温度',
+    config_error: '配置错误',
   }
 };
 
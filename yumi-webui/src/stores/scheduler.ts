@@ -7,25 +7,34 @@ export const useSchedulerStore = defineStore('scheduler', {
     currentMode: 'balance',
     appRules: {} as Record<string, string>,
     isDaemonRunning: false, // 必须有这个初始状态
+    // 守护进程每秒写出的 state.json，读不到时为 null
+    runtime: null as any | null,
     loading: false
   }),
   actions: {
     async initData() {
       this.loading = true;
       try {
-        // 必须在这里同时调用三个接口
-        const [mode, rules, running] = await Promise.all([
+        // 必须在这里同时调用四个接口
+        const [mode, rules, running, runtime] = await Promise.all([
           Bridge.getCurrentMode(),
           Bridge.getAppRules(),
-          Bridge.isDaemonRunning() 
+          Bridge.isDaemonRunning(),
+          Bridge.getRuntimeStatus()
         ]);
         this.currentMode = mode;
         this.appRules = rules;
         this.isDaemonRunning = running; // 必须有这一行赋值
+        this.runtime = runtime;
       } finally {
         this.loading = false;
       }
     },
+    async refreshRuntime() {
+      const [running, runtime] = await Promise.all([Bridge.isDaemonRunning(), Bridge.getRuntimeStatus()]);
+      this.isDaemonRunning = running;
+      this.runtime = runtime;
+    },
     async switchMode(mode: string) {
       await Bridge.setMode(mode);
       this.currentMode = mode;
@@ -13,12 +13,68 @@ const MODULE_BASE_PATH = "/data/adb/modules/yumi";
 const PATHS = {
   RULES_YAML: `${MODULE_BASE_PATH}/rules.yaml`,          
   CONFIG_YAML: `${MODULE_BASE_PATH}/config/config.yaml`, 
+  DEVICE_YAML: `${MODULE_BASE_PATH}/config/device.yaml`,
+  USER_YAML: `${MODULE_BASE_PATH}/config/user.yaml`,
   CURRENT_MODE: `${MODULE_BASE_PATH}/current_mode.txt`,
+  STATE_JSON: `${MODULE_BASE_PATH}/state.json`,
   DAEMON_LOG: `${MODULE_BASE_PATH}/logs/daemon.log`
 };
 
 const isDev = import.meta.env.DEV || typeof window.ksu === 'undefined';
 
+const isPlainObject = (v: any): v is Record<string, any> =>
+  v !== null && typeof v === 'object' && !Array.isArray(v);
+
+// 与守护进程一致：对象逐键深度合并 (键名大小写不敏感)，列表与标量整体替换
+function findKey(obj: Record<string, any>, key: string): string | undefined {
+  return Object.keys(obj).find(k => k.toLowerCase() === key.toLowerCase());
+}
+
+// 覆盖层中值为 null 的键表示删除下层的同名键，与守护进程的合并规则一致
+function mergeLayer(base: any, patch: any): any {
+  if (!isPlainObject(base) || !isPlainObject(patch)) return patch === undefined ? base : patch;
+  const out: Record<string, any> = { ...base };
+  for (const [key, value] of Object.entries(patch)) {
+    const existing = findKey(out, key) ?? key;
+    if (value === null) delete out[existing];
+    else out[existing] = mergeLayer(out[existing], value);
+  }
+  return out;
+}
+
+// 深度比较，映射的键名大小写不敏感且与顺序无关
+function sameValue(a: any, b: any): boolean {
+  if (isPlainObject(a) && isPlainObject(b)) {
+    const keys = Object.keys(a);
+    if (keys.length !== Object.keys(b).length) return false;
+    return keys.every(k => {
+      const other = findKey(b, k);
+      return other !== undefined && sameValue(a[k], b[other]);
+    });
+  }
+  if (Array.isArray(a) && Array.isArray(b)) {
+    return a.length === b.length && a.every((v, i) => sameValue(v, b[i]));
+  }
+  return a === b;
+}
+
+// 只保留 value 中与 base 不同的键，作为用户覆盖层写入；base 中有而 value 中删掉的键写为 null
+function diffLayer(base: any, value: any): any {
+  if (!isPlainObject(base) || !isPlainObject(value)) {
+    return sameValue(base, value) ? undefined : value;
+  }
+  const out: Record<string, any> = {};
+  for (const [key, v] of Object.entries(value)) {
+    const baseKey = findKey(base, key);
+    const d = diffLayer(baseKey === undefined ? undefined : base[baseKey], v);
+    if (d !== undefined && !(isPlainObject(d) && Object.keys(d).length === 0)) out[key] = d;
+  }
+  for (const key of Object.keys(base)) {
+    if (findKey(value, key) === undefined) out[key] = null;
+  }
+  return out;
+}
+
 const RealBridge = {
   async isDaemonRunning(): Promise<boolean> {
     try {
@@ -42,10 +98,49 @@ const RealBridge = {
 
   async getRulesConfig(): Promise<any> { try { return yaml.load(await this.readFile(PATHS.RULES_YAML)) || {}; } catch (e) { return {}; } },
   async saveRulesConfig(config: any): Promise<void> { await this.writeFile(PATHS.RULES_YAML, yaml.dump(config)); },
-  async getMainConfig(): Promise<any> { try { return yaml.load(await this.readFile(PATHS.CONFIG_YAML)) || {}; } catch (e) { return {}; } },
-  async saveMainConfig(config: any): Promise<void> { await this.writeFile(PATHS.CONFIG_YAML, yaml.dump(config)); toast('核心配置已保存'); },
+  /**
+   * 读取一层 YAML：文件不存在视为空层；存在但解析失败时抛出，避免保存时覆盖掉用户手写的内容
+   */
+  async readYaml(path: string): Promise<any> {
+    const { errno } = await exec(`[ -e "${path}" ]`);
+    if (errno !== 0) return {};
+    const content = await this.readFile(path);
+    try {
+      return yaml.load(content) || {};
+    } catch (e: any) {
+      throw new Error(`${path}: ${e?.message ?? e}`);
+    }
+  },
+  /**
+   * 主配置分层存放：config.yaml (随模块更新替换) < device.yaml < user.yaml，这里返回合并结果
+   */
+  async getMainConfig(): Promise<any> {
+    const layers = await Promise.all([PATHS.CONFIG_YAML, PATHS.DEVICE_YAML, PATHS.USER_YAML].map(p => this.readYaml(p)));
+    return layers.reduce((merged, layer) => mergeLayer(merged, layer), {});
+  },
+  /**
+   * 只把与下层不同的键写入 user.yaml，模块更新后用户改动依然生效
+   */
+  async saveMainConfig(config: any): Promise<void> {
+    const [baseLayer, deviceLayer] = await Promise.all([PATHS.CONFIG_YAML, PATHS.DEVICE_YAML].map(p => this.readYaml(p)));
+    // 现有的 user.yaml 解析失败时不覆盖，留给用户手动修复
+    await this.readYaml(PATHS.USER_YAML);
+    const base = mergeLayer(baseLayer, deviceLayer);
+    await this.writeFile(PATHS.USER_YAML, yaml.dump(diffLayer(base, config)));
+    toast('核心配置已保存');
+  },
 
   async getCurrentMode(): Promise<string> { try { return (await this.readFile(PATHS.CURRENT_MODE)).trim(); } catch (e) { return 'balance'; } },
+  /**
+   * 读取守护进程每秒发布的运行时状态快照，未运行或尚未生成时返回 null
+   */
+  async getRuntimeStatus(): Promise<any | null> {
+    try {
+      return JSON.parse(await this.readFile(PATHS.STATE_JSON));
+    } catch (e) {
+      return null;
+    }
+  },
   async setMode(mode: string): Promise<void> {
     const rules = await this.getRulesConfig();
     rules.global_mode = mode;
@@ -113,6 +113,20 @@ const mockGovernors: string[] = ['schedutil', 'performance', 'powersave', 'users
 export const MockBridge = {
   async isDaemonRunning(): Promise<boolean> { await delay(100); return true; },
   async getCurrentMode(): Promise<string> { await delay(200); return simulatedModeTxt; },
+  async getRuntimeStatus(): Promise<any | null> {
+    await delay(150);
+    return {
+      timestamp_ms: Date.now(), daemon_pid: 1234, enabled: true, dry_run: false, config_errors: {},
+      mode: simulatedModeTxt, mode_override: null,
+      package: 'com.android.chrome', pid: 4321, screen_on: true, temperature: 42.5,
+      boosting: false, fas_suspended: false, fas: null,
+      clg: { active: true, clusters: [{ policy_id: 0, cpus: [0, 1, 2, 3], current_perf: 0.42, current_freq: 1200000 }] },
+      sources: [
+        { name: 'cpu_load', state: 'running', restarts: 0, last_error: null, since_ms: Date.now() - 60000, retry_at_ms: null },
+        { name: 'fps', state: 'running', restarts: 0, last_error: null, since_ms: Date.now() - 60000, retry_at_ms: null }
+      ]
+    };
+  },
   async setMode(mode: string): Promise<void> { 
     await delay(200); mockRules.global_mode = mode; 
     setTimeout(() => { simulatedModeTxt = mode; }, 800);
@@ -0,0 +1,421 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 守护进程事件总线
+//!
+//! 取代原先单一的无界 `mpsc` 通道。每个订阅者拥有三条独立车道：
+//!
+//! - 控制车道：ModeChange / ConfigReload / Control / SourceHealth，优先出队
+//! - 负载车道：只保留最新一条 SystemLoadUpdate，旧的直接合并掉
+//! - 帧车道：有界环形队列，满了丢弃最旧的 FrameUpdate
+//!
+//! 出队顺序为 控制 > 负载 > 帧。负载车道最多只有一条，不会饿死帧车道；
+//! 144fps 下的帧洪峰也不会再挡住模式切换。
+//!
+//! `send` / `recv_timeout` 沿用 `std::sync::mpsc` 的签名与错误类型，
+//! 生产者与 scheduler 的循环只需替换类型。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, SendError};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::common::DaemonEvent;
+
+/// 帧车道容量，约 220ms@144fps
+pub const FRAME_QUEUE_CAPACITY: usize = 32;
+/// 控制车道容量，正常情况下远达不到；只有消费者卡死时才会丢弃最旧的事件
+pub const CONTROL_QUEUE_CAPACITY: usize = 256;
+
+lazy_static::lazy_static! {
+    /// 所有存活订阅者的统计，供 metrics 导出
+    static ref REGISTRY: Mutex<Vec<Weak<Queue>>> = Mutex::new(Vec::new());
+}
+
+/// 事件种类，订阅时声明关心哪些
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    ModeChange,
+    FrameUpdate,
+    SystemLoadUpdate,
+    ConfigReload,
+    Control,
+    SourceHealth,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 6] = [
+        EventKind::ModeChange,
+        EventKind::FrameUpdate,
+        EventKind::SystemLoadUpdate,
+        EventKind::ConfigReload,
+        EventKind::Control,
+        EventKind::SourceHealth,
+    ];
+
+    fn of(event: &DaemonEvent) -> Self {
+        match event {
+            DaemonEvent::ModeChange { .. } => EventKind::ModeChange,
+            DaemonEvent::FrameUpdate { .. } => EventKind::FrameUpdate,
+            DaemonEvent::SystemLoadUpdate { .. } => EventKind::SystemLoadUpdate,
+            DaemonEvent::ConfigReload(_) => EventKind::ConfigReload,
+            DaemonEvent::Control { .. } => EventKind::Control,
+            DaemonEvent::SourceHealth { .. } => EventKind::SourceHealth,
+        }
+    }
+
+    fn bit(self) -> u8 { 1 << (self as u8) }
+}
+
+/// 单个订阅者的计数器
+#[derive(Default)]
+struct QueueStats {
+    delivered: AtomicU64,
+    frames_dropped: AtomicU64,
+    control_dropped: AtomicU64,
+    loads_coalesced: AtomicU64,
+    frame_depth_max: AtomicUsize,
+}
+
+/// 某个订阅者的统计快照
+#[derive(Debug, Clone)]
+pub struct SubscriberStats {
+    pub name: String,
+    pub control_depth: usize,
+    pub frame_depth: usize,
+    pub load_pending: bool,
+    /// 启动以来帧车道的最高水位
+    pub frame_depth_max: usize,
+    pub delivered: u64,
+    pub frames_dropped: u64,
+    pub control_dropped: u64,
+    pub loads_coalesced: u64,
+}
+
+#[derive(Default)]
+struct Lanes {
+    control: VecDeque<DaemonEvent>,
+    load: Option<DaemonEvent>,
+    frames: VecDeque<DaemonEvent>,
+    /// 所有 Publisher 都已销毁
+    disconnected: bool,
+}
+
+impl Lanes {
+    fn pop(&mut self) -> Option<DaemonEvent> {
+        self.control.pop_front()
+            .or_else(|| self.load.take())
+            .or_else(|| self.frames.pop_front())
+    }
+}
+
+struct Queue {
+    name: String,
+    mask: u8,
+    lanes: Mutex<Lanes>,
+    cond: Condvar,
+    stats: QueueStats,
+}
+
+impl Queue {
+    fn push(&self, event: DaemonEvent) {
+        let mut lanes = self.lanes.lock().unwrap();
+        match event {
+            DaemonEvent::FrameUpdate { .. } => {
+                if lanes.frames.len() >= FRAME_QUEUE_CAPACITY {
+                    lanes.frames.pop_front();
+                    self.stats.frames_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                lanes.frames.push_back(event);
+                self.stats.frame_depth_max.fetch_max(lanes.frames.len(), Ordering::Relaxed);
+            }
+            DaemonEvent::SystemLoadUpdate { .. } => {
+                if lanes.load.replace(event).is_some() {
+                    self.stats.loads_coalesced.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            _ => {
+                if lanes.control.len() >= CONTROL_QUEUE_CAPACITY {
+                    lanes.control.pop_front();
+                    self.stats.control_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                lanes.control.push_back(event);
+            }
+        }
+        drop(lanes);
+        self.cond.notify_one();
+    }
+
+    fn disconnect(&self) {
+        self.lanes.lock().unwrap().disconnected = true;
+        self.cond.notify_all();
+    }
+
+    fn snapshot(&self) -> SubscriberStats {
+        let lanes = self.lanes.lock().unwrap();
+        SubscriberStats {
+            name: self.name.clone(),
+            control_depth: lanes.control.len(),
+            frame_depth: lanes.frames.len(),
+            load_pending: lanes.load.is_some(),
+            frame_depth_max: self.stats.frame_depth_max.load(Ordering::Relaxed),
+            delivered: self.stats.delivered.load(Ordering::Relaxed),
+            frames_dropped: self.stats.frames_dropped.load(Ordering::Relaxed),
+            control_dropped: self.stats.control_dropped.load(Ordering::Relaxed),
+            loads_coalesced: self.stats.loads_coalesced.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Shared {
+    subscribers: Mutex<Vec<Arc<Queue>>>,
+    publishers: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl Shared {
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        for queue in self.subscribers.lock().unwrap().iter() {
+            queue.disconnect();
+        }
+    }
+}
+
+/// 总线本体，负责创建发布端与订阅端
+pub struct EventBus {
+    shared: Arc<Shared>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                subscribers: Mutex::new(Vec::new()),
+                publishers: AtomicUsize::new(0),
+                closed: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn publisher(&self) -> Publisher {
+        self.shared.publishers.fetch_add(1, Ordering::SeqCst);
+        Publisher { shared: Arc::clone(&self.shared) }
+    }
+
+    /// 新增一个订阅者，只接收 `kinds` 中的事件
+    ///
+    /// Control 事件携带回复通道，应当只由一个订阅者 (scheduler) 订阅。
+    pub fn subscribe(&self, name: &str, kinds: &[EventKind]) -> Subscriber {
+        let queue = Arc::new(Queue {
+            name: name.to_string(),
+            mask: kinds.iter().fold(0, |m, k| m | k.bit()),
+            lanes: Mutex::new(Lanes::default()),
+            cond: Condvar::new(),
+            stats: QueueStats::default(),
+        });
+        if self.shared.closed.load(Ordering::SeqCst) {
+            queue.disconnect();
+        }
+        self.shared.subscribers.lock().unwrap().push(Arc::clone(&queue));
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.retain(|w| w.strong_count() > 0);
+        registry.push(Arc::downgrade(&queue));
+        Subscriber { queue, shared: Arc::clone(&self.shared) }
+    }
+}
+
+/// 发布端，可任意克隆；全部销毁后订阅者收到 Disconnected
+pub struct Publisher {
+    shared: Arc<Shared>,
+}
+
+impl Clone for Publisher {
+    fn clone(&self) -> Self {
+        self.shared.publishers.fetch_add(1, Ordering::SeqCst);
+        Self { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        if self.shared.publishers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.close();
+        }
+    }
+}
+
+impl Publisher {
+    /// 投递给所有订阅了该类事件的订阅者；没有任何订阅者存活时返回错误
+    ///
+    /// 与 `mpsc::Sender::send` 一样把事件原样退回，ConfigReload 较大也不装箱。
+    #[allow(clippy::result_large_err)]
+    pub fn send(&self, event: DaemonEvent) -> Result<(), SendError<DaemonEvent>> {
+        let subscribers = self.shared.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return Err(SendError(event));
+        }
+        let bit = EventKind::of(&event).bit();
+        let mut targets = subscribers.iter().filter(|q| q.mask & bit != 0).peekable();
+        while let Some(queue) = targets.next() {
+            if targets.peek().is_some() {
+                queue.push(event.clone());
+            } else {
+                queue.push(event);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 订阅端，销毁时自动从总线上注销
+pub struct Subscriber {
+    queue: Arc<Queue>,
+    shared: Arc<Shared>,
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.shared.subscribers.lock().unwrap().retain(|q| !Arc::ptr_eq(q, &self.queue));
+    }
+}
+
+impl Subscriber {
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<DaemonEvent, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut lanes = self.queue.lanes.lock().unwrap();
+        loop {
+            if let Some(event) = lanes.pop() {
+                self.queue.stats.delivered.fetch_add(1, Ordering::Relaxed);
+                return Ok(event);
+            }
+            if lanes.disconnected {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            lanes = self.queue.cond.wait_timeout(lanes, deadline - now).unwrap().0;
+        }
+    }
+}
+
+/// 所有存活订阅者的统计
+pub fn stats() -> Vec<SubscriberStats> {
+    REGISTRY.lock().unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|q| q.snapshot())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(n: u64) -> DaemonEvent {
+        DaemonEvent::FrameUpdate { fps: 60.0, frame_delta_ns: n }
+    }
+
+    fn load(util: f32) -> DaemonEvent {
+        DaemonEvent::SystemLoadUpdate { core_utils: vec![util], foreground_max_util: util }
+    }
+
+    fn mode(name: &str) -> DaemonEvent {
+        DaemonEvent::ModeChange { package_name: String::new(), pid: 0, mode: name.to_string(), temperature: 0.0 }
+    }
+
+    fn drain(rx: &Subscriber) -> Vec<DaemonEvent> {
+        std::iter::from_fn(|| rx.recv_timeout(Duration::ZERO).ok()).collect()
+    }
+
+    #[test]
+    fn control_lane_is_served_first() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe("test", &EventKind::ALL);
+        let tx = bus.publisher();
+        tx.send(frame(1)).unwrap();
+        tx.send(load(0.5)).unwrap();
+        tx.send(mode("fas")).unwrap();
+
+        let order: Vec<EventKind> = drain(&rx).iter().map(EventKind::of).collect();
+        assert_eq!(order, [EventKind::ModeChange, EventKind::SystemLoadUpdate, EventKind::FrameUpdate]);
+    }
+
+    #[test]
+    fn loads_coalesce_into_one_slot() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe("test", &[EventKind::SystemLoadUpdate]);
+        let tx = bus.publisher();
+        for util in [0.1, 0.2, 0.3] {
+            tx.send(load(util)).unwrap();
+        }
+
+        let events = drain(&rx);
+        assert!(matches!(events[..], [DaemonEvent::SystemLoadUpdate { foreground_max_util, .. }] if foreground_max_util == 0.3));
+        let stats = rx.queue.snapshot();
+        assert_eq!((stats.loads_coalesced, stats.delivered), (2, 1));
+    }
+
+    #[test]
+    fn full_frame_lane_drops_the_oldest() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe("test", &[EventKind::FrameUpdate]);
+        let tx = bus.publisher();
+        let sent = FRAME_QUEUE_CAPACITY as u64 + 5;
+        for n in 0..sent {
+            tx.send(frame(n)).unwrap();
+        }
+
+        let stats = rx.queue.snapshot();
+        assert_eq!((stats.frame_depth, stats.frame_depth_max, stats.frames_dropped), (FRAME_QUEUE_CAPACITY, FRAME_QUEUE_CAPACITY, 5));
+        let deltas: Vec<u64> = drain(&rx).into_iter().map(|e| match e {
+            DaemonEvent::FrameUpdate { frame_delta_ns, .. } => frame_delta_ns,
+            other => panic!("unexpected {:?}", other),
+        }).collect();
+        assert_eq!(deltas, (5..sent).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn subscribers_only_receive_their_kinds() {
+        let bus = EventBus::new();
+        let frames = bus.subscribe("frames", &[EventKind::FrameUpdate]);
+        let control = bus.subscribe("control", &[EventKind::ModeChange]);
+        let tx = bus.publisher();
+        tx.send(frame(1)).unwrap();
+        tx.send(mode("balance")).unwrap();
+
+        assert!(matches!(drain(&frames)[..], [DaemonEvent::FrameUpdate { .. }]));
+        assert!(matches!(drain(&control)[..], [DaemonEvent::ModeChange { .. }]));
+    }
+
+    #[test]
+    fn dropping_all_publishers_disconnects() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe("test", &EventKind::ALL);
+        let tx = bus.publisher();
+        tx.clone().send(mode("balance")).unwrap();
+        drop(tx);
+
+        // 已入队的事件仍然送达
+        assert!(rx.recv_timeout(Duration::ZERO).is_ok());
+        assert!(matches!(rx.recv_timeout(Duration::ZERO), Err(RecvTimeoutError::Disconnected)));
+    }
+}
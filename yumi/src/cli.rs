@@ -0,0 +1,559 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 命令行子命令
+//!
+//! 同一个二进制既是守护进程也是调试工具：
+//!
+//! ```text
+//! yumi [daemon] [workdir]        启动守护进程 (兼容旧的 `yumi <dir>` 用法)
+//!      [--sysfs-root <dir>]      把 /sys、/proc、/dev 重定向到假设备树
+//!      [--dry-run]               只记录将要进行的写入，不改动系统
+//! yumi status                    查询运行中实例的状态
+//! yumi set-mode <mode> [...]     立即切换模式，可带结束条件作为临时覆盖
+//! yumi clear-override            取消临时模式覆盖
+//! yumi reload                    强制重载 rules.yaml 与 config.yaml
+//! yumi validate <file>           离线校验配置文件 (语法、未知字段与语义检查)；
+//!                                device.yaml / user.yaml 叠加在同目录的下层上校验
+//! yumi dump-effective-config     输出合并各层、补全默认值后的实际生效配置
+//! yumi diff-config <base> <file> 输出 file 相对 base 的改动，作为 user.yaml 覆盖层
+//! yumi schema --config|--rules   输出配置文件的 JSON Schema
+//!      [--overlay]               device.yaml / user.yaml 覆盖层使用的 schema
+//! yumi restore                   把 yumi 写过的节点恢复为原值
+//! yumi replay <trace>            离线回放帧 trace，输出 FAS 决策时间线
+//! yumi simulate <scenario>       闭环仿真，比较不同 rules 下的 jank 率与平均频率
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config_check;
+use crate::control::{self, ControlRequest, ControlResponse};
+use crate::migrate::{self, ConfigKind};
+use crate::monitor::config::{self as rules_config, RulesConfig};
+use crate::replay::{self, OutputFormat, ReplayOptions};
+use crate::restore;
+use crate::schema;
+use crate::sim::{self, GovernorKind, ReportFormat, SimOptions};
+use crate::scheduler::config::{self as main_config, Config};
+use crate::status;
+
+const USAGE: &str = "\
+Usage: yumi [COMMAND]
+
+Commands:
+  daemon [workdir] [--sysfs-root <dir>] [--dry-run]
+                               Run the daemon (default when no command is given);
+                               --sysfs-root (or $YUMI_SYSFS_ROOT) redirects /sys,
+                               /proc and /dev to a fake device tree;
+                               --dry-run (or $YUMI_DRY_RUN=1) logs every write to
+                               /sys, /proc and /dev instead of performing it
+  status                       Show the state of the running daemon
+  set-mode <mode> [--for <duration>] [--until-unplugged] [--until-app-exit]
+                               Switch the running daemon to <mode>; with any of the
+                               options the mode overrides the rules until it ends
+  clear-override               Drop a temporary mode override
+  reload                       Force the running daemon to reload its config files
+  fas-state                    Dump the FAS controller state
+  clg-state                    Dump the CPU load governor state
+  validate [--rules|--config] <file>
+                               Check a config file without starting the daemon:
+                               syntax, unknown keys and device-aware sanity checks;
+                               device.yaml and user.yaml are checked on top of the
+                               layers below them
+  dump-effective-config [--rules|--config]
+                               Print the config as the daemon sees it: layers merged,
+                               defaults filled in
+  diff-config <base> <file>    Print the keys of <file> that differ from <base> as an
+                               overlay for user.yaml; keys missing from <file> are
+                               written as null
+  schema --config|--rules [--overlay] [<file>]
+                               Write the JSON Schema of config.yaml or rules.yaml
+                               (to stdout when no file is given); --overlay writes
+                               the schema for device.yaml and user.yaml, where every
+                               key is optional and null removes it
+  restore                      Restore every node yumi has written to its original value
+  replay <trace> [--rules <file>] [--freq-table <file>] [--format csv|jsonl]
+                               Drive the FAS controller from a recorded frame trace
+                               and print its decisions per frame
+  simulate <scenario> [--rules <file>]... [--governor fas|clg] [--format text|json]
+           [--max-jank <pct>]
+                               Run FAS / CLG against a synthetic workload and report
+                               jank rate against average frequency
+  help                         Show this message";
+
+#[derive(Debug)]
+pub enum Command {
+    Daemon { workdir: Option<String>, sysfs_root: Option<PathBuf>, dry_run: bool },
+    Status,
+    SetMode {
+        mode: String,
+        duration_secs: Option<u64>,
+        until_unplugged: bool,
+        until_app_exit: bool,
+    },
+    ClearOverride,
+    Reload,
+    FasState,
+    ClgState,
+    Validate { kind: Option<ConfigKind>, path: PathBuf },
+    DumpEffectiveConfig { kind: Option<ConfigKind> },
+    DiffConfig { base: PathBuf, path: PathBuf },
+    Schema { kind: ConfigKind, overlay: bool, out: Option<PathBuf> },
+    Restore,
+    Replay(ReplayOptions),
+    Simulate(SimOptions),
+    Help,
+}
+
+/// 解析命令行参数 (不含 argv[0])
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command> {
+    let mut args = args.into_iter();
+    let Some(first) = args.next() else {
+        return Ok(Command::Daemon { workdir: None, sysfs_root: None, dry_run: false });
+    };
+    let rest: Vec<String> = args.collect();
+
+    let cmd = match first.as_str() {
+        "daemon" => parse_daemon(&rest)?,
+        "status" => Command::Status,
+        "set-mode" => parse_set_mode(&rest)?,
+        "clear-override" => Command::ClearOverride,
+        "reload" => Command::Reload,
+        "fas-state" => Command::FasState,
+        "clg-state" => Command::ClgState,
+        "validate" => {
+            let (kind, positional) = parse_kind_flag(&rest)?;
+            match positional.first() {
+                Some(path) => Command::Validate { kind, path: PathBuf::from(path) },
+                None => bail!("validate: missing <file>\n\n{}", USAGE),
+            }
+        }
+        "dump-effective-config" => {
+            let (kind, _) = parse_kind_flag(&rest)?;
+            Command::DumpEffectiveConfig { kind }
+        }
+        "diff-config" => match rest.as_slice() {
+            [base, path] => Command::DiffConfig { base: PathBuf::from(base), path: PathBuf::from(path) },
+            _ => bail!("diff-config: expected <base> <file>\n\n{}", USAGE),
+        },
+        "schema" => {
+            let overlay = rest.iter().any(|a| a == "--overlay");
+            let rest: Vec<String> = rest.into_iter().filter(|a| a != "--overlay").collect();
+            let (kind, positional) = parse_kind_flag(&rest)?;
+            let Some(kind) = kind else { bail!("schema: --config or --rules is required\n\n{}", USAGE) };
+            if overlay && kind == ConfigKind::Rules {
+                bail!("schema: --overlay only applies to --config");
+            }
+            Command::Schema { kind, overlay, out: positional.first().map(PathBuf::from) }
+        }
+        "restore" => Command::Restore,
+        "replay" => parse_replay(&rest)?,
+        "simulate" => parse_simulate(&rest)?,
+        "help" | "-h" | "--help" => Command::Help,
+        // 旧版 service.sh 以 `yumi <工作目录>` 启动
+        other if Path::new(other).is_dir() => Command::Daemon { workdir: Some(other.to_string()), sysfs_root: None, dry_run: false },
+        other => bail!("unknown command '{}'\n\n{}", other, USAGE),
+    };
+    Ok(cmd)
+}
+
+fn parse_daemon(args: &[String]) -> Result<Command> {
+    let mut workdir = None;
+    let mut sysfs_root = None;
+    let mut dry_run = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sysfs-root" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--sysfs-root: missing <dir>"))?;
+                sysfs_root = Some(PathBuf::from(value));
+            }
+            "--dry-run" => dry_run = true,
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ if workdir.is_none() => workdir = Some(arg.clone()),
+            _ => bail!("daemon: unexpected argument '{}'", arg),
+        }
+    }
+    Ok(Command::Daemon { workdir, sysfs_root, dry_run })
+}
+
+fn parse_replay(args: &[String]) -> Result<Command> {
+    let mut trace = None;
+    let mut rules = None;
+    let mut freq_table = None;
+    let mut format = OutputFormat::Csv;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--rules: missing <file>"))?;
+                rules = Some(PathBuf::from(value));
+            }
+            "--freq-table" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--freq-table: missing <file>"))?;
+                freq_table = Some(PathBuf::from(value));
+            }
+            "--format" => {
+                format = match iter.next().map(String::as_str) {
+                    Some("csv") => OutputFormat::Csv,
+                    Some("jsonl") => OutputFormat::Jsonl,
+                    other => bail!("--format: expected csv or jsonl, got {:?}", other.unwrap_or("")),
+                };
+            }
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ if trace.is_none() => trace = Some(PathBuf::from(arg)),
+            _ => bail!("replay: unexpected argument '{}'", arg),
+        }
+    }
+
+    match trace {
+        Some(trace) => Ok(Command::Replay(ReplayOptions { trace, rules, freq_table, format })),
+        None => bail!("replay: missing <trace>\n\n{}", USAGE),
+    }
+}
+
+fn parse_simulate(args: &[String]) -> Result<Command> {
+    let mut scenario = None;
+    let mut rules = Vec::new();
+    let mut governors = Vec::new();
+    let mut format = ReportFormat::Text;
+    let mut max_jank_pct = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--rules: missing <file>"))?;
+                rules.push(PathBuf::from(value));
+            }
+            "--governor" => {
+                let kind = match iter.next().map(String::as_str) {
+                    Some("fas") => GovernorKind::Fas,
+                    Some("clg") => GovernorKind::Clg,
+                    other => bail!("--governor: expected fas or clg, got {:?}", other.unwrap_or("")),
+                };
+                if !governors.contains(&kind) {
+                    governors.push(kind);
+                }
+            }
+            "--format" => {
+                format = match iter.next().map(String::as_str) {
+                    Some("text") => ReportFormat::Text,
+                    Some("json") => ReportFormat::Json,
+                    other => bail!("--format: expected text or json, got {:?}", other.unwrap_or("")),
+                };
+            }
+            "--max-jank" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--max-jank: missing <pct>"))?;
+                let pct: f64 = value.trim_end_matches('%').parse()
+                    .map_err(|_| anyhow!("--max-jank: invalid percentage '{}'", value))?;
+                max_jank_pct = Some(pct);
+            }
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ if scenario.is_none() => scenario = Some(PathBuf::from(arg)),
+            _ => bail!("simulate: unexpected argument '{}'", arg),
+        }
+    }
+
+    if governors.is_empty() {
+        governors = vec![GovernorKind::Fas, GovernorKind::Clg];
+    }
+    match scenario {
+        Some(scenario) => Ok(Command::Simulate(SimOptions { scenario, rules, governors, format, max_jank_pct })),
+        None => bail!("simulate: missing <scenario>\n\n{}", USAGE),
+    }
+}
+
+fn parse_set_mode(args: &[String]) -> Result<Command> {
+    let mut mode = None;
+    let mut duration_secs = None;
+    let mut until_unplugged = false;
+    let mut until_app_exit = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--for" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--for: missing <duration>"))?;
+                duration_secs = Some(parse_duration_secs(value)?);
+            }
+            "--until-unplugged" => until_unplugged = true,
+            "--until-app-exit" => until_app_exit = true,
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ if mode.is_none() => mode = Some(arg.clone()),
+            _ => bail!("set-mode: unexpected argument '{}'", arg),
+        }
+    }
+
+    match mode {
+        Some(mode) => Ok(Command::SetMode { mode, duration_secs, until_unplugged, until_app_exit }),
+        None => bail!("set-mode: missing <mode>\n\n{}", USAGE),
+    }
+}
+
+/// 解析 `90`、`90s`、`15m`、`2h` 形式的时长
+fn parse_duration_secs(value: &str) -> Result<u64> {
+    let (digits, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+    let amount: u64 = digits.parse().map_err(|_| anyhow!("invalid duration '{}'", value))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => bail!("invalid duration '{}', expected a number with s/m/h suffix", value),
+    };
+    amount.checked_mul(multiplier).ok_or_else(|| anyhow!("duration '{}' is out of range", value))
+}
+
+fn parse_kind_flag(args: &[String]) -> Result<(Option<ConfigKind>, Vec<String>)> {
+    let mut kind = None;
+    let mut positional = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--rules" => kind = Some(ConfigKind::Rules),
+            "--config" => kind = Some(ConfigKind::Main),
+            flag if flag.starts_with("--") => bail!("unknown option '{}'", flag),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    Ok((kind, positional))
+}
+
+/// 执行非 daemon 子命令，返回进程退出码
+pub fn run(cmd: Command) -> i32 {
+    let result = match cmd {
+        Command::Daemon { .. } => unreachable!("daemon is started by main"),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Command::Status => status(),
+        Command::SetMode { mode, duration_secs, until_unplugged, until_app_exit } => {
+            request_and_print(&ControlRequest::SetMode { mode, duration_secs, until_unplugged, until_app_exit })
+        }
+        Command::ClearOverride => request_and_print(&ControlRequest::ClearOverride),
+        Command::Reload => request_and_print(&ControlRequest::Reload),
+        Command::FasState => request_and_print(&ControlRequest::FasState),
+        Command::ClgState => request_and_print(&ControlRequest::ClgState),
+        Command::Validate { kind, path } => validate(kind, &path),
+        Command::DumpEffectiveConfig { kind } => dump_effective_config(kind),
+        Command::DiffConfig { base, path } => diff_config(&base, &path),
+        Command::Schema { kind, overlay, out } => write_schema(kind, overlay, out.as_deref()),
+        Command::Restore => restore(),
+        Command::Replay(opts) => replay::run(&opts),
+        Command::Simulate(opts) => sim::run(&opts),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            1
+        }
+    }
+}
+
+fn request_and_print(request: &ControlRequest) -> Result<()> {
+    let response = control::send_request(request)?;
+    print_response(response)
+}
+
+fn print_response(response: ControlResponse) -> Result<()> {
+    if !response.ok {
+        bail!("{}", response.error.unwrap_or_else(|| "request failed".to_string()));
+    }
+    if let Some(data) = response.data {
+        println!("{}", serde_json::to_string_pretty(&data)?);
+    }
+    Ok(())
+}
+
+/// 守护进程不在线时退回到读最后一次发布的 state.json
+fn status() -> Result<()> {
+    match control::send_request(&ControlRequest::Status) {
+        Ok(response) => print_response(response),
+        Err(e) => {
+            eprintln!("warning: {:#}", e);
+            let state_path = status::get_state_path();
+            let last_state = fs::read_to_string(&state_path)
+                .map_err(|err| anyhow!("{}: {}", state_path.display(), err))?;
+            let last_state: serde_json::Value = serde_json::from_str(&last_state)?;
+            let data = serde_json::json!({
+                "running": false,
+                "last_state": last_state,
+            });
+            println!("{}", serde_json::to_string_pretty(&data)?);
+            Ok(())
+        }
+    }
+}
+
+/// 守护进程在线时由它释放控制器后恢复；否则直接按 restore.json 恢复
+fn restore() -> Result<()> {
+    match control::send_request(&ControlRequest::Restore) {
+        Ok(response) => print_response(response),
+        Err(e) => {
+            eprintln!("warning: {:#}, restoring from journal", e);
+            let report = restore::restore_offline()?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+    }
+}
+
+/// 未指定类型时按文件名猜测：包含 "rules" 的视为 rules.yaml
+fn guess_kind(path: &Path) -> ConfigKind {
+    let is_rules = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.contains("rules"));
+    if is_rules { ConfigKind::Rules } else { ConfigKind::Main }
+}
+
+/// 语法、未知字段与语义检查；有 error 时以非零状态退出，warning 只打印
+fn validate(kind: Option<ConfigKind>, path: &Path) -> Result<()> {
+    let kind = kind.unwrap_or_else(|| guess_kind(path));
+    let report = match kind {
+        ConfigKind::Main => config_check::check_config_layers(&layers_for(path)).1,
+        ConfigKind::Rules => config_check::check_rules_file(path, &config_check::running_modes()).1,
+    };
+
+    for d in &report.diagnostics {
+        println!("{}", report.format(d));
+    }
+    if kind == ConfigKind::Main && !report.device_checked {
+        println!("{}: note: no cpufreq policies found, device checks skipped", path.display());
+    }
+    if report.has_errors() {
+        bail!("{}: {} error(s), {} warning(s)", path.display(), report.errors(), report.warnings());
+    }
+    println!("{}: OK ({} warning(s))", path.display(), report.warnings());
+    Ok(())
+}
+
+/// 覆盖层 (device.yaml / user.yaml) 不是完整的配置，与同目录下优先级更低的层一起校验；
+/// 同目录没有基础层时使用模块的配置目录
+fn layers_for(path: &Path) -> Vec<PathBuf> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let Some(rank) = main_config::LAYER_FILES.iter().skip(1).position(|&f| f == name).map(|i| i + 1) else {
+        return vec![path.to_path_buf()];
+    };
+    let dir = path.parent().filter(|d| d.join(main_config::LAYER_FILES[0]).exists())
+        .map_or_else(main_config::get_config_dir, Path::to_path_buf);
+    let mut layers: Vec<PathBuf> = main_config::layer_paths(&dir).into_iter()
+        .filter(|p| main_config::LAYER_FILES[..rank].iter().any(|f| p.file_name().is_some_and(|n| n == *f)))
+        .collect();
+    layers.push(path.to_path_buf());
+    layers
+}
+
+/// 与守护进程的加载逻辑保持一致：解析失败时退回默认值 (并在 stderr 提示)
+fn dump_effective_config(kind: Option<ConfigKind>) -> Result<()> {
+    if kind != Some(ConfigKind::Rules) {
+        let layers = main_config::layer_paths(&main_config::get_config_dir());
+        let mut config = Config::from_layers(&layers).unwrap_or_else(|e| {
+            eprintln!("warning: {:#}, using defaults", e);
+            Config::default()
+        });
+        for name in config.mode_defs.keys().filter(|name| !config.custom_modes.contains_key(*name)) {
+            eprintln!("warning: modes.{} cannot be resolved and is left out, see `yumi validate`", name);
+        }
+        // "auto" 的 CoreFramework 档位显示为本机识别出的 policy
+        config.core_framework = config.core_framework.resolved();
+        println!("---");
+        for path in &layers {
+            println!("# {}", path.display());
+        }
+        print!("{}", serde_yaml::to_string(&config)?);
+    }
+
+    if kind != Some(ConfigKind::Main) {
+        let rules_path = rules_config::get_rules_path();
+        let rules = migrate::read(ConfigKind::Rules, &rules_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| serde_yaml::from_str::<RulesConfig>(&s).map_err(anyhow::Error::from))
+            .unwrap_or_else(|e| {
+                eprintln!("warning: {}: {}, using defaults", rules_path.display(), e);
+                RulesConfig::default()
+            });
+        print_yaml(&rules_path, &rules)?;
+    }
+    Ok(())
+}
+
+/// 安装脚本用它把旧版直接改过的 config.yaml 转换为 user.yaml；两份文件先在内存中迁移到当前版本，
+/// 没有差异时不输出任何内容
+fn diff_config(base: &Path, path: &Path) -> Result<()> {
+    let load = |path: &Path| -> Result<serde_yaml::Value> {
+        let content = migrate::read(ConfigKind::Main, path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        // 版本号由 yumi 维护，不属于用户的改动
+        if let serde_yaml::Value::Mapping(map) = &mut value {
+            map.remove(migrate::VERSION_KEY);
+        }
+        Ok(value)
+    };
+    if let Some(diff) = main_config::diff_values(&load(base)?, &load(path)?) {
+        print!("{}", serde_yaml::to_string(&diff)?);
+    }
+    Ok(())
+}
+
+fn write_schema(kind: ConfigKind, overlay: bool, out: Option<&Path>) -> Result<()> {
+    let schema = match kind {
+        ConfigKind::Main if overlay => schema::config_overlay_schema(),
+        ConfigKind::Main => schema::config_schema(),
+        ConfigKind::Rules => schema::rules_schema(),
+    };
+    let json = serde_json::to_string_pretty(&schema)? + "\n";
+    match out {
+        Some(path) => fs::write(path, json).map_err(|e| anyhow!("{}: {}", path.display(), e)),
+        None => {
+            print!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+fn print_yaml<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    println!("---\n# {}", path.display());
+    print!("{}", serde_yaml::to_string(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration_secs("90").unwrap(), 90);
+        assert_eq!(parse_duration_secs("15m").unwrap(), 900);
+        assert_eq!(parse_duration_secs("2h").unwrap(), 7200);
+        assert!(parse_duration_secs("5d").is_err());
+        assert!(parse_duration_secs("-5m").is_err());
+        assert!(parse_duration_secs("99999999999999999999s").is_err());
+        assert!(parse_duration_secs(&format!("{}h", u64::MAX / 3600 + 1)).is_err());
+    }
+}
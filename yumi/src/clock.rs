@@ -0,0 +1,63 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 可切换的单调时钟
+//!
+//! 守护进程里等同于 `Instant::now()`。离线回放 / 仿真时切到虚拟时钟，
+//! 时间只随 trace 时间戳推进，FAS 的冷启动窗口、频率校验间隔等
+//! 与真实运行时完全一致，且结果可复现。
+//!
+//! 虚拟时钟按线程生效：只有调用了 [`use_virtual`] 的线程看到虚拟时间，
+//! 同一进程中的其他线程 (包括并行运行的测试) 不受影响。
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// 当前线程的虚拟时间 (相对零点的纳秒数)；None 表示使用真实时钟
+    static VIRTUAL_NS: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+lazy_static::lazy_static! {
+    /// 虚拟时钟的零点
+    static ref EPOCH: Instant = Instant::now();
+}
+
+pub fn now() -> Instant {
+    match VIRTUAL_NS.with(Cell::get) {
+        Some(ns) => *EPOCH + Duration::from_nanos(ns),
+        None => Instant::now(),
+    }
+}
+
+pub fn elapsed(since: Instant) -> Duration {
+    now().saturating_duration_since(since)
+}
+
+/// 把当前线程切换到虚拟时钟，时间从 0 开始
+pub fn use_virtual() {
+    VIRTUAL_NS.with(|v| v.set(Some(0)));
+}
+
+/// 把虚拟时钟推进到 `ns` (相对零点)；时间不会倒退，未切换到虚拟时钟时无效果
+pub fn advance_to(ns: u64) {
+    VIRTUAL_NS.with(|v| {
+        if let Some(cur) = v.get() {
+            v.set(Some(cur.max(ns)));
+        }
+    });
+}
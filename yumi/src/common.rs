@@ -16,8 +16,10 @@
  */
 
 use crate::monitor::config::RulesConfig;
+use crate::control::{ControlRequest, ControlResponse};
 use std::path::PathBuf;
 use std::env;
+use std::sync::mpsc::Sender;
 
 /// 守护进程全局事件总线
 #[derive(Debug, Clone)]
@@ -43,6 +45,18 @@ pub enum DaemonEvent {
     },
 
     ConfigReload(RulesConfig),
+
+    /// eBPF 数据源上线 / 下线 (由 monitor::supervisor 发出)
+    SourceHealth {
+        source: String,
+        healthy: bool,
+    },
+
+    /// 控制 socket 请求，scheduler 处理完成后通过 reply 回复
+    Control {
+        request: ControlRequest,
+        reply: Sender<ControlResponse>,
+    },
 }
 
 /// 获取模块根目录的绝对路径
@@ -0,0 +1,945 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! config.yaml / rules.yaml 严格校验
+//!
+//! 与守护进程的宽松加载 (解析失败退回默认值) 不同，这里把所有问题收集成带
+//! 行列号的诊断：
+//!
+//! - 语法 / 类型错误 (error)
+//! - 未知字段 (warning，多半是拼写错误，serde 会静默忽略)
+//! - 语义检查：perf_floor <= perf_ceil、CoreFramework 的 policy 是否存在、
+//!   频率是否在 scaling_available_frequencies 中、cpuset 是否超出设备的 CPU、
+//!   app_modes / global_mode 引用的模式是否存在
+//!
+//! 依赖设备节点的检查只在能读到 cpufreq 时进行。启动、重载与 `yumi validate`
+//! 共用这里的实现。
+//!
+//! 分层的 config.yaml (基础层 + 设备层 + 用户层) 逐层检查语法，合并后再做类型与语义检查，
+//! 诊断定位到写出该键的优先级最高的一层。
+
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use log::{error, warn};
+
+use crate::migrate::{self, ConfigKind};
+use crate::monitor::config::RulesConfig;
+use crate::scheduler::config::{self, Config, Mode, TIER_NAMES};
+use crate::scheduler::freq_expr::FreqExpr;
+use crate::scheduler::topology;
+use crate::status;
+use crate::utils::host_path;
+
+const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+
+lazy_static::lazy_static! {
+    /// 守护进程正在使用的 (最近一次成功加载的) config.yaml 中的模式名，由 scheduler 更新
+    static ref RUNNING_MODES: RwLock<Option<Vec<String>>> = RwLock::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 诊断所在的文件；None 时为 Report 的路径 (分层配置的诊断可能落在覆盖层)
+    pub path: Option<PathBuf>,
+    /// 1 起始；定位不到时为 None
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/// 单个文件的校验结果
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+    /// 是否进行了依赖设备节点的检查
+    pub device_checked: bool,
+}
+
+impl Report {
+    fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), diagnostics: Vec::new(), device_checked: false }
+    }
+
+    pub fn errors(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors() > 0
+    }
+
+    /// 逐条写入守护进程日志
+    pub fn log(&self) {
+        for d in &self.diagnostics {
+            match d.severity {
+                Severity::Error => error!("[Config] {}", self.format(d)),
+                Severity::Warning => warn!("[Config] {}", self.format(d)),
+            }
+        }
+    }
+
+    /// `path:line:col: severity: message`
+    pub fn format(&self, d: &Diagnostic) -> String {
+        let mut location = d.path.as_deref().unwrap_or(&self.path).display().to_string();
+        if let Some(line) = d.line {
+            location.push_str(&format!(":{}", line));
+            if let Some(column) = d.column {
+                location.push_str(&format!(":{}", column));
+            }
+        }
+        format!("{}: {}: {}", location, d.severity, d.message)
+    }
+
+    /// 第一条错误，用于一行式的失败原因
+    pub fn first_error(&self) -> Option<String> {
+        self.diagnostics.iter().find(|d| d.severity == Severity::Error).map(|d| self.format(d))
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// 参与校验的一个文件；分层配置的每一层各一个
+struct Source<'a> {
+    /// 单文件校验时为 None，诊断沿用 Report 的路径
+    path: Option<&'a Path>,
+    content: &'a str,
+}
+
+/// 收集诊断，语义检查按 YAML 键路径定位行列
+struct Checker<'a> {
+    sources: Vec<Source<'a>>,
+    report: Report,
+}
+
+impl<'a> Checker<'a> {
+    /// 定位到写出键路径最深的那一层；深度相同时取优先级高的层 (实际生效的值)
+    fn push(&mut self, severity: Severity, key_path: &[Seg], message: String) {
+        let found = self.sources.iter()
+            .filter_map(|s| match locate_prefix(s.content, key_path) {
+                (Some(location), depth) => Some((s.path, location, depth)),
+                (None, _) => None,
+            })
+            .max_by_key(|&(_, _, depth)| depth);
+        let (path, line, column) = match found {
+            Some((path, (l, c), _)) => (path.map(Path::to_path_buf), Some(l), Some(c)),
+            None => (None, None, None),
+        };
+        self.report.diagnostics.push(Diagnostic { severity, path, line, column, message });
+    }
+
+    fn error(&mut self, key_path: &[Seg], message: String) {
+        self.push(Severity::Error, key_path, message);
+    }
+
+    fn warning(&mut self, key_path: &[Seg], message: String) {
+        self.push(Severity::Warning, key_path, message);
+    }
+
+    /// 只写了旧格式的键时定位到它；否则使用新格式的键 (两种都写时新格式生效)
+    fn key_path(&self, legacy: &[&str], current: &[&str]) -> Vec<Seg> {
+        if self.present(legacy) && !self.present(current) { keys(legacy) } else { keys(current) }
+    }
+
+    /// 文件 (任意一层) 中是否写出了这个键
+    fn present(&self, path: &[&str]) -> bool {
+        self.written(&keys(path))
+    }
+
+    /// 同 [`Checker::present`]，参数为已拆分的键路径
+    fn written(&self, segs: &[Seg]) -> bool {
+        self.sources.iter().any(|s| locate_prefix(s.content, segs).1 == segs.len())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  入口
+// ════════════════════════════════════════════════════════════════
+
+/// 校验 config.yaml；能解析时同时返回配置 (即使有语义错误)
+pub fn check_config_file(path: &Path) -> (Option<Config>, Report) {
+    let Some(content) = read(path) else { return (None, unreadable(path)) };
+    check_upgraded(path, &content, ConfigKind::Main, check_config)
+}
+
+/// 校验 rules.yaml；`modes` 为可以引用的模式名 (见 [`known_modes`])
+pub fn check_rules_file(path: &Path, modes: &[String]) -> (Option<RulesConfig>, Report) {
+    let Some(content) = read(path) else { return (None, unreadable(path)) };
+    check_upgraded(path, &content, ConfigKind::Rules, |path, content| check_rules(path, content, modes))
+}
+
+/// 校验分层配置 (见 [`config::layer_paths`])；只有基础层时与 [`check_config_file`] 相同
+pub fn check_config_layers(paths: &[PathBuf]) -> (Option<Config>, Report) {
+    let base = match paths {
+        [] => return (None, unreadable(&config::get_config_dir().join(config::LAYER_FILES[0]))),
+        [single] => return check_config_file(single),
+        [base, ..] => base,
+    };
+
+    let mut contents = Vec::new();
+    let mut notes = Vec::new();
+    for path in paths {
+        let Some(content) = read(path) else { return (None, unreadable(path)) };
+        let (content, note) = migrated(ConfigKind::Main, &content);
+        if let Some(message) = note {
+            notes.push(Diagnostic { severity: Severity::Warning, path: Some(path.clone()), line: None, column: None, message });
+        }
+        contents.push(content);
+    }
+
+    let sources = paths.iter().zip(&contents)
+        .map(|(path, content)| Source { path: Some(path.as_path()), content })
+        .collect();
+    let (config, checker) = parse_layers::<Config>(base, sources);
+    let (config, mut report) = check_parsed_config(config, checker);
+    report.diagnostics.splice(0..0, notes);
+    (config, report)
+}
+
+/// 旧版本的文件按迁移后的内容校验 (与守护进程加载时一致)
+fn check_upgraded<T>(
+    path: &Path,
+    content: &str,
+    kind: ConfigKind,
+    check: impl FnOnce(&Path, &str) -> (Option<T>, Report),
+) -> (Option<T>, Report) {
+    let (content, note) = migrated(kind, content);
+    let (value, mut report) = check(path, &content);
+    if let Some(message) = note {
+        report.diagnostics.insert(0, Diagnostic { severity: Severity::Warning, path: None, line: None, column: None, message });
+    }
+    (value, report)
+}
+
+/// 实际参与校验的内容与需要提示的迁移信息；只改版本号时沿用原文，行列号与磁盘上一致
+fn migrated(kind: ConfigKind, content: &str) -> (String, Option<String>) {
+    match migrate::upgrade(kind, content) {
+        Ok(upgraded) if upgraded.changed => {
+            let note = format!(
+                "schema_version {} is outdated, checked as migrated to {} ({}); line numbers refer to the migrated content, \
+                 the daemon rewrites the file on its next start",
+                upgraded.from, upgraded.to, upgraded.steps.join("; "));
+            (upgraded.content, Some(note))
+        }
+        Ok(_) => (content.to_string(), None),
+        Err(e) => (content.to_string(), Some(e.to_string())),
+    }
+}
+
+pub fn check_config(path: &Path, content: &str) -> (Option<Config>, Report) {
+    let (config, checker) = parse::<Config>(path, content);
+    check_parsed_config(config, checker)
+}
+
+fn check_parsed_config(mut config: Option<Config>, mut checker: Checker) -> (Option<Config>, Report) {
+    if let Some(config) = &mut config {
+        check_custom_modes(&mut checker, config);
+    }
+    if let Some(config) = &config {
+        let device = Device::probe();
+        checker.report.device_checked = device.is_some();
+        check_config_semantics(&mut checker, config, device.as_ref());
+    }
+    (config, checker.report)
+}
+
+pub fn check_rules(path: &Path, content: &str, modes: &[String]) -> (Option<RulesConfig>, Report) {
+    let (rules, mut checker) = parse::<RulesConfig>(path, content);
+    if let Some(rules) = &rules {
+        check_rules_semantics(&mut checker, rules, modes);
+    }
+    (rules, checker.report)
+}
+
+/// 启动与重载用：诊断写入日志；有 error 时拒绝这份配置 (调用方保留上一份)，
+/// 原因同步到 state.json 的 `config_errors`，成功加载后清除
+pub fn load_config_checked(paths: &[PathBuf]) -> Result<Config, String> {
+    accept_config_layers(check_config_layers(paths))
+}
+
+/// 与 [`load_config_checked`] 相同，但使用已完成的校验结果
+/// (启动时日志初始化之前就需要从中取得语言与日志级别)
+pub fn accept_config_layers(checked: (Option<Config>, Report)) -> Result<Config, String> {
+    let path = checked.1.path.clone();
+    accept(&path, checked)
+}
+
+/// 模式名按守护进程正在使用的配置检查 ([`running_modes`])，而不是磁盘上可能尚未生效的版本
+pub fn load_rules_checked(path: &Path) -> Result<RulesConfig, String> {
+    accept(path, check_rules_file(path, &running_modes()))
+}
+
+fn accept<T>(path: &Path, (value, report): (Option<T>, Report)) -> Result<T, String> {
+    report.log();
+    let file = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+    match value {
+        Some(value) if !report.has_errors() => {
+            status::set_config_error(&file, None);
+            Ok(value)
+        }
+        _ => {
+            let error = report.first_error().unwrap_or_else(|| format!("{}: invalid config", path.display()));
+            status::set_config_error(&file, Some(error.clone()));
+            Err(error)
+        }
+    }
+}
+
+/// rules.yaml 中可以引用的模式名：内置模式、fas 与 `config` (合并各层后) 中的自定义模式
+pub fn known_modes(config: &Config) -> Vec<String> {
+    let mut modes: Vec<String> = config.mode_names().into_iter().map(str::to_string).collect();
+    modes.push("fas".to_string());
+    modes
+}
+
+/// scheduler 采用一份新配置 (启动或重载成功) 时调用
+pub fn set_running_config(config: &Config) {
+    *RUNNING_MODES.write().unwrap() = Some(known_modes(config));
+}
+
+/// 守护进程正在使用的模式名；scheduler 尚未加载配置时按磁盘上合并后的各层计算
+pub fn running_modes() -> Vec<String> {
+    if let Some(modes) = RUNNING_MODES.read().unwrap().as_ref() {
+        return modes.clone();
+    }
+    known_modes(&Config::load().unwrap_or_default())
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+fn unreadable(path: &Path) -> Report {
+    let mut report = Report::new(path);
+    let reason = fs::metadata(path).err().map_or_else(|| "cannot read file".to_string(), |e| e.to_string());
+    report.diagnostics.push(Diagnostic { severity: Severity::Error, path: None, line: None, column: None, message: reason });
+    report
+}
+
+/// 反序列化并记录语法错误与未知字段
+fn parse<'a, T: DeserializeOwned>(path: &Path, content: &'a str) -> (Option<T>, Checker<'a>) {
+    let mut checker = Checker { sources: vec![Source { path: None, content }], report: Report::new(path) };
+    let mut ignored: Vec<Vec<Seg>> = Vec::new();
+
+    let result: Result<T, serde_yaml::Error> = serde_ignored::deserialize(
+        serde_yaml::Deserializer::from_str(content),
+        |p| {
+            let mut segs = Vec::new();
+            collect_segments(&p, &mut segs);
+            ignored.push(segs);
+        },
+    );
+
+    match result {
+        Ok(value) => {
+            for segs in ignored {
+                let message = format!("unknown key '{}' is ignored", display_path(&segs));
+                checker.warning(&segs, message);
+            }
+            (Some(value), checker)
+        }
+        Err(e) => {
+            checker.report.diagnostics.push(error_diagnostic(&e, None));
+            (None, checker)
+        }
+    }
+}
+
+/// 分层配置：逐层检查语法后深度合并，再反序列化合并结果
+fn parse_layers<'a, T: DeserializeOwned>(path: &Path, sources: Vec<Source<'a>>) -> (Option<T>, Checker<'a>) {
+    let mut checker = Checker { sources, report: Report::new(path) };
+    let parsed: Result<Vec<serde_yaml::Value>, Diagnostic> = checker.sources.iter()
+        .map(|s| serde_yaml::from_str(s.content).map_err(|e| error_diagnostic(&e, s.path)))
+        .collect();
+    let layers = match parsed {
+        Ok(layers) => layers,
+        Err(d) => {
+            checker.report.diagnostics.push(d);
+            return (None, checker);
+        }
+    };
+
+    let mut ignored: Vec<Vec<Seg>> = Vec::new();
+    let result: Result<T, serde_yaml::Error> = serde_ignored::deserialize(
+        config::merge_values(layers.iter().cloned()),
+        |p| {
+            let mut segs = Vec::new();
+            collect_segments(&p, &mut segs);
+            ignored.push(segs);
+        },
+    );
+
+    match result {
+        Ok(value) => {
+            for segs in ignored {
+                let message = format!("unknown key '{}' is ignored", display_path(&segs));
+                checker.warning(&segs, message);
+            }
+            (Some(value), checker)
+        }
+        Err(e) => {
+            // 合并后的值没有行列信息：逐层累加，找出第一个引入错误的层
+            let culprit = (1..=layers.len())
+                .find(|&n| serde_yaml::from_value::<T>(config::merge_values(layers[..n].iter().cloned())).is_err())
+                .map_or(layers.len() - 1, |n| n - 1);
+            let source = &checker.sources[culprit];
+            // 按文本单独解析这一层取得行列号 (以及键路径)；覆盖层单独解析时错误相同才采用
+            let message = e.to_string();
+            let diagnostic = match serde_yaml::from_str::<T>(source.content).map_err(|e| error_diagnostic(&e, source.path)) {
+                Err(d) if culprit == 0 || d.message.ends_with(&message) => d,
+                _ => Diagnostic {
+                    severity: Severity::Error,
+                    path: source.path.map(Path::to_path_buf),
+                    line: None,
+                    column: None,
+                    message,
+                },
+            };
+            checker.report.diagnostics.push(diagnostic);
+            (None, checker)
+        }
+    }
+}
+
+fn error_diagnostic(e: &serde_yaml::Error, path: Option<&Path>) -> Diagnostic {
+    let location = e.location();
+    // serde_yaml 的错误信息已经带有 "at line X column Y"，行列单独给出
+    let message = e.to_string();
+    let message = match message.find(" at line ") {
+        Some(idx) if location.is_some() => message[..idx].to_string(),
+        _ => message,
+    };
+    Diagnostic {
+        severity: Severity::Error,
+        path: path.map(Path::to_path_buf),
+        line: location.as_ref().map(|l| l.line()),
+        column: location.as_ref().map(|l| l.column()),
+        message,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  语义检查
+// ════════════════════════════════════════════════════════════════
+
+/// 设备上的 cpufreq 信息
+struct Device {
+    /// policy id -> scaling_available_frequencies (升序，可能为空)
+    policies: HashMap<i32, Vec<u32>>,
+    /// /sys/devices/system/cpu/possible；读不到时为 None
+    possible: Option<Vec<usize>>,
+    /// /sys/devices/system/cpu/online；读不到时为 None
+    online: Option<Vec<usize>>,
+}
+
+/// cpuset 检查用的 CPU 集合
+#[derive(Clone, Copy)]
+struct CpuSets<'a> {
+    possible: Option<&'a [usize]>,
+    online: Option<&'a [usize]>,
+}
+
+impl Device {
+    fn probe() -> Option<Self> {
+        let entries = fs::read_dir(host_path(CPUFREQ_DIR)).ok()?;
+        let mut policies = HashMap::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(id) = name.to_str().and_then(|n| n.strip_prefix("policy")).and_then(|n| n.parse::<i32>().ok()) else {
+                continue;
+            };
+            policies.insert(id, topology::available_freqs(id));
+        }
+        if policies.is_empty() {
+            return None;
+        }
+        let cpu_list = |node: &str| fs::read_to_string(host_path(&format!("/sys/devices/system/cpu/{}", node)))
+            .ok()
+            .and_then(|s| parse_cpu_list(s.trim()).ok());
+        Some(Self { policies, possible: cpu_list("possible"), online: cpu_list("online") })
+    }
+}
+
+/// 展开自定义模式的继承；无法展开的模式报 error，自己写出的未知键报 warning
+fn check_custom_modes(c: &mut Checker, config: &mut Config) {
+    for (name, e) in config.resolve_modes() {
+        c.error(&keys(&["modes", &name]), format!("modes.{}: {}", name, e));
+    }
+    let names: Vec<String> = config.custom_modes.keys().cloned().collect();
+    for name in names {
+        let Ok(value) = config.mode_value(&name) else { continue };
+        let mut ignored: Vec<Vec<Seg>> = Vec::new();
+        let _: Result<Mode, _> = serde_ignored::deserialize(value, |p| {
+            let mut segs = vec![Seg::Key("modes".into()), Seg::Key(name.clone())];
+            collect_segments(&p, &mut segs);
+            ignored.push(segs);
+        });
+        for segs in ignored {
+            // 继承来的未知键已在父模式处报告
+            if c.written(&segs) {
+                let message = format!("unknown key '{}' is ignored", display_path(&segs));
+                c.warning(&segs, message);
+            }
+        }
+    }
+}
+
+fn check_config_semantics(c: &mut Checker, config: &Config, device: Option<&Device>) {
+    let framework = &config.core_framework;
+    let policy_missing = |policy: i32| policy >= 0 && device.is_some_and(|d| !d.policies.contains_key(&policy));
+
+    for (tier, policy) in TIER_NAMES.iter().zip(framework.legacy_paths()) {
+        let Some(policy) = policy else { continue };
+        let key = format!("{}Path", tier);
+        if policy < -1 {
+            c.error(&keys(&["CoreFramework", &key]), format!("CoreFramework.{} must be a policy id or -1, got {}", key, policy));
+        } else if policy_missing(policy) {
+            c.error(&keys(&["CoreFramework", &key]),
+                format!("CoreFramework.{}: {}/policy{} does not exist", key, CPUFREQ_DIR, policy));
+        }
+    }
+    if !framework.clusters.is_empty() && framework.legacy_paths().iter().any(Option::is_some) {
+        c.warning(&keys(&["CoreFramework", "Clusters"]),
+            "CoreFramework: both Clusters and *CorePath are set, *CorePath is ignored".to_string());
+    }
+    for (idx, def) in framework.clusters.iter().enumerate() {
+        let path = [Seg::Key("CoreFramework".into()), Seg::Key("Clusters".into()), Seg::Index(idx)];
+        if def.policy < 0 {
+            c.error(&path, format!("CoreFramework.Clusters[{}]: policy must be >= 0, got {}", idx, def.policy));
+        } else if policy_missing(def.policy) {
+            c.error(&path, format!("CoreFramework.Clusters[{}]: {}/policy{} does not exist", idx, CPUFREQ_DIR, def.policy));
+        }
+        if !def.name.is_empty() && framework.clusters[..idx].iter().any(|other| other.name == def.name) {
+            c.error(&path, format!("CoreFramework.Clusters[{}]: duplicate cluster name '{}'", idx, def.name));
+        }
+    }
+
+    // 自动识别且本机没有 cpufreq 时无从判断键是否有效；
+    // 旧格式总是四档都写，没有该档时的 SmallCore ... SuperBigCore 也不提示
+    let clusters = framework.clusters();
+    let find_cluster = |key: &str| clusters.iter().find(|cl| cl.matches(key));
+    let legacy_filler = |key: &str| framework.clusters.is_empty() && TIER_NAMES.contains(&key);
+    let unknown = |key: &str| !clusters.is_empty() && !legacy_filler(key) && find_cluster(key).is_none();
+
+    for mode_name in config.mode_names() {
+        let Some(mode) = config.get_mode(mode_name) else { continue };
+        // 自定义模式只检查自己写出的项，继承来的值在父模式处已经检查过
+        let custom = !Config::MODE_NAMES.contains(&mode_name);
+        let prefix: Vec<&str> = if custom { vec!["modes", mode_name] } else { vec![mode_name] };
+        let at = |rest: &[&str]| -> Vec<String> { prefix.iter().chain(rest).map(|k| k.to_string()).collect() };
+        let label = prefix.join(".");
+
+        for (name, range) in &mode.freq.clusters {
+            let (legacy_min, legacy_max) = (format!("{}MinFreq", name), format!("{}MaxFreq", name));
+            let min_path = c.key_path(&strs(&at(&["Freq", &legacy_min])), &strs(&at(&["Freq", "Clusters", name, "Min"])));
+            let max_path = c.key_path(&strs(&at(&["Freq", &legacy_max])), &strs(&at(&["Freq", "Clusters", name, "Max"])));
+            if custom && ![at(&["Freq", &legacy_min]), at(&["Freq", &legacy_max]), at(&["Freq", "Clusters", name])]
+                .iter().any(|path| c.present(&strs(path))) {
+                continue;
+            }
+            if unknown(name) {
+                c.warning(&min_path, format!("{}.Freq: '{}' does not match any cluster, ignored", label, name));
+            }
+            let Some(cluster) = find_cluster(name) else { continue };
+            let table = device.and_then(|d| d.policies.get(&cluster.policy)).filter(|t| !t.is_empty());
+            // 相对写法只有读到频率表时才能比较
+            let resolved = match table {
+                Some(table) => range.min.resolve(table).zip(range.max.resolve(table)),
+                None => range.min.absolute().zip(range.max.absolute()),
+            };
+            if let Some((min, max)) = resolved.filter(|(min, max)| min > max) {
+                c.warning(&min_path, format!("{}.Freq.{}: min ({} = {} kHz) is above max ({} = {} kHz), it will be clamped",
+                    label, name, range.min, min, range.max, max));
+            }
+            check_freq(c, device, cluster.policy, &range.min, &min_path);
+            check_freq(c, device, cluster.policy, &range.max, &max_path);
+        }
+        for name in mode.governor.clusters.keys() {
+            let path = c.key_path(&strs(&at(&["Governor", name])), &strs(&at(&["Governor", "Clusters", name])));
+            if custom && !c.present(&strs(&at(&["Governor", name]))) && !c.present(&strs(&at(&["Governor", "Clusters", name]))) {
+                continue;
+            }
+            if unknown(name) {
+                c.warning(&path, format!("{}.Governor: '{}' does not match any cluster, ignored", label, name));
+            }
+        }
+    }
+
+    for (name, freq) in &config.app_launch_boost_settings.clusters {
+        let path = c.key_path(&["AppLaunchBoostSettings", &format!("{}BoostFreq", name)], &["AppLaunchBoostSettings", "Clusters", name]);
+        if unknown(name) {
+            c.warning(&path, format!("AppLaunchBoostSettings: '{}' does not match any cluster, ignored", name));
+        }
+        if let Some(cluster) = find_cluster(name) {
+            check_freq(c, device, cluster.policy, freq, &path);
+        }
+    }
+
+    let cpus = CpuSets {
+        possible: device.and_then(|d| d.possible.as_deref()),
+        online: device.and_then(|d| d.online.as_deref()),
+    };
+    check_cpuset(c, cpus, &config.core_allocation.cpu_set_core, &["CoreAllocation", "CpuSetCore"]);
+    let cpusets = [
+        ("top_app", &config.cpu_set.top_app),
+        ("foreground", &config.cpu_set.foreground),
+        ("restricted", &config.cpu_set.restricted),
+        ("system_background", &config.cpu_set.system_background),
+        ("background", &config.cpu_set.background),
+    ];
+    for (key, value) in cpusets {
+        check_cpuset(c, cpus, value, &["Cpuset", key]);
+    }
+}
+
+/// 显式频率必须在对应 policy 的频率表中；相对写法需要频率表，且 step 不应超出档位数
+/// ("min" / "max" 不检查)
+fn check_freq(c: &mut Checker, device: Option<&Device>, policy: i32, freq: &FreqExpr, key_path: &[Seg]) {
+    if policy < 0 {
+        return;
+    }
+    let Some(table) = device.and_then(|d| d.policies.get(&policy)) else { return };
+    if table.is_empty() {
+        if freq.needs_table() {
+            c.warning(key_path, format!(
+                "{}: '{}' needs scaling_available_frequencies, which policy{} does not provide; the limit will not be applied",
+                display_path(key_path), freq, policy));
+        }
+        return;
+    }
+    match *freq {
+        FreqExpr::Khz(khz) if !table.contains(&khz) => {
+            let nearest = FreqExpr::Nearest(khz).resolve(table).unwrap_or_default();
+            c.warning(key_path, format!(
+                "{}: {} kHz is not in policy{} scaling_available_frequencies, the kernel will pick a nearby step (closest: {})",
+                display_path(key_path), khz, policy, nearest));
+        }
+        FreqExpr::Step(n) if n.unsigned_abs() as usize >= table.len() => {
+            let khz = freq.resolve(table).unwrap_or_default();
+            c.warning(key_path, format!(
+                "{}: '{}' is beyond the {} steps of policy{}, clamped to {} kHz",
+                display_path(key_path), freq, table.len(), policy, khz));
+        }
+        _ => {}
+    }
+}
+
+/// 设备上不存在的 CPU (不在 possible 中) 报 error；存在但暂时离线 (热插拔、省电核心休眠) 只报 warning
+fn check_cpuset(c: &mut Checker, sets: CpuSets, value: &str, key_path: &[&str]) {
+    let value = value.trim();
+    if value.is_empty() {
+        return;
+    }
+    let cpus = match parse_cpu_list(value) {
+        Ok(cpus) => cpus,
+        Err(e) => {
+            c.error(&keys(key_path), format!("{}: invalid cpu list '{}': {}", key_path.join("."), value, e));
+            return;
+        }
+    };
+    let missing = |set: &[usize]| -> Vec<String> {
+        cpus.iter().filter(|cpu| !set.contains(cpu)).map(|cpu| cpu.to_string()).collect()
+    };
+
+    if let Some(possible) = sets.possible {
+        let absent = missing(possible);
+        if !absent.is_empty() {
+            c.error(&keys(key_path), format!("{}: cpu {} does not exist (possible: {})",
+                key_path.join("."), absent.join(","), format_cpu_list(possible)));
+            return;
+        }
+    }
+    if let Some(online) = sets.online {
+        let offline = missing(online);
+        if !offline.is_empty() {
+            c.warning(&keys(key_path), format!("{}: cpu {} not online (online: {})",
+                key_path.join("."), offline.join(","), format_cpu_list(online)));
+        }
+    }
+}
+
+fn check_rules_semantics(c: &mut Checker, rules: &RulesConfig, modes: &[String]) {
+    let fas = &rules.fas_rules;
+    if fas.perf_floor > fas.perf_ceil {
+        c.error(&keys(&["fas_rules", "perf_floor"]),
+            format!("fas_rules.perf_floor ({}) is greater than perf_ceil ({})", fas.perf_floor, fas.perf_ceil));
+    }
+    if fas.loading_perf_floor > fas.loading_perf_ceiling {
+        c.error(&keys(&["fas_rules", "loading_perf_floor"]),
+            format!("fas_rules.loading_perf_floor ({}) is greater than loading_perf_ceiling ({})",
+                fas.loading_perf_floor, fas.loading_perf_ceiling));
+    }
+
+    let clg = &rules.cpu_load_governor;
+    if clg.perf_floor > clg.perf_ceil {
+        c.error(&keys(&["cpu_load_governor", "perf_floor"]),
+            format!("cpu_load_governor.perf_floor ({}) is greater than perf_ceil ({})", clg.perf_floor, clg.perf_ceil));
+    }
+    if clg.down_threshold > clg.up_threshold {
+        c.error(&keys(&["cpu_load_governor", "down_threshold"]),
+            format!("cpu_load_governor.down_threshold ({}) is greater than up_threshold ({})", clg.down_threshold, clg.up_threshold));
+    }
+
+    if !modes.contains(&rules.global_mode) {
+        c.error(&keys(&["global_mode"]),
+            format!("global_mode: unknown mode '{}' (expected one of {})", rules.global_mode, modes.join(", ")));
+    }
+    let mut app_modes: Vec<(&String, &String)> = rules.app_modes.iter().collect();
+    app_modes.sort();
+    for (package, mode) in app_modes {
+        if !modes.contains(mode) {
+            c.error(&keys(&["app_modes", package]),
+                format!("app_modes.{}: unknown mode '{}' (expected one of {})", package, mode, modes.join(", ")));
+        }
+    }
+}
+
+/// 解析 "0-3,6,7" 形式的 CPU 列表
+pub fn parse_cpu_list(s: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in s.split(',').map(str::trim) {
+        let parse = |v: &str| v.trim().parse::<usize>().map_err(|_| format!("'{}' is not a cpu number", v.trim()));
+        match part.split_once('-') {
+            Some((lo, hi)) => {
+                let (lo, hi) = (parse(lo)?, parse(hi)?);
+                if lo > hi {
+                    return Err(format!("range {}-{} is reversed", lo, hi));
+                }
+                cpus.extend(lo..=hi);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut iter = cpus.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        parts.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+    }
+    parts.join(",")
+}
+
+// ════════════════════════════════════════════════════════════════
+//  键路径与行列定位
+// ════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+enum Seg {
+    Key(String),
+    Index(usize),
+}
+
+fn keys(path: &[&str]) -> Vec<Seg> {
+    path.iter().map(|k| Seg::Key(k.to_string())).collect()
+}
+
+fn strs(path: &[String]) -> Vec<&str> {
+    path.iter().map(String::as_str).collect()
+}
+
+fn collect_segments(path: &serde_ignored::Path, out: &mut Vec<Seg>) {
+    use serde_ignored::Path as P;
+    match path {
+        P::Root => {}
+        P::Seq { parent, index } => {
+            collect_segments(parent, out);
+            out.push(Seg::Index(*index));
+        }
+        P::Map { parent, key } => {
+            collect_segments(parent, out);
+            out.push(Seg::Key(key.clone()));
+        }
+        P::Some { parent } | P::NewtypeStruct { parent } | P::NewtypeVariant { parent } => {
+            collect_segments(parent, out);
+        }
+    }
+}
+
+fn display_path(segs: &[Seg]) -> String {
+    let mut out = String::new();
+    for seg in segs {
+        match seg {
+            Seg::Key(k) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(k);
+            }
+            Seg::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+/// 块风格 YAML 中的一个结构标记
+struct Token {
+    line: usize,
+    indent: usize,
+    kind: TokenKind,
+}
+
+enum TokenKind {
+    /// 序列项 `- `
+    Item,
+    Key(String),
+}
+
+/// 只识别块风格的映射与序列 (配置文件都是这种写法)；flow 风格 `{ }` / `[ ]`
+/// 内部的键定位不到，诊断会落在最近的外层键上
+fn tokenize(content: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (idx, raw) in content.lines().enumerate() {
+        let mut indent = raw.len() - raw.trim_start().len();
+        let mut rest = raw.trim_start();
+        if rest.is_empty() || rest.starts_with('#') || rest.starts_with("---") {
+            continue;
+        }
+        while rest == "-" || rest.starts_with("- ") {
+            tokens.push(Token { line: idx + 1, indent, kind: TokenKind::Item });
+            let after = rest[1..].trim_start();
+            indent += rest.len() - after.len();
+            rest = after;
+        }
+        if let Some(key) = mapping_key(rest) {
+            tokens.push(Token { line: idx + 1, indent, kind: TokenKind::Key(key) });
+        }
+    }
+    tokens
+}
+
+fn mapping_key(s: &str) -> Option<String> {
+    if let Some(quote) = s.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = s[1..].find(quote)? + 1;
+        let after = s[end + 1..].trim_start();
+        return after.starts_with(':').then(|| s[1..end].to_string());
+    }
+    let bytes = s.as_bytes();
+    (0..bytes.len())
+        .find(|&i| bytes[i] == b':' && (i + 1 == bytes.len() || bytes[i + 1] == b' '))
+        .map(|i| s[..i].trim_end().to_string())
+}
+
+/// 找到键路径在文本中的 (行, 列)；只找到前缀时返回最深一层已找到的位置，
+/// 另外返回实际匹配到的层数
+fn locate_prefix(content: &str, path: &[Seg]) -> (Option<(usize, usize)>, usize) {
+    let tokens = tokenize(content);
+    let mut matched = 0;
+    let mut range = 0..tokens.len();
+    let mut parent_indent: Option<usize> = None;
+    let mut found = None;
+
+    for seg in path {
+        let child_indent = tokens[range.clone()].iter()
+            .map(|t| t.indent)
+            .find(|&i| parent_indent.is_none_or(|p| i > p));
+        let Some(child_indent) = child_indent else { break };
+
+        let at_level = range.clone().filter(|&i| tokens[i].indent == child_indent);
+        let hit = match seg {
+            Seg::Key(k) => at_level.clone().find(|&i| matches!(&tokens[i].kind, TokenKind::Key(key) if key == k)),
+            Seg::Index(n) => at_level.clone().filter(|&i| matches!(tokens[i].kind, TokenKind::Item)).nth(*n),
+        };
+        let Some(idx) = hit else { break };
+
+        found = Some((tokens[idx].line, tokens[idx].indent + 1));
+        matched += 1;
+        let end = (idx + 1..range.end).find(|&i| tokens[i].indent <= child_indent).unwrap_or(range.end);
+        range = idx + 1..end;
+        parent_indent = Some(child_indent);
+    }
+    (found, matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modes(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn rules_modes_are_checked_against_given_set() {
+        let mut rules = RulesConfig { global_mode: "balance".to_string(), ..Default::default() };
+        rules.app_modes.insert("com.example.game".to_string(), "turbo".to_string());
+        let content = serde_yaml::to_string(&rules).unwrap();
+        let (_, report) = check_rules(Path::new("rules.yaml"), &content, &modes(&["balance", "fas"]));
+        let error = report.first_error().expect("unknown mode is an error");
+        assert!(error.contains("unknown mode 'turbo'"), "{}", error);
+
+        let (_, report) = check_rules(Path::new("rules.yaml"), &content, &modes(&["balance", "fas", "turbo"]));
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn running_modes_include_custom_modes() {
+        let mut config: Config = serde_yaml::from_str("modes:\n  turbo:\n    extends: performance\n").unwrap();
+        assert!(config.resolve_modes().is_empty());
+        let modes = known_modes(&config);
+        assert!(modes.iter().any(|m| m == "turbo"));
+        assert!(modes.iter().any(|m| m == "fas"));
+    }
+
+    #[test]
+    fn cpuset_offline_cpu_is_warning_and_missing_cpu_is_error() {
+        let content = "Cpuset:\n  top_app: 0-7\n";
+        let mut c = Checker { sources: vec![Source { path: None, content }], report: Report::new(Path::new("config.yaml")) };
+        let (possible, online) = ([0, 1, 2, 3, 4, 5, 6, 7], [0, 1, 2, 3, 4, 5]);
+        let sets = CpuSets { possible: Some(&possible), online: Some(&online) };
+
+        check_cpuset(&mut c, sets, "0-7", &["Cpuset", "top_app"]);
+        assert!(!c.report.has_errors());
+        let d = c.report.diagnostics.last().expect("offline cpu is reported");
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.line, Some(2));
+        assert!(d.message.contains("cpu 6,7 not online"), "{}", d.message);
+
+        check_cpuset(&mut c, sets, "0-9", &["Cpuset", "top_app"]);
+        assert!(c.report.first_error().unwrap().contains("cpu 8,9 does not exist"));
+    }
+}
@@ -0,0 +1,300 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 本地控制 socket
+//!
+//! 在模块根目录下监听 `yumi.sock`，协议为一行一个 JSON 请求、一行一个 JSON 应答：
+//!
+//! ```text
+//! -> {"cmd":"set_mode","mode":"fast"}
+//! <- {"ok":true,"data":{"mode":"fast"}}
+//! ```
+//!
+//! 请求通过 `DaemonEvent::Control` 注入事件总线，由 scheduler_ipc 线程同步处理并回复。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use log::{info, warn, debug};
+
+use crate::bus::Publisher;
+use crate::common::{self, DaemonEvent};
+use crate::config_check;
+use crate::i18n::t_with_args;
+use crate::fluent_args;
+use crate::monitor::app_detect;
+use crate::monitor::config::{self as rules_config, RulesConfig};
+use crate::status;
+
+/// 等待 scheduler 回复的最长时间
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+/// 单个连接的读超时，客户端挂起时及时释放连接线程
+const CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// 单个连接的写超时，客户端不读应答时同样释放
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 同时服务的连接数上限
+const MAX_CLIENTS: usize = 8;
+
+pub fn get_socket_path() -> PathBuf { common::get_module_root().join("yumi.sock") }
+
+/// 控制请求，`cmd` 字段区分类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// 当前模式、前台应用及控制器概况
+    Status,
+    /// 立即切换到指定模式
+    ///
+    /// 带任一结束条件时作为临时覆盖生效，优先于 rules.yaml 的规则，直到条件满足；
+    /// 否则只切换一次，下次前台应用变化时恢复按规则选择。
+    SetMode {
+        mode: String,
+        /// 覆盖持续时间 (秒)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration_secs: Option<u64>,
+        /// 拔掉电源时结束覆盖
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        until_unplugged: bool,
+        /// 当前前台应用离开时结束覆盖
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        until_app_exit: bool,
+    },
+    /// 取消临时模式覆盖，恢复按规则选择
+    ClearOverride,
+    /// 强制重载 rules.yaml 与 config/config.yaml
+    Reload,
+    /// FAS 控制器运行时状态
+    FasState,
+    /// CPU 负载调频器运行时状态
+    ClgState,
+    /// 释放 FAS/负载调频器并把所有写过的节点恢复为原值 (下一次模式切换会重新应用)
+    Restore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl ControlResponse {
+    pub fn ok(data: serde_json::Value) -> Self {
+        Self { ok: true, error: None, data: Some(data) }
+    }
+
+    pub fn error<S: Into<String>>(msg: S) -> Self {
+        Self { ok: false, error: Some(msg.into()), data: None }
+    }
+}
+
+/// 启动控制 socket 监听线程
+///
+/// `config_arc` / `force_refresh_arc` 与 app_detect 共享，
+/// 使 `reload` 请求能像 inotify 重载一样刷新前台模式判断。
+pub fn start_control_server(
+    tx: Publisher,
+    config_arc: Arc<Mutex<RulesConfig>>,
+    force_refresh_arc: Arc<AtomicBool>,
+) -> Result<()> {
+    let socket_path = get_socket_path();
+    // 清理上次进程遗留的 socket 文件，否则 bind 会返回 EADDRINUSE
+    if socket_path.exists() {
+        fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    let _ = fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o660));
+
+    thread::Builder::new()
+        .name("control_server".to_string())
+        .spawn(move || {
+            info!("{}", t_with_args("control-server-started", &fluent_args!("path" => format!("{:?}", socket_path))));
+            let active = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("Control: accept failed: {}", e);
+                        continue;
+                    }
+                };
+                // 每个连接一个线程，一个不发请求的客户端不会挡住其他客户端
+                if active.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    reject_busy(stream);
+                    continue;
+                }
+                let (tx, config_arc, force_refresh_arc, slot) =
+                    (tx.clone(), config_arc.clone(), force_refresh_arc.clone(), active.clone());
+                let spawned = thread::Builder::new()
+                    .name("control_client".to_string())
+                    .spawn(move || {
+                        if let Err(e) = handle_client(stream, &tx, &config_arc, &force_refresh_arc) {
+                            debug!("Control: client session ended with error: {}", e);
+                        }
+                        slot.fetch_sub(1, Ordering::SeqCst);
+                    });
+                if let Err(e) = spawned {
+                    warn!("Control: failed to spawn client thread: {}", e);
+                    active.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+/// 连接数已满：回一条错误后断开
+fn reject_busy(stream: UnixStream) {
+    let _ = stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT));
+    if let Ok(mut out) = serde_json::to_string(&ControlResponse::error("too many control clients")) {
+        out.push('\n');
+        let _ = (&stream).write_all(out.as_bytes());
+    }
+}
+
+fn handle_client(
+    stream: UnixStream,
+    tx: &Publisher,
+    config_arc: &Arc<Mutex<RulesConfig>>,
+    force_refresh_arc: &Arc<AtomicBool>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => {
+                debug!("Control: request {:?}", request);
+                dispatch(request, tx, config_arc, force_refresh_arc)
+            }
+            Err(e) => ControlResponse::error(format!("invalid request: {}", e)),
+        };
+
+        let mut out = serde_json::to_string(&response)?;
+        out.push('\n');
+        writer.write_all(out.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn dispatch(
+    request: ControlRequest,
+    tx: &Publisher,
+    config_arc: &Arc<Mutex<RulesConfig>>,
+    force_refresh_arc: &Arc<AtomicBool>,
+) -> ControlResponse {
+    if let ControlRequest::Reload = request {
+        // 出错时直接把错误回给客户端，当前规则保持不变
+        let new_rules = match config_check::load_rules_checked(&rules_config::get_rules_path()) {
+            Ok(rules) => rules,
+            Err(e) => return ControlResponse::error(e),
+        };
+        *config_arc.lock().unwrap() = new_rules.clone();
+        if tx.send(DaemonEvent::ConfigReload(new_rules)).is_err() {
+            return ControlResponse::error("scheduler channel closed");
+        }
+        force_refresh_arc.store(true, Ordering::SeqCst);
+    }
+
+    if let ControlRequest::ClearOverride = request {
+        let cleared = app_detect::clear_mode_override();
+        force_refresh_arc.store(true, Ordering::SeqCst);
+        return ControlResponse::ok(serde_json::json!({ "cleared": cleared }));
+    }
+
+    // 覆盖要在 scheduler 切换之前就位，避免 app_detect 在此期间按规则把模式切回去
+    let mut override_installed = false;
+    if let ControlRequest::SetMode { mode, duration_secs, until_unplugged, until_app_exit } = &request
+        && (duration_secs.is_some() || *until_unplugged || *until_app_exit)
+    {
+        // 时长来自客户端，换算成截止时间时可能溢出
+        let expires_at_ms = match duration_secs {
+            Some(secs) => match secs.checked_mul(1000).and_then(|ms| status::now_ms().checked_add(ms)) {
+                Some(deadline) => Some(deadline),
+                None => return ControlResponse::error(format!("duration {}s is out of range", secs)),
+            },
+            None => None,
+        };
+        if *until_unplugged && !app_detect::is_power_connected() {
+            return ControlResponse::error("until_unplugged requested but the device is not plugged in");
+        }
+        let current_package = app_detect::get_current_package();
+        if *until_app_exit && current_package.is_empty() {
+            return ControlResponse::error("until_app_exit requested but no foreground app is known yet");
+        }
+        app_detect::set_mode_override(app_detect::ModeOverride {
+            mode: mode.clone(),
+            expires_at_ms,
+            until_unplugged: *until_unplugged,
+            until_app_exit: until_app_exit.then_some(current_package),
+        });
+        override_installed = true;
+    }
+
+    let one_shot = matches!(request, ControlRequest::SetMode { .. }) && !override_installed;
+
+    // 其余部分 (包括 config.yaml 的重载) 交给 scheduler_ipc 线程处理
+    let (reply_tx, reply_rx) = mpsc::channel();
+    if tx.send(DaemonEvent::Control { request, reply: reply_tx }).is_err() {
+        return ControlResponse::error("scheduler channel closed");
+    }
+    let response = reply_rx.recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| ControlResponse::error("timed out waiting for scheduler"));
+
+    // 模式名被 scheduler 拒绝时撤销覆盖
+    if override_installed && !response.ok {
+        app_detect::clear_mode_override();
+    }
+    // 一次性切换绕过了 app_detect：让它忘掉上次的模式，下一次应用切换时照常按规则切回
+    if one_shot && response.ok {
+        app_detect::invalidate_last_mode();
+    }
+    response
+}
+
+/// 客户端：向正在运行的守护进程发送单个请求并等待应答
+pub fn send_request(request: &ControlRequest) -> Result<ControlResponse> {
+    let socket_path = get_socket_path();
+    let mut stream = UnixStream::connect(&socket_path)
+        .map_err(|e| anyhow::anyhow!("cannot connect to {} (is the daemon running?): {}", socket_path.display(), e))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT + CLIENT_READ_TIMEOUT))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(serde_json::from_str(&reply)?)
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 结构化事件日志 (logs/events.jsonl)
+//!
+//! 每个调度决策一行 JSON，字段名固定、与 i18n 无关，便于事后分析。
+//! 写入走 log4rs 的独立 logger (`yumi::journal`)，由 [`crate::logger`] 负责滚动，
+//! 不受 daemon.log 日志等级影响。
+
+use serde::Serialize;
+
+use crate::status;
+
+/// 事件日志专用的 log target
+pub const TARGET: &str = "yumi::journal";
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEvent<'a> {
+    ModeChange { from: &'a str, to: &'a str, package: &'a str, pid: i32, temperature: f64 },
+    RulesReload { yumi_scheduler: bool, dynamic_enabled: bool, global_mode: &'a str },
+    ConfigReload { ok: bool, error: Option<String> },
+    GearSwitch { package: &'a str, from_fps: f32, to_fps: f32, perf: f32 },
+    LoadingEnter { package: &'a str, frames: u32, cumulative_ms: f32, perf: f32 },
+    LoadingExit { package: &'a str, perf: f32 },
+    FloorRescue { package: &'a str, stuck_frames: u32, avg_fps: f32, perf_before: f32, perf_after: f32 },
+    BoostStart { mode: &'a str, duration_ms: u64 },
+    BoostEnd { mode: &'a str },
+    ClgActivate { clusters: usize },
+    ClgRelease,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    ts_ms: u64,
+    #[serde(flatten)]
+    event: &'a JournalEvent<'a>,
+}
+
+/// 追加一条事件
+pub fn record(event: JournalEvent) {
+    if !log::log_enabled!(target: TARGET, log::Level::Info) {
+        return;
+    }
+    let line = Line { ts_ms: status::now_ms(), event: &event };
+    if let Ok(json) = serde_json::to_string(&line) {
+        log::info!(target: TARGET, "{}", json);
+    }
+}
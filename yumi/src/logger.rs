@@ -21,7 +21,7 @@ use log4rs::append::rolling_file::RollingFileAppender;
 use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
 use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
 use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
-use log4rs::config::{Appender, Config, Root};
+use log4rs::config::{Appender, Config, Logger, Root};
 use log4rs::encode::pattern::PatternEncoder;
 use log4rs::Handle;
 use once_cell::sync::OnceCell;
@@ -58,8 +58,24 @@ fn build_config(level: LevelFilter) -> Result<Config> {
         .encoder(Box::new(PatternEncoder::new("[{d(%Y-%m-%d %H:%M:%S)}] [{l}] [{M}] {m}{n}")))
         .build(log_path, Box::new(policy))?;
 
+    // 事件日志：纯 JSON 行，独立滚动，不受 daemon.log 等级影响
+    let journal_roller = FixedWindowRoller::builder()
+        .build(root.join("logs/events.{}.jsonl").to_str().unwrap(), 3)?;
+    let journal_policy = CompoundPolicy::new(
+        Box::new(SizeTrigger::new(2 * 1024 * 1024)), // 2MB
+        Box::new(journal_roller),
+    );
+    let journal_appender = RollingFileAppender::builder()
+        .encoder(Box::new(PatternEncoder::new("{m}{n}")))
+        .build(root.join("logs/events.jsonl"), Box::new(journal_policy))?;
+
     let config = Config::builder()
         .appender(Appender::builder().build("logfile", Box::new(appender)))
+        .appender(Appender::builder().build("journal", Box::new(journal_appender)))
+        .logger(Logger::builder()
+            .appender("journal")
+            .additive(false)
+            .build(crate::journal::TARGET, LevelFilter::Info))
         .build(Root::builder().appender("logfile").build(level))?;
 
     Ok(config)
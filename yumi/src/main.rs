@@ -15,47 +15,121 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod bus;
+mod cli;
+mod clock;
 mod common;
+mod config_check;
+mod control;
+mod journal;
 mod logger;
+mod metrics;
+mod migrate;
 mod monitor;
+mod recorder;
+mod replay;
+mod restore;
+mod schema;
 mod scheduler;
+mod sim;
+mod status;
+mod trace;
 pub mod i18n;
 pub mod utils;
-use std::sync::mpsc;
 use std::thread;
 use anyhow::Result;
-use log::{info, error};
+use log::{info, warn, error};
 use crate::i18n::{t, t_with_args, load_language};
-use crate::scheduler::config::Config;
+use crate::scheduler::config::Meta;
 
 fn main() -> Result<()> {
+    match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Daemon { workdir, sysfs_root, dry_run }) => run_daemon(workdir, sysfs_root, dry_run),
+        Ok(cmd) => std::process::exit(cli::run(cmd)),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run_daemon(workdir: Option<String>, sysfs_root: Option<std::path::PathBuf>, dry_run: bool) -> Result<()> {
     // 1. 环境初始化
-    if let Some(path) = std::env::args().nth(1) {
+    if let Some(path) = workdir {
         nix::unistd::chdir(path.as_str())?;
     }
+    // 必须早于任何设备节点访问 (restore 日志在第一次写入时初始化)
+    utils::set_sysfs_root(sysfs_root);
+    utils::set_dry_run(dry_run);
 
     let root = common::get_module_root();
     let log_dir = root.join("logs");
     std::fs::create_dir_all(&log_dir)?;
     
     
-    // 2. 提前读取配置
-    let config_path: std::path::PathBuf = root.join("config/config.yaml");
-    let config = Config::from_file(config_path.to_str().unwrap()).unwrap_or_default();
+    // 2. 旧版本的配置文件 (config.yaml 的每一层与 rules.yaml) 先迁移并写回
+    //    (dry-run 时只在内存中迁移；日志初始化后再记录结果)，再提前校验合并后的配置
+    let config_layers = scheduler::config::layer_paths(&root.join("config"));
+    let rules_path = monitor::config::get_rules_path();
+    let migrate_file = if utils::is_dry_run() { migrate::pending } else { migrate::upgrade_file };
+    let migrations: Vec<_> = config_layers.iter()
+        .map(|path| (path.clone(), migrate_file(migrate::ConfigKind::Main, path)))
+        .chain(std::iter::once((rules_path.clone(), migrate_file(migrate::ConfigKind::Rules, &rules_path))))
+        .collect();
+    let checked_config = config_check::check_config_layers(&config_layers);
+    let meta = checked_config.0.as_ref().map(|config| &config.meta);
 
-    // 3. 立即加载语言
-    load_language(&config.meta.language);
+    // 3. 立即加载语言 (配置无法使用时按默认值)
+    load_language(meta.map_or(&Meta::default().language, |meta| &meta.language));
 
     // 4. 初始化日志
-    logger::init(&config.meta.loglevel)?; 
+    logger::init(meta.map_or(&Meta::default().loglevel, |meta| &meta.loglevel))?; 
     
     info!("{}", t("yumi-module-starting"));
+    if let Some(sysfs_root) = utils::sysfs_root() {
+        info!("Redirecting /sys, /proc and /dev to {}", sysfs_root.display());
+    }
+    if utils::is_dry_run() {
+        info!("Dry-run: writes to /sys, /proc and /dev are logged, not performed");
+    }
+    for (path, result) in migrations {
+        match result {
+            Ok(Some(upgraded)) if utils::is_dry_run() => info!("[Config] Dry-run: {} would be migrated from schema_version {} to {} ({}), file left unchanged",
+                path.display(), upgraded.from, upgraded.to, upgraded.steps.join("; ")),
+            Ok(Some(upgraded)) => info!("[Config] Migrated {} from schema_version {} to {} ({}), backup: {}",
+                path.display(), upgraded.from, upgraded.to, upgraded.steps.join("; "),
+                migrate::backup_path(&path, upgraded.from).display()),
+            Ok(None) => {}
+            Err(e) => warn!("[Config] Could not migrate {}: {}", path.display(), e),
+        }
+    }
+
+    // 启动时按校验结果加载两个配置文件：问题写入日志与 state.json；
+    // 任一文件有错误时 scheduler 不下发调优，节点保持内核原值，直到修正后重载
+    let config = config_check::accept_config_layers(checked_config);
+    if let Ok(config) = &config {
+        config_check::set_running_config(config);
+    }
+    let rules = config_check::load_rules_checked(&rules_path);
+    if config.is_err() || rules.is_err() {
+        warn!("[Config] Configuration has errors, tunables are not applied until it is fixed");
+    }
+
+    // 在创建任何工作线程之前接管 SIGTERM/SIGINT
+    restore::install_signal_handler()?;
+
+    let metrics_settings = config.as_ref().map(|config| config.metrics.clone()).unwrap_or_default();
+    if let Err(e) = metrics::start_metrics_server(&metrics_settings) {
+        error!("Metrics: failed to listen on {}: {}", metrics_settings.listen, e);
+    }
 
-    // 3. 创建通信通道
-    let (tx, rx) = mpsc::channel::<common::DaemonEvent>();
+    // 3. 创建事件总线，scheduler 是唯一订阅 Control 的消费者
+    let event_bus = bus::EventBus::new();
+    let rx = event_bus.subscribe("scheduler", &bus::EventKind::ALL);
+    let tx = event_bus.publisher();
 
     // 4. 启动 Scheduler
-    if let Err(e) = scheduler::start_scheduler_thread(rx) {
+    if let Err(e) = scheduler::start_scheduler_thread(rx, tx.clone(), config, rules) {
         error!("{}", t_with_args("scheduler-module-start-failed", &fluent_args!("error" => e.to_string())));
         return Err(e);
     }
@@ -0,0 +1,237 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! OpenMetrics 计数器导出
+//!
+//! 计数器是进程级的原子变量，热路径上只做一次 `fetch_add`。
+//! config.yaml 的 `Metrics` 段开启后，在本地 TCP 端口或 Unix socket 上
+//! 以 OpenMetrics 文本格式提供给 Prometheus 抓取 (任意路径都返回同一份数据)。
+//! 事件总线的队列深度属于瞬时值，以 gauge 导出。
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use log::{info, warn, debug};
+
+use crate::bus;
+use crate::monitor::supervisor::{self, SourceState};
+use crate::scheduler::config::MetricsSettings;
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub const fn new() -> Self { Self(AtomicU64::new(0)) }
+    #[inline]
+    pub fn inc(&self) { self.0.fetch_add(1, Ordering::Relaxed); }
+    pub fn get(&self) -> u64 { self.0.load(Ordering::Relaxed) }
+}
+
+// ── FAS ──
+pub static FAS_FRAMES: Counter = Counter::new();
+pub static FAS_GEAR_SWITCHES: Counter = Counter::new();
+pub static FAS_LOADING_ENTRIES: Counter = Counter::new();
+pub static FAS_JANK_HEAVY: Counter = Counter::new();
+pub static FAS_JANK_CRIT: Counter = Counter::new();
+pub static FAS_FLOOR_RESCUES: Counter = Counter::new();
+pub static FAS_FREQ_MISMATCHES: Counter = Counter::new();
+// ── CLG ──
+pub static CLG_TICKS: Counter = Counter::new();
+// ── Scheduler ──
+pub static MODE_CHANGES: Counter = Counter::new();
+pub static RULES_RELOADS: Counter = Counter::new();
+pub static CONFIG_RELOADS: Counter = Counter::new();
+
+lazy_static::lazy_static! {
+    /// 每个 FastWriter 节点一个计数器，同一路径的多个 writer 共享
+    static ref SYSFS_WRITES: Mutex<BTreeMap<String, Arc<AtomicU64>>> = Mutex::new(BTreeMap::new());
+}
+
+/// 取得 (或创建) 某个节点的写入计数器，由 FastWriter 持有
+pub fn sysfs_write_counter(path: &str) -> Arc<AtomicU64> {
+    SYSFS_WRITES.lock().unwrap()
+        .entry(path.to_string())
+        .or_insert_with(|| Arc::new(AtomicU64::new(0)))
+        .clone()
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, samples: &[(&str, u64)]) {
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}_total{} {}", name, labels, value);
+    }
+}
+
+/// 渲染 OpenMetrics 文本
+pub fn render() -> String {
+    let mut out = String::with_capacity(2048);
+    write_counter(&mut out, "yumi_fas_frames", "Frames processed by the FAS controller.", &[("", FAS_FRAMES.get())]);
+    write_counter(&mut out, "yumi_fas_gear_switches", "FAS target fps gear switches.", &[("", FAS_GEAR_SWITCHES.get())]);
+    write_counter(&mut out, "yumi_fas_loading_entries", "Times FAS entered loading state.", &[("", FAS_LOADING_ENTRIES.get())]);
+    write_counter(&mut out, "yumi_fas_jank_events", "FAS jank events by severity.", &[
+        ("{kind=\"heavy\"}", FAS_JANK_HEAVY.get()),
+        ("{kind=\"crit\"}", FAS_JANK_CRIT.get()),
+    ]);
+    write_counter(&mut out, "yumi_fas_floor_rescues", "FAS floor-rescue interventions.", &[("", FAS_FLOOR_RESCUES.get())]);
+    write_counter(&mut out, "yumi_fas_freq_mismatches", "Frequency mismatches found by FAS verification.", &[("", FAS_FREQ_MISMATCHES.get())]);
+    write_counter(&mut out, "yumi_clg_ticks", "CPU load governor ticks.", &[("", CLG_TICKS.get())]);
+    write_counter(&mut out, "yumi_mode_changes", "Applied mode changes.", &[("", MODE_CHANGES.get())]);
+    write_counter(&mut out, "yumi_config_reloads", "Config reloads by file.", &[
+        ("{file=\"rules\"}", RULES_RELOADS.get()),
+        ("{file=\"config\"}", CONFIG_RELOADS.get()),
+    ]);
+
+    let writes = SYSFS_WRITES.lock().unwrap();
+    let labels: Vec<(String, u64)> = writes.iter()
+        .map(|(path, c)| (format!("{{path=\"{}\"}}", escape_label(path)), c.load(Ordering::Relaxed)))
+        .collect();
+    drop(writes);
+    let samples: Vec<(&str, u64)> = labels.iter().map(|(l, v)| (l.as_str(), *v)).collect();
+    write_counter(&mut out, "yumi_sysfs_writes", "Writes issued per FastWriter node.", &samples);
+    render_bus(&mut out);
+
+    let sources = supervisor::snapshot();
+    let up: Vec<(String, u64)> = sources.iter()
+        .map(|s| (format!("{{source=\"{}\"}}", s.name), (s.state == SourceState::Running) as u64))
+        .collect();
+    write_gauge(&mut out, "yumi_source_up", "Whether an eBPF data source is running.", &as_samples(&up));
+    let restarts: Vec<(String, u64)> = sources.iter()
+        .map(|s| (format!("{{source=\"{}\"}}", s.name), s.restarts as u64))
+        .collect();
+    write_counter(&mut out, "yumi_source_restarts", "Supervisor restarts of an eBPF data source.", &as_samples(&restarts));
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, samples: &[(&str, u64)]) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+fn as_samples(v: &[(String, u64)]) -> Vec<(&str, u64)> {
+    v.iter().map(|(l, n)| (l.as_str(), *n)).collect()
+}
+
+/// 事件总线：每个订阅者的队列深度与丢弃计数
+fn render_bus(out: &mut String) {
+    let stats = bus::stats();
+    let label = |name: &str, lane: &str| format!("{{subscriber=\"{}\",lane=\"{}\"}}", escape_label(name), lane);
+
+    let depth: Vec<(String, u64)> = stats.iter().flat_map(|s| [
+        (label(&s.name, "control"), s.control_depth as u64),
+        (label(&s.name, "load"), s.load_pending as u64),
+        (label(&s.name, "frame"), s.frame_depth as u64),
+    ]).collect();
+    let max: Vec<(String, u64)> = stats.iter()
+        .map(|s| (label(&s.name, "frame"), s.frame_depth_max as u64))
+        .collect();
+    let dropped: Vec<(String, u64)> = stats.iter().flat_map(|s| [
+        (label(&s.name, "control"), s.control_dropped),
+        (label(&s.name, "frame"), s.frames_dropped),
+    ]).collect();
+    let coalesced: Vec<(String, u64)> = stats.iter()
+        .map(|s| (label(&s.name, "load"), s.loads_coalesced))
+        .collect();
+    let delivered: Vec<(String, u64)> = stats.iter()
+        .map(|s| (format!("{{subscriber=\"{}\"}}", escape_label(&s.name)), s.delivered))
+        .collect();
+
+    write_gauge(out, "yumi_bus_queue_depth", "Events currently queued per subscriber lane.", &as_samples(&depth));
+    write_gauge(out, "yumi_bus_queue_depth_max", "High-water mark of the frame lane since start.", &as_samples(&max));
+    write_counter(out, "yumi_bus_dropped", "Events dropped because a lane was full.", &as_samples(&dropped));
+    write_counter(out, "yumi_bus_coalesced", "SystemLoadUpdate events replaced by a newer one before delivery.", &as_samples(&coalesced));
+    write_counter(out, "yumi_bus_delivered", "Events delivered to each subscriber.", &as_samples(&delivered));
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// 读掉请求头后返回一份完整的 HTTP 响应
+fn serve<S: std::io::Read + Write>(mut stream: S) -> Result<()> {
+    {
+        let mut reader = BufReader::new(&mut stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" { break; }
+        }
+    }
+    let body = render();
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    Ok(())
+}
+
+/// 按配置启动导出线程；`listen` 以 `/` 开头时视为 Unix socket 路径，否则为 TCP 地址
+pub fn start_metrics_server(settings: &MetricsSettings) -> Result<()> {
+    if !settings.enabled {
+        return Ok(());
+    }
+    let listen = settings.listen.clone();
+
+    if listen.starts_with('/') {
+        let _ = fs::remove_file(&listen);
+        let listener = UnixListener::bind(&listen)?;
+        thread::Builder::new()
+            .name("metrics_server".to_string())
+            .spawn(move || {
+                info!("Metrics: serving OpenMetrics on unix:{}", listen);
+                for stream in listener.incoming().flatten() {
+                    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+                    if let Err(e) = serve(stream) {
+                        debug!("Metrics: request failed: {}", e);
+                    }
+                }
+            })?;
+    } else {
+        let listener = TcpListener::bind(&listen)?;
+        thread::Builder::new()
+            .name("metrics_server".to_string())
+            .spawn(move || {
+                info!("Metrics: serving OpenMetrics on http://{}", listen);
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+                            if let Err(e) = serve(stream) {
+                                debug!("Metrics: request failed: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Metrics: accept failed: {}", e),
+                    }
+                }
+            })?;
+    }
+    Ok(())
+}
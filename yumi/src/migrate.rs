@@ -0,0 +1,467 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 配置文件版本迁移
+//!
+//! config.yaml 与 rules.yaml 顶层的 `schema_version` 记录文件格式的版本 (缺省为 0)。
+//! 每个文件有一条按顺序排列的迁移链，第 N 项把版本 N 的文件升级到 N + 1，
+//! 在 YAML 层面改写，不依赖当前的 serde 类型。
+//!
+//! - 守护进程启动时 ([`upgrade_file`]) 把旧文件迁移后原子写回，原文件备份为
+//!   `<文件名>.v<旧版本>.bak`；dry-run 时只在内存中迁移 ([`pending`])；
+//! - 其他读取者 ([`read`]：重载、`yumi validate`、replay 等) 只在内存中迁移。
+//!   运行期间不写回，避免替换文件导致 inotify 监听失效。
+//!
+//! 写回按文本进行：只改写 `schema_version` 行与迁移改动过的键，其余行 (注释、
+//! 空行、引号风格、CRLF 换行) 原样保留。只有文件用了无法按行定位的写法
+//! (flow 风格的映射等) 时才整份重新序列化。
+
+use anyhow::{bail, Result};
+use serde_yaml::{Mapping, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const VERSION_KEY: &str = "schema_version";
+
+/// 配置文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    /// config/config.yaml
+    Main,
+    /// rules.yaml
+    Rules,
+}
+
+/// 迁移链中的一步：把上一版本的文件升级到 `to`
+pub struct Migration {
+    pub to: u32,
+    pub summary: &'static str,
+    pub apply: fn(&mut Mapping),
+}
+
+const CONFIG_MIGRATIONS: &[Migration] = &[
+    Migration { to: 1, summary: "add schema_version", apply: |_| {} },
+];
+
+const RULES_MIGRATIONS: &[Migration] = &[
+    Migration { to: 1, summary: "fold fas_rules.per_app_margins into per_app_profiles", apply: fold_per_app_margins },
+];
+
+impl ConfigKind {
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            Self::Main => CONFIG_MIGRATIONS,
+            Self::Rules => RULES_MIGRATIONS,
+        }
+    }
+
+    /// 当前程序写出的版本
+    pub fn current_version(self) -> u32 {
+        self.migrations().last().map_or(0, |m| m.to)
+    }
+}
+
+/// 一次升级的结果
+#[derive(Debug, Clone)]
+pub struct Upgraded {
+    pub from: u32,
+    pub to: u32,
+    /// 除版本号外内容是否有改动
+    pub changed: bool,
+    /// 执行过的迁移说明
+    pub steps: Vec<&'static str>,
+    /// 升级后的文件内容；未升级时与原内容相同
+    pub content: String,
+}
+
+impl Upgraded {
+    pub fn is_upgraded(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// 在内存中把文件内容升级到当前版本
+///
+/// 无法解析的文件原样返回 (由校验报告语法错误)；版本号比当前程序新的文件报错，
+/// 调用方照常按当前格式解析。
+pub fn upgrade(kind: ConfigKind, content: &str) -> Result<Upgraded> {
+    let current = kind.current_version();
+    let unchanged = |version| Upgraded { from: version, to: version, changed: false, steps: Vec::new(), content: content.to_string() };
+
+    let Ok(Value::Mapping(mut map)) = serde_yaml::from_str::<Value>(content) else {
+        return Ok(unchanged(current));
+    };
+    let from = match map.get(VERSION_KEY) {
+        None => 0,
+        Some(v) => match v.as_u64().and_then(|v| u32::try_from(v).ok()) {
+            Some(v) => v,
+            None => bail!("{} must be a non-negative integer", VERSION_KEY),
+        },
+    };
+    if from > current {
+        bail!("{} {} is newer than this yumi supports ({})", VERSION_KEY, from, current);
+    }
+    if from == current {
+        return Ok(unchanged(from));
+    }
+
+    let before = map.clone();
+    let mut steps = Vec::new();
+    for migration in kind.migrations().iter().filter(|m| m.to > from) {
+        (migration.apply)(&mut map);
+        steps.push(migration.summary);
+    }
+    let changed = map != before;
+
+    let mut expected = Mapping::new();
+    expected.insert(VERSION_KEY.into(), current.into());
+    expected.extend(map.iter().filter(|(k, _)| k.as_str() != Some(VERSION_KEY)).map(|(k, v)| (k.clone(), v.clone())));
+
+    let patched = if changed { patch_text(content, &before, &map) } else { Some(content.to_string()) };
+    // 逐行改写只适用于块写法；流式写法 (如 WebUI 写出的 `{}`) 插入版本号后不再是合法 YAML，退回重新生成
+    let content = match patched.map(|text| stamp_version(&text, current))
+        .filter(|text| serde_yaml::from_str::<Value>(text).is_ok_and(|v| v == Value::Mapping(expected.clone())))
+    {
+        Some(text) => text,
+        None => {
+            let text = serde_yaml::to_string(&expected)?;
+            if eol(content) == "\n" { text } else { text.replace('\n', eol(content)) }
+        }
+    };
+    Ok(Upgraded { from, to: current, changed, steps, content })
+}
+
+/// 读取文件并在内存中升级 (不写回)；升级失败时返回原内容
+pub fn read(kind: ConfigKind, path: &Path) -> std::io::Result<String> {
+    let content = fs::read_to_string(path)?;
+    Ok(match upgrade(kind, &content) {
+        Ok(upgraded) if upgraded.changed => upgraded.content,
+        _ => content,
+    })
+}
+
+/// 文件版本落后时返回内存中的升级结果，不写回 (dry-run 使用)
+pub fn pending(kind: ConfigKind, path: &Path) -> Result<Option<Upgraded>> {
+    let Ok(content) = fs::read_to_string(path) else { return Ok(None) };
+    let upgraded = upgrade(kind, &content)?;
+    Ok(upgraded.is_upgraded().then_some(upgraded))
+}
+
+/// 启动时调用：文件版本落后时升级并原子写回，返回写回的结果
+///
+/// 原文件先复制为 `<文件名>.v<旧版本>.bak`，新内容写入临时文件并 fsync 后 rename 覆盖。
+pub fn upgrade_file(kind: ConfigKind, path: &Path) -> Result<Option<Upgraded>> {
+    let Some(upgraded) = pending(kind, path)? else { return Ok(None) };
+
+    fs::copy(path, backup_path(path, upgraded.from))?;
+    let tmp_path = path.with_extension("yaml.tmp");
+    let mut tmp = File::create(&tmp_path)?;
+    tmp.write_all(upgraded.content.as_bytes())?;
+    tmp.sync_all()?;
+    drop(tmp);
+    fs::rename(&tmp_path, path)?;
+    Ok(Some(upgraded))
+}
+
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// 只改版本号：替换已有的顶层 `schema_version` 行，没有时插到文件开头
+fn stamp_version(content: &str, version: u32) -> String {
+    let line = format!("{}: {}", VERSION_KEY, version);
+    let prefix = format!("{}:", VERSION_KEY);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    match lines.iter().position(|l| l.starts_with(&prefix)) {
+        Some(existing) => lines[existing] = line,
+        None => lines.splice(0..0, [line, String::new()]).for_each(drop),
+    }
+    join_lines(content, lines)
+}
+
+/// 原文件使用的换行符
+fn eol(content: &str) -> &'static str {
+    if content.contains("\r\n") { "\r\n" } else { "\n" }
+}
+
+/// 按原文件的换行符拼接，末尾总是带一个换行
+fn join_lines(original: &str, lines: Vec<String>) -> String {
+    let eol = eol(original);
+    let mut out = lines.join(eol);
+    out.push_str(eol);
+    out
+}
+
+// ════════════════════════════════════════════════════════════════
+//  按文本写回
+// ════════════════════════════════════════════════════════════════
+
+/// 映射中一个键在文本中占据的行
+struct Block {
+    key: Value,
+    /// 紧贴在键上方的注释行 (随键一起删除)
+    lead: usize,
+    /// 键所在的行
+    line: usize,
+    /// 最后一行内容之后 (不含结尾的空行与注释)
+    end: usize,
+    /// 值写在键的同一行 (标量、flow 风格或锚点)，不能再往下细分
+    inline: bool,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// 空行、注释与文档标记，不属于任何键
+fn is_filler(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty() || t.starts_with('#') || t == "---"
+}
+
+/// 解析 `key: ...` 形式的行，返回键与冒号之后的内容
+fn parse_key(line: &str) -> Option<(Value, &str)> {
+    let t = line.trim_start();
+    if t.starts_with(['-', '?', '[', '{']) {
+        return None;
+    }
+    let colon = match t.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let close = t[1..].find(quote)? + 1;
+            t[close + 1..].starts_with(':').then_some(close + 1)?
+        }
+        _ => t.match_indices(':').map(|(i, _)| i)
+            .find(|&i| t[i + 1..].chars().next().is_none_or(char::is_whitespace))?,
+    };
+    let key = serde_yaml::from_str::<Value>(&t[..colon]).ok()?;
+    Some((key, &t[colon + 1..]))
+}
+
+/// `lines[lo..hi]` 中同一缩进的各个键；出现无法按行定位的写法时返回 None
+fn child_blocks(lines: &[&str], lo: usize, hi: usize) -> Option<(usize, Vec<Block>)> {
+    let first = (lo..hi).find(|&i| !is_filler(lines[i]))?;
+    let indent = indent_of(lines[first]);
+    let mut blocks: Vec<Block> = Vec::new();
+    for i in first..hi {
+        let line = lines[i];
+        if is_filler(line) || indent_of(line) > indent {
+            if !is_filler(line) {
+                blocks.last_mut()?.end = i + 1;
+            }
+            continue;
+        }
+        if indent_of(line) < indent {
+            return None;
+        }
+        let (key, rest) = parse_key(line)?;
+        let rest = rest.trim();
+        let mut lead = i;
+        while lead > lo && lines[lead - 1].trim().starts_with('#') && indent_of(lines[lead - 1]) == indent {
+            lead -= 1;
+        }
+        blocks.push(Block { key, lead, line: i, end: i + 1, inline: !(rest.is_empty() || rest.starts_with('#')) });
+    }
+    Some((indent, blocks))
+}
+
+/// 以 `indent` 缩进序列化一个键
+fn render_entry(key: &Value, value: &Value, indent: usize) -> Option<Vec<String>> {
+    let mut entry = Mapping::new();
+    entry.insert(key.clone(), value.clone());
+    let text = serde_yaml::to_string(&entry).ok()?;
+    Some(text.lines().map(|l| format!("{:indent$}{}", "", l)).collect())
+}
+
+/// 收集把 `before` 改写为 `after` 所需的行替换 (起始行, 结束行, 新内容)
+fn diff_blocks(
+    lines: &[&str],
+    (lo, hi): (usize, usize),
+    before: &Mapping,
+    after: &Mapping,
+    edits: &mut Vec<(usize, usize, Vec<String>)>,
+) -> Option<()> {
+    let (indent, blocks) = child_blocks(lines, lo, hi)?;
+    if blocks.len() != before.len() || blocks.iter().any(|b| !before.contains_key(&b.key)) {
+        return None;
+    }
+    for block in &blocks {
+        let old = &before[&block.key];
+        match after.get(&block.key) {
+            None => edits.push((block.lead, block.end, Vec::new())),
+            Some(new) if new == old => {}
+            Some(Value::Mapping(new)) if !block.inline && block.line + 1 < block.end => {
+                let Value::Mapping(old) = old else { return None };
+                diff_blocks(lines, (block.line + 1, block.end), old, new, edits)?;
+            }
+            Some(new) => edits.push((block.line, block.end, render_entry(&block.key, new, indent)?)),
+        }
+    }
+    let mut added = Vec::new();
+    for (key, value) in after.iter().filter(|(k, _)| !before.contains_key(*k)) {
+        added.extend(render_entry(key, value, indent)?);
+    }
+    if !added.is_empty() {
+        let at = blocks.last()?.end;
+        edits.push((at, at, added));
+    }
+    Some(())
+}
+
+/// 只改写 `before` 与 `after` 之间有差异的键，其余行原样保留
+fn patch_text(content: &str, before: &Mapping, after: &Mapping) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut edits = Vec::new();
+    diff_blocks(&lines, (0, lines.len()), before, after, &mut edits)?;
+
+    // 从后往前应用；同一位置的插入，外层 (后收集的) 排在内层之后
+    let mut edits: Vec<_> = edits.into_iter().enumerate().collect();
+    edits.sort_by(|(ia, a), (ib, b)| (b.0, ib).cmp(&(a.0, ia)));
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    for (_, (start, end, replacement)) in edits {
+        out.splice(start..end, replacement);
+    }
+    Some(join_lines(content, out))
+}
+
+// ════════════════════════════════════════════════════════════════
+//  迁移
+// ════════════════════════════════════════════════════════════════
+
+/// rules v1：`fas_rules.per_app_margins` 并入 `per_app_profiles.<包名>.fps_margin`
+fn fold_per_app_margins(rules: &mut Mapping) {
+    let Some(Value::Mapping(fas)) = rules.get_mut("fas_rules") else { return };
+    let Some(Value::Mapping(margins)) = fas.remove("per_app_margins") else { return };
+
+    let profiles = fas.entry("per_app_profiles".into()).or_insert_with(|| Value::Mapping(Mapping::new()));
+    if !profiles.is_mapping() {
+        *profiles = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(profiles) = profiles else { return };
+    for (pkg, margin) in margins {
+        let profile = profiles.entry(pkg).or_insert_with(|| Value::Mapping(Mapping::new()));
+        if !profile.is_mapping() {
+            *profile = Value::Mapping(Mapping::new());
+        }
+        if let Value::Mapping(profile) = profile {
+            profile.insert("fps_margin".into(), margin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES_V0: &str = "\
+# yumi rules
+global_mode: balance
+
+fas_rules:
+  # 目标帧率余量
+  fps_margin: 3
+  # 每个应用单独的余量
+  per_app_margins:
+    com.example.game: 5
+  per_app_profiles:
+    # 已有的 profile
+    com.example.other:
+      fps_margin: 1
+app_modes:
+  com.example.game: fas
+";
+
+    #[test]
+    fn flow_mapping_is_regenerated() {
+        let upgraded = upgrade(ConfigKind::Main, "{}\n").unwrap();
+        assert_eq!(upgraded.content, "schema_version: 1\n");
+
+        let upgraded = upgrade(ConfigKind::Main, "{balance: {Freq: {SmallCoreMaxFreq: 1200000}}}\n").unwrap();
+        let value: Value = serde_yaml::from_str(&upgraded.content).unwrap();
+        assert_eq!(value["schema_version"], Value::from(1));
+        assert_eq!(value["balance"]["Freq"]["SmallCoreMaxFreq"], Value::from(1200000));
+    }
+
+    #[test]
+    fn version_only_upgrade_keeps_text() {
+        let content = "# 注释\r\nmeta:\r\n  loglevel: info\r\n";
+        let upgraded = upgrade(ConfigKind::Main, content).unwrap();
+        assert!(!upgraded.changed);
+        assert_eq!(upgraded.content, "schema_version: 1\r\n\r\n# 注释\r\nmeta:\r\n  loglevel: info\r\n");
+    }
+
+    #[test]
+    fn migration_rewrites_only_changed_keys() {
+        let upgraded = upgrade(ConfigKind::Rules, RULES_V0).unwrap();
+        assert!(upgraded.changed);
+        assert_eq!(upgraded.content, "\
+schema_version: 1
+
+# yumi rules
+global_mode: balance
+
+fas_rules:
+  # 目标帧率余量
+  fps_margin: 3
+  per_app_profiles:
+    # 已有的 profile
+    com.example.other:
+      fps_margin: 1
+    com.example.game:
+      fps_margin: 5
+app_modes:
+  com.example.game: fas
+");
+        let migrated: Value = serde_yaml::from_str(&upgraded.content).unwrap();
+        assert_eq!(migrated["fas_rules"]["per_app_profiles"]["com.example.game"]["fps_margin"], 5);
+    }
+
+    #[test]
+    fn migration_keeps_crlf() {
+        let content = RULES_V0.replace('\n', "\r\n");
+        let upgraded = upgrade(ConfigKind::Rules, &content).unwrap();
+        assert!(upgraded.content.contains("# 目标帧率余量\r\n"));
+        assert_eq!(upgraded.content.matches('\n').count(), upgraded.content.matches("\r\n").count());
+    }
+
+    #[test]
+    fn added_keys_go_to_the_end_of_their_mapping() {
+        let content = "fas_rules:\n  fps_margin: 3\n  per_app_margins:\n    com.a: 2\n\napp_modes: {}\n";
+        let upgraded = upgrade(ConfigKind::Rules, content).unwrap();
+        assert_eq!(upgraded.content,
+            "schema_version: 1\n\nfas_rules:\n  fps_margin: 3\n  per_app_profiles:\n    com.a:\n      fps_margin: 2\n\napp_modes: {}\n");
+    }
+
+    #[test]
+    fn flow_style_falls_back_to_reserializing() {
+        let content = "fas_rules: {per_app_margins: {com.a: 2}}\n";
+        let upgraded = upgrade(ConfigKind::Rules, content).unwrap();
+        let migrated: Value = serde_yaml::from_str(&upgraded.content).unwrap();
+        assert_eq!(migrated[VERSION_KEY], 1);
+        assert_eq!(migrated["fas_rules"]["per_app_profiles"]["com.a"]["fps_margin"], 2);
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let content = "schema_version: 1\nglobal_mode: balance\n";
+        let upgraded = upgrade(ConfigKind::Rules, content).unwrap();
+        assert!(!upgraded.is_upgraded());
+        assert_eq!(upgraded.content, content);
+    }
+}
@@ -24,10 +24,14 @@ use inotify::{Inotify, WatchMask};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, AtomicI32};
 use std::error::Error;
 use std::process::Command;
-use std::sync::mpsc::Sender;
+use crate::bus::Publisher;
+
+use serde::Serialize;
 
 use crate::common::DaemonEvent;
+use crate::config_check;
 use crate::i18n::{t, t_with_args};
+use crate::migrate::ConfigKind;
 use crate::fluent_args;
 use crate::utils;
 use super::config::{self, RulesConfig};
@@ -37,6 +41,10 @@ static VALID_CGROUP_IDX: AtomicUsize = AtomicUsize::new(usize::MAX);
 
 static CURRENT_PID: AtomicI32 = AtomicI32::new(0);
 
+/// 模式被规则以外的途径 (控制 socket 的一次性 set-mode) 切换后置位，
+/// 检测循环据此忘掉上次按规则下发的模式，下一次应用切换一定会发出 ModeChange
+static LAST_MODE_STALE: AtomicBool = AtomicBool::new(false);
+
 // 获取系统已启用的输入法列表
 fn get_system_ime_packages() -> HashSet<String> {
     let mut imes = HashSet::new();
@@ -75,12 +83,78 @@ fn get_system_ime_packages() -> HashSet<String> {
 lazy_static::lazy_static! {
     static ref CURRENT_PACKAGE: Arc<Mutex<String>> = Arc::new(Mutex::new("".to_string()));    
     static ref IME_BLOCKLIST: HashSet<String> = get_system_ime_packages();
+    static ref MODE_OVERRIDE: Mutex<Option<ModeOverride>> = Mutex::new(None);
+}
+
+/// 临时模式覆盖：优先于 app_modes / global_mode，满足任一结束条件后自动清除
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeOverride {
+    pub mode: String,
+    /// 到期时间 (Unix 毫秒)
+    pub expires_at_ms: Option<u64>,
+    /// 拔掉电源时结束
+    pub until_unplugged: bool,
+    /// 该应用离开前台时结束
+    pub until_app_exit: Option<String>,
+}
+
+impl ModeOverride {
+    /// 已满足的结束条件，尚未结束时返回 None
+    fn end_reason(&self, current_package: &str) -> Option<&'static str> {
+        if self.expires_at_ms.is_some_and(|t| crate::status::now_ms() >= t) {
+            return Some("expired");
+        }
+        if self.until_unplugged && !is_power_connected() {
+            return Some("unplugged");
+        }
+        if self.until_app_exit.as_deref().is_some_and(|pkg| !current_package.is_empty() && pkg != current_package) {
+            return Some("app exited");
+        }
+        None
+    }
+}
+
+pub fn get_mode_override() -> Option<ModeOverride> {
+    MODE_OVERRIDE.lock().unwrap().clone()
+}
+
+pub fn set_mode_override(mode_override: ModeOverride) {
+    info!("{}", t_with_args("app-detect-override-set", &fluent_args!("mode" => mode_override.mode.as_str())));
+    *MODE_OVERRIDE.lock().unwrap() = Some(mode_override);
+}
+
+/// 返回被清除的覆盖 (若有)
+pub fn clear_mode_override() -> Option<ModeOverride> {
+    MODE_OVERRIDE.lock().unwrap().take()
+}
+
+/// 覆盖满足结束条件时清除它，返回被清除的覆盖与结束原因
+fn take_ended_override(current_package: &str) -> Option<(ModeOverride, &'static str)> {
+    let mut guard = MODE_OVERRIDE.lock().unwrap();
+    let reason = guard.as_ref()?.end_reason(current_package)?;
+    guard.take().map(|ended| (ended, reason))
+}
+
+/// 当前模式已不是检测循环上次下发的模式
+pub fn invalidate_last_mode() {
+    LAST_MODE_STALE.store(true, Ordering::SeqCst);
+}
+
+/// 电源是否接入；充满或暂停充电 ("Full" / "Not charging") 也算接入
+pub fn is_power_connected() -> bool {
+    std::fs::read_to_string(utils::host_path("/sys/class/power_supply/battery/status"))
+        .map(|s| s.trim() != "Discharging")
+        .unwrap_or(false)
 }
 
 pub fn get_current_pid() -> i32 {
     CURRENT_PID.load(Ordering::Relaxed)
 }
 
+pub fn get_current_package() -> String {
+    CURRENT_PACKAGE.lock().unwrap().clone()
+}
+
 // 在检测到新包名时更新它
 fn set_current_package(pkg: &str, pid: i32) {
     *CURRENT_PACKAGE.lock().unwrap() = pkg.to_string();
@@ -167,7 +241,47 @@ fn get_focused_app_from_cgroup(ignored_apps: &[String]) -> Result<(String, i32),
 
 // ==================== [辅助函数] ====================
 
+/// 检测循环决定是否下发 ModeChange 的状态
+///
+/// 重新判定的请求保留到真正判定过一次为止：息屏时循环跳过判定，期间到来的请求 (规则重载、覆盖结束)
+/// 不会丢失，亮屏后第一次判定一定下发。
+#[derive(Debug, Default)]
+struct ModeTracker {
+    /// 上次下发的模式；为空表示未知
+    last_mode: String,
+    refresh_pending: bool,
+}
+
+impl ModeTracker {
+    fn request_refresh(&mut self) {
+        self.refresh_pending = true;
+    }
+
+    /// 当前模式已被规则以外的途径切换 (一次性 set-mode)，不能再和上次下发的模式比较
+    fn forget_last_mode(&mut self) {
+        self.last_mode.clear();
+    }
+
+    /// 覆盖结束：它的模式可能从未经过检测循环下发，按规则重新判定并无条件下发
+    fn override_ended(&mut self) {
+        self.forget_last_mode();
+        self.request_refresh();
+    }
+
+    /// 规则给出 new_mode 时是否需要下发；需要时返回上次下发的模式
+    fn decide(&mut self, new_mode: &str) -> Option<String> {
+        let refresh = std::mem::take(&mut self.refresh_pending);
+        if self.last_mode == new_mode && !refresh {
+            return None;
+        }
+        Some(std::mem::replace(&mut self.last_mode, new_mode.to_string()))
+    }
+}
+
 fn determine_mode(config: &RulesConfig, current_package: &str) -> String {
+    if let Some(mode_override) = MODE_OVERRIDE.lock().unwrap().as_ref() {
+        return mode_override.mode.clone();
+    }
     if !config.dynamic_enabled {
         return config.global_mode.clone();
     }
@@ -176,6 +290,7 @@ fn determine_mode(config: &RulesConfig, current_package: &str) -> String {
 
 pub fn get_default_rules() -> RulesConfig {
     RulesConfig {
+        schema_version: ConfigKind::Rules.current_version(),
         yumi_scheduler: true,
         dynamic_enabled: true,
         global_mode: "balance".to_string(),
@@ -183,13 +298,14 @@ pub fn get_default_rules() -> RulesConfig {
         ignored_apps: Vec::new(),
         fas_rules: super::config::FasRulesConfig::default(),
         cpu_load_governor: super::config::CpuLoadGovernorConfig::default(),
+        trace_recorder: super::config::TraceRecorderConfig::default(),
     }
 }
 
 pub fn watch_config_file(
     config_arc: Arc<Mutex<RulesConfig>>,
     force_refresh_arc: Arc<AtomicBool>,
-    tx: Sender<DaemonEvent>
+    tx: Publisher
 ) -> Result<(), Box<dyn Error>> {
     let mut inotify = Inotify::init()?;
     let rules_path = config::get_rules_path();
@@ -205,11 +321,14 @@ pub fn watch_config_file(
             while let Ok(events) = inotify.read_events(&mut buffer) { if events.peekable().peek().is_none() { break; } }
             info!("{}", t("app-detect-reloading"));
             
-            let new_config = config::read_config::<RulesConfig, _>(&rules_path)
-                                .unwrap_or_else(|e| { 
-                                    warn!("{}", t_with_args("app-detect-load-failed", &fluent_args!("error" => e.to_string()))); 
-                                    get_default_rules() 
-                                });
+            // 有错误的 rules.yaml 整体拒绝，继续使用上一份有效规则
+            let new_config = match config_check::load_rules_checked(&rules_path) {
+                Ok(rules) => rules,
+                Err(error) => {
+                    warn!("{}", t_with_args("app-detect-reload-rejected", &fluent_args!("error" => error)));
+                    continue;
+                }
+            };
             
             *config_arc.lock().unwrap() = new_config.clone();
             
@@ -227,13 +346,13 @@ pub fn app_detection_loop(
     config_arc: Arc<Mutex<RulesConfig>>, 
     screen_state_arc: Arc<Mutex<bool>>,
     force_refresh_arc: Arc<AtomicBool>,
-    tx: Sender<DaemonEvent>
+    tx: Publisher
 ) -> Result<(), Box<dyn Error>> {
     info!("{}", t("app-detect-loop-started"));
     
     let temp_sensor_path = utils::find_cpu_temp_path().unwrap_or_default();
     let mut last_package = String::new();
-    let mut last_mode = String::new();
+    let mut tracker = ModeTracker::default();
     let mut last_screen_state = true; 
     
     // 状态机变量：用于无阻塞防抖
@@ -242,12 +361,24 @@ pub fn app_detection_loop(
     let mut debounce_start = Instant::now();
     
     loop {
-        let force_refresh = force_refresh_arc.swap(false, Ordering::SeqCst);
+        if force_refresh_arc.swap(false, Ordering::SeqCst) {
+            tracker.request_refresh();
+        }
+        if LAST_MODE_STALE.swap(false, Ordering::SeqCst) {
+            tracker.forget_last_mode();
+        }
+
+        // 覆盖结束后按规则重新判定模式 (息屏时推迟到亮屏)
+        if let Some((ended, reason)) = take_ended_override(&last_package) {
+            info!("{}", t_with_args("app-detect-override-ended", &fluent_args!("mode" => ended.mode.as_str(), "reason" => reason)));
+            tracker.override_ended();
+        }
         let current_screen_state = { *screen_state_arc.lock().unwrap() };
         
         if current_screen_state != last_screen_state {
             info!("{}", t_with_args("app-detect-screen-changed", &fluent_args!("old" => last_screen_state.to_string(), "new" => current_screen_state.to_string())));
             last_screen_state = current_screen_state;
+            crate::status::set_screen_on(current_screen_state);
             if current_screen_state {
                 last_package.clear();
                 pending_package.clear();
@@ -288,22 +419,21 @@ pub fn app_detection_loop(
             utils::read_f64_from_file(&temp_sensor_path).unwrap_or(0.0) / 1000.0
         } else { 0.0 };
         
-        if last_package != final_pkg || force_refresh {
+        if last_package != final_pkg || tracker.refresh_pending {
             if !final_pkg.is_empty() {
                 set_current_package(&final_pkg, final_pid);
                 // 使用已获取的 config_snapshot，不再重复加锁
                 let new_mode = determine_mode(&config_snapshot, &final_pkg);
 
-                if last_mode != new_mode || force_refresh {
-                    info!("{}", t_with_args("app-detect-mode-change-pkg", &fluent_args!("old" => last_mode.clone(), "new" => new_mode.as_str(), "pkg" => final_pkg.as_str())));
+                if let Some(old_mode) = tracker.decide(&new_mode) {
+                    info!("{}", t_with_args("app-detect-mode-change-pkg", &fluent_args!("old" => old_mode, "new" => new_mode.as_str(), "pkg" => final_pkg.as_str())));
                     // ModeChange 事件现在携带 pid 字段
                     let _ = tx.send(DaemonEvent::ModeChange {
                         package_name: final_pkg.clone(),
                         pid: final_pid,
-                        mode: new_mode.clone(),
+                        mode: new_mode,
                         temperature: current_temp,
                     });
-                    last_mode = new_mode;
                 }
                 last_package = final_pkg;
             }
@@ -311,4 +441,45 @@ pub fn app_detection_loop(
 
         thread::sleep(Duration::from_millis(1500));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_mode_is_not_resent_unless_refreshed() {
+        let mut tracker = ModeTracker::default();
+        assert_eq!(tracker.decide("balance").as_deref(), Some(""));
+        assert_eq!(tracker.decide("balance"), None);
+        assert_eq!(tracker.decide("performance").as_deref(), Some("balance"));
+
+        tracker.request_refresh();
+        assert_eq!(tracker.decide("performance").as_deref(), Some("performance"));
+        assert_eq!(tracker.decide("performance"), None);
+    }
+
+    #[test]
+    fn override_expiring_while_screen_is_off_is_undone_on_screen_on() {
+        let mut tracker = ModeTracker::default();
+        tracker.decide("balance");
+
+        // 息屏时经控制 socket 装上覆盖，模式由 scheduler 直接切到 powersave，检测循环并未下发
+        set_mode_override(ModeOverride {
+            mode: "powersave".to_string(),
+            expires_at_ms: Some(crate::status::now_ms()),
+            until_unplugged: false,
+            until_app_exit: None,
+        });
+        // 覆盖在息屏期间到期；息屏的循环只清除覆盖，不做判定
+        let (ended, reason) = take_ended_override("com.example.app").unwrap();
+        assert_eq!((ended.mode.as_str(), reason), ("powersave", "expired"));
+        tracker.override_ended();
+        assert!(get_mode_override().is_none());
+
+        // 亮屏后规则给出的模式与上次下发的相同，仍要下发以离开 powersave
+        assert_eq!(determine_mode(&get_default_rules(), "com.example.app"), "balance");
+        assert!(tracker.decide("balance").is_some());
+        assert_eq!(tracker.decide("balance"), None);
+    }
+}
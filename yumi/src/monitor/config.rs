@@ -15,8 +15,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
@@ -24,6 +27,8 @@ use serde::de::DeserializeOwned;
 use log::warn;
 use std::path::PathBuf;
 use crate::common;
+use crate::migrate::{self, ConfigKind};
+use crate::scheduler::config::{ClusterDef, TIER_NAMES};
 
 pub fn get_rules_path() -> PathBuf { common::get_module_root().join("rules.yaml") }
 pub fn get_boot_scripts_path() -> PathBuf { common::get_module_root().join("boot_scripts.yaml") }
@@ -33,7 +38,7 @@ pub fn get_scripts_dir() -> PathBuf { common::get_module_root().join("scripts")
 //  PID 系数
 // ════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PidCoefficients {
     #[serde(default = "default_kp")]  pub kp: f32,
     #[serde(default = "default_ki")]  pub ki: f32,
@@ -50,7 +55,7 @@ impl Default for PidCoefficients {
 //  Cluster 配置
 // ════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ClusterProfile {
     #[serde(default = "default_capacity_weight")]
     pub capacity_weight: f32,
@@ -59,13 +64,81 @@ fn default_capacity_weight() -> f32 { 1.0 }
 impl Default for ClusterProfile {
     fn default() -> Self { Self { capacity_weight: 1.0 } }
 }
-pub fn default_cluster_profiles() -> Vec<ClusterProfile> {
-    vec![
-        ClusterProfile { capacity_weight: 1.0 },
-        ClusterProfile { capacity_weight: 1.5 },
-        ClusterProfile { capacity_weight: 2.5 },
-        ClusterProfile { capacity_weight: 3.5 },
-    ]
+
+/// 各 cluster 的 FAS 参数，键为 cluster 名 (见 config.yaml 的 CoreFramework) 或 "policyN"
+///
+/// 旧格式的列表按位置对应 SmallCore / MediumCore / BigCore / SuperBigCore。
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(transparent)]
+pub struct ClusterProfiles(pub BTreeMap<String, ClusterProfile>);
+
+impl ClusterProfiles {
+    pub fn get(&self, cluster: &ClusterDef) -> ClusterProfile {
+        cluster.lookup(|k| self.0.get(k)).cloned().unwrap_or_default()
+    }
+}
+
+impl<'de> Deserialize<'de> for ClusterProfiles {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ProfilesVisitor;
+
+        impl<'de> Visitor<'de> for ProfilesVisitor {
+            type Value = ClusterProfiles;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of cluster name to profile, or a list of profiles")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut map = BTreeMap::new();
+                let mut idx = 0;
+                while let Some(profile) = seq.next_element::<ClusterProfile>()? {
+                    match TIER_NAMES.get(idx) {
+                        Some(name) => { map.insert(name.to_string(), profile); }
+                        None => warn!("cluster_profiles: list entry #{} has no cluster to map to, use the map form instead", idx),
+                    }
+                    idx += 1;
+                }
+                Ok(ClusterProfiles(map))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut map = BTreeMap::new();
+                while let Some((name, profile)) = access.next_entry::<String, ClusterProfile>()? {
+                    map.insert(name, profile);
+                }
+                Ok(ClusterProfiles(map))
+            }
+        }
+
+        deserializer.deserialize_any(ProfilesVisitor)
+    }
+}
+
+/// 与 Deserialize 一致：cluster 名到参数的表，或按档位顺序的列表
+impl JsonSchema for ClusterProfiles {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ClusterProfiles".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let profile = generator.subschema_for::<ClusterProfile>();
+        json_schema!({
+            "description": "键为 cluster 名 (见 config.yaml 的 CoreFramework) 或 \"policyN\"；旧格式的列表按位置对应 SmallCore / MediumCore / BigCore / SuperBigCore",
+            "anyOf": [
+                { "type": "object", "additionalProperties": profile },
+                { "type": "array", "items": profile }
+            ]
+        })
+    }
+}
+
+pub fn default_cluster_profiles() -> ClusterProfiles {
+    let weights = [1.0, 1.5, 2.5, 3.5];
+    ClusterProfiles(TIER_NAMES.iter()
+        .zip(weights)
+        .map(|(name, w)| (name.to_string(), ClusterProfile { capacity_weight: w }))
+        .collect())
 }
 
 // ════════════════════════════════════════════════════════════════
@@ -88,7 +161,7 @@ pub fn default_cluster_profiles() -> Vec<ClusterProfile> {
 ///     target_fps: [60, 90, 120]
 ///     fps_margin: 3.0
 /// ```
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
 pub struct PerAppProfile {
     /// 该游戏会渲染到的目标帧率数组，运行时动态匹配
     /// 例如 [30, 60] 表示游戏可能以 30fps 或 60fps 渲染
@@ -104,7 +177,7 @@ pub struct PerAppProfile {
 //  CPU Load Governor 配置
 // ════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct CpuLoadGovernorConfig {
     /// 是否启用负载调频
     #[serde(default)]
@@ -174,16 +247,48 @@ impl Default for CpuLoadGovernorConfig {
     }
 }
 
+// ════════════════════════════════════════════════════════════════
+//  帧 trace 录制配置
+// ════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct TraceRecorderConfig {
+    /// 需要录制的包名，FAS 会话期间把帧 / 负载 / 温度 / 模式变化写到 logs/traces/
+    #[serde(default)]
+    pub packages: Vec<String>,
+
+    /// logs/traces/ 下最多保留的 trace 文件数，超出时删除最旧的；0 = 不限制
+    #[serde(default = "d_trace_max_files")]
+    pub max_files: usize,
+
+    /// 单个 trace 文件的大小上限 (MB)，达到后本次会话停止录制
+    #[serde(default = "d_trace_max_file_mb")]
+    pub max_file_mb: u64,
+}
+
+fn d_trace_max_files() -> usize { 10 }
+fn d_trace_max_file_mb() -> u64 { 32 }
+
+impl Default for TraceRecorderConfig {
+    fn default() -> Self {
+        Self {
+            packages: Vec::new(),
+            max_files: d_trace_max_files(),
+            max_file_mb: d_trace_max_file_mb(),
+        }
+    }
+}
+
 // ════════════════════════════════════════════════════════════════
 //  FAS Rules 配置
 // ════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct FasRulesConfig {
     #[serde(default = "default_fps_gears")]       pub fps_gears: Vec<f32>,
     #[serde(default = "default_fps_margin")]       pub fps_margin: String,
     #[serde(default)]                              pub pid: PidCoefficients,
-    #[serde(default = "default_cluster_profiles")] pub cluster_profiles: Vec<ClusterProfile>,
+    #[serde(default = "default_cluster_profiles")] pub cluster_profiles: ClusterProfiles,
     #[serde(default = "d_auto_cap")]               pub auto_capacity_weight: bool,
 
     #[serde(default = "d_perf_floor")]   pub perf_floor: f32,
@@ -234,9 +339,6 @@ pub struct FasRulesConfig {
     #[serde(default)]
     pub per_app_profiles: HashMap<String, PerAppProfile>,
 
-    #[serde(default)]
-    pub per_app_margins: HashMap<String, f32>,
-
     /// 温度降频阈值（℃），0 = 禁用
     #[serde(default = "d_temp_thresh")]
     pub core_temp_threshold: f64,
@@ -290,18 +392,6 @@ fn d_temp_thresh() -> f64 { 0.0 }
 fn d_temp_perf() -> f32 { 0.70 }
 fn d_util_cap_divisor() -> f32 { 0.45 }
 
-impl FasRulesConfig {
-    /// 将旧的 per_app_margins 迁移到 per_app_profiles
-    pub fn migrate_legacy_margins(&mut self) {
-        for (pkg, margin) in self.per_app_margins.drain() {
-            self.per_app_profiles
-                .entry(pkg)
-                .or_default()
-                .fps_margin = Some(margin);
-        }
-    }
-}
-
 impl Default for FasRulesConfig {
     fn default() -> Self {
         Self {
@@ -336,7 +426,6 @@ impl Default for FasRulesConfig {
             fixed_max_frame_ms: d_max_frame(), cold_boot_ms: d_cold_ms(),
             verify_freq_interval_secs: d_verify_interval(),
             per_app_profiles: HashMap::new(),
-            per_app_margins: HashMap::new(),
             core_temp_threshold: d_temp_thresh(),
             core_temp_throttle_perf: d_temp_perf(),
             util_cap_divisor: d_util_cap_divisor(),
@@ -350,8 +439,10 @@ impl Default for FasRulesConfig {
 
 fn default_true() -> bool { true }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
 pub struct RulesConfig {
+    /// 文件格式版本，旧版本在加载时迁移 (见 migrate.rs)
+    #[serde(default)] pub schema_version: u32,
     #[serde(default = "default_true")] pub yumi_scheduler: bool,
     pub dynamic_enabled: bool,
     pub global_mode: String,
@@ -359,6 +450,7 @@ pub struct RulesConfig {
     #[serde(default)] pub ignored_apps: Vec<String>,
     #[serde(default)] pub fas_rules: FasRulesConfig,
     #[serde(default)] pub cpu_load_governor: CpuLoadGovernorConfig,
+    #[serde(default)] pub trace_recorder: TraceRecorderConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -374,14 +466,30 @@ where T: DeserializeOwned + Default, P: AsRef<std::path::Path>,
         Ok(mut file) => {
             let mut s = String::new();
             file.read_to_string(&mut s)?;
-            serde_yaml::from_str(&s).or_else(|e| {
-                warn!("[Config] Parse error {}: {}. Default.", path_ref.display(), e);
-                Ok(T::default())
-            })
+            parse_or_default(path_ref, &s)
         }
         Err(_) => {
             warn!("[Config] Not found: {}. Default.", path_ref.display());
             Ok(T::default())
         }
     }
+}
+
+/// 读取 rules.yaml，旧版本的文件先在内存中迁移
+pub fn read_rules<P: AsRef<std::path::Path>>(path: P) -> Result<RulesConfig, Box<dyn Error>> {
+    let path_ref = path.as_ref();
+    match migrate::read(ConfigKind::Rules, path_ref) {
+        Ok(s) => parse_or_default(path_ref, &s),
+        Err(_) => {
+            warn!("[Config] Not found: {}. Default.", path_ref.display());
+            Ok(RulesConfig::default())
+        }
+    }
+}
+
+fn parse_or_default<T: DeserializeOwned + Default>(path: &std::path::Path, content: &str) -> Result<T, Box<dyn Error>> {
+    serde_yaml::from_str(content).or_else(|e| {
+        warn!("[Config] Parse error {}: {}. Default.", path.display(), e);
+        Ok(T::default())
+    })
 }
\ No newline at end of file
@@ -2,19 +2,21 @@ use aya::{Ebpf, include_bytes_aligned, programs::TracePoint};
 use aya::maps::PerCpuArray;
 use aya::maps::HashMap as BpfHashMap;
 use aya::util::online_cpus;
-use std::sync::mpsc::Sender;
+use crate::bus::Publisher;
+use crate::monitor::supervisor::{self, SourceHandle};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use crate::common::DaemonEvent;
 use crate::monitor::app_detect;
 use log::{info, warn, debug};
 use std::time::Instant;
+use tokio::task::JoinSet;
 
 /// 从 /proc/{pid}/task/ 读取前台进程的所有线程 TID
 fn get_thread_tids(pid: u32) -> Vec<u32> {
     let task_dir = format!("/proc/{}/task", pid);
     let mut tids = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(&task_dir) {
+    if let Ok(entries) = std::fs::read_dir(crate::utils::host_path(&task_dir)) {
         for entry in entries.flatten() {
             if let Some(name) = entry.file_name().to_str() {
                 if let Ok(tid) = name.parse::<u32>() {
@@ -26,13 +28,10 @@ fn get_thread_tids(pid: u32) -> Vec<u32> {
     tids
 }
 
-pub async fn start_cpu_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error> {
+pub async fn start_cpu_loop(tx: Publisher, source: SourceHandle) -> Result<(), anyhow::Error> {
     static BPF_DATA: &[u8] = include_bytes_aligned!(env!("BPF_CPU_OBJ_PATH"));
     
-    // Box::leak 将 Ebpf 提升为 'static 生命周期，
-    // 使得从中借出的 Map 引用可以安全地 move 进 tokio::spawn
-    // （与 fps_monitor.rs 中的做法一致）
-    let bpf = Box::leak(Box::new(Ebpf::load(BPF_DATA)?));
+    let mut bpf = Ebpf::load(BPF_DATA)?;
     let program: &mut TracePoint = bpf.program_mut("handle_sched_switch").unwrap().try_into()?;
     program.load()?;
     program.attach("sched", "sched_switch")?;
@@ -44,32 +43,28 @@ pub async fn start_cpu_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
     let num_cpus = num_cpus.min(16);
     info!("Detected {} online CPU cores for monitoring.", num_cpus);
 
-    // 先取裸指针，再通过 unsafe 分别获取各个 map
-    // 这样避免对 bpf 产生多次 &mut 借用冲突
-    // 安全性：每个 map 是独立的内核对象，内存不重叠，且后续不再直接访问 bpf 本体
-    let bpf_ptr = bpf as *mut Ebpf;
+    // 用 take_map 取出各个 map 的所有权 move 进读取任务；bpf 本体留在本函数中，
+    // 任何一步失败或数据源退出时随之 drop，program 自动 detach，监管线程重试不会泄漏
+    let take_map = |bpf: &mut Ebpf, name: &str| bpf.take_map(name)
+        .ok_or_else(|| anyhow::anyhow!("{} map not found", name));
 
     // core_idle_time: BPF_MAP_TYPE_PERCPU_ARRAY
-    let core_idle_map: PerCpuArray<_, u64> = PerCpuArray::try_from(
-        unsafe { &mut *bpf_ptr }.map_mut("core_idle_time").unwrap()
-    )?;
+    let core_idle_map: PerCpuArray<_, u64> = PerCpuArray::try_from(take_map(&mut bpf, "core_idle_time")?)?;
 
     // core_busy_time: BPF_MAP_TYPE_PERCPU_ARRAY
     // 与 idle 对称，用于区分"真正空闲"和"深度休眠"
-    let core_busy_map: PerCpuArray<_, u64> = PerCpuArray::try_from(
-        unsafe { &mut *bpf_ptr }.map_mut("core_busy_time").unwrap()
-    )?;
+    let core_busy_map: PerCpuArray<_, u64> = PerCpuArray::try_from(take_map(&mut bpf, "core_busy_time")?)?;
 
-    let thread_run_map: BpfHashMap<_, u32, u64> = BpfHashMap::try_from(
-        unsafe { &mut *bpf_ptr }.map_mut("thread_run_time").unwrap()
-    )?;
+    let thread_run_map: BpfHashMap<_, u32, u64> = BpfHashMap::try_from(take_map(&mut bpf, "thread_run_time")?)?;
 
     // 追踪前台 PID，用于查询其线程的运行时间
     let shared_pid = Arc::new(AtomicU32::new(app_detect::get_current_pid() as u32));
     let pid_arc = shared_pid.clone();
 
+    let mut tasks = JoinSet::new();
+
     // 独立轻量任务：定期同步前台 PID
-    tokio::spawn(async move {
+    tasks.spawn(async move {
         let mut last_pid: u32 = 0;
         loop {
             tokio::time::sleep(std::time::Duration::from_millis(500)).await;
@@ -82,7 +77,7 @@ pub async fn start_cpu_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
         }
     });
 
-    tokio::spawn(async move {
+    tasks.spawn(async move {
         // 全局核心历史数据
         let mut last_idle_times = vec![0u64; num_cpus];
         // busy 时间历史快照
@@ -199,11 +194,11 @@ pub async fn start_cpu_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
                 foreground_max_util,
             }).is_err() {
                 warn!("CPU monitor: channel closed, exiting loop.");
-                break;
+                return Ok(());
             }
         }
     });
 
-    std::future::pending::<()>().await;
-    Ok(())
+    source.ready();
+    supervisor::join_readers(&mut tasks).await
 }
\ No newline at end of file
@@ -19,18 +19,22 @@
 use aya::{Ebpf, include_bytes_aligned, programs::UProbe, maps::perf::AsyncPerfEventArray};
 use aya::util::online_cpus;
 use bytes::BytesMut;
-use std::sync::mpsc::Sender;
+use crate::bus::Publisher;
+use crate::monitor::supervisor::{self, SourceHandle};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use tokio::task::JoinSet;
 use crate::common::DaemonEvent;
 use crate::monitor::app_detect;
 use log::{info, debug, warn};
 
-pub async fn start_fps_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error> {
+pub async fn start_fps_loop(tx: Publisher, source: SourceHandle) -> Result<(), anyhow::Error> {
     static BPF_DATA: &[u8] = include_bytes_aligned!(env!("BPF_FPS_OBJ_PATH"));
     info!("Initializing eBPF FPS monitor...");
 
-    let bpf = Box::leak(Box::new(Ebpf::load(BPF_DATA)?));
+    // bpf 本体留在本函数中，任何一步失败或数据源退出时随之 drop，
+    // program 自动 detach，监管线程重试不会泄漏 eBPF 对象
+    let mut bpf = Ebpf::load(BPF_DATA)?;
     let program: &mut UProbe = bpf.program_mut("handle_frame").unwrap().try_into()?;
     program.load()?;
     
@@ -53,23 +57,23 @@ pub async fn start_fps_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
         return Err(anyhow::anyhow!("Failed to attach any Uprobe symbols!"));
     }
 
-    let bpf_ptr = bpf as *mut Ebpf;
-    let mut target_pid_arr = if let Some(map) = unsafe { &mut *bpf_ptr }.map_mut("target_pid") {
-        aya::maps::Array::<_, u32>::try_from(map).ok()
-    } else {
-        None
-    };
+    // map 用 take_map 取出所有权 move 进读取任务
+    let mut target_pid_arr = bpf.take_map("target_pid")
+        .and_then(|map| aya::maps::Array::<_, u32>::try_from(map).ok());
 
     let has_kernel_filter = target_pid_arr.is_some();
-    let map = unsafe { &mut *bpf_ptr }.map_mut("frame_events").expect("frame_events map not found");
+    let map = bpf.take_map("frame_events")
+        .ok_or_else(|| anyhow::anyhow!("frame_events map not found"))?;
     let mut perf_array = AsyncPerfEventArray::try_from(map)?;
+    let cpus = online_cpus().map_err(|e| anyhow::anyhow!("CPU access error: {:?}", e))?;
 
     let shared_pid = Arc::new(AtomicU32::new(app_detect::get_current_pid() as u32));
+    let mut tasks = JoinSet::new();
 
     // 独立轻量任务：仅负责更新内核 BPF Map 和 PID 原子变量
     {
         let pid_arc = shared_pid.clone();
-        tokio::spawn(async move {
+        tasks.spawn(async move {
             let mut last_pid: u32 = 0;
             loop {
                 tokio::time::sleep(std::time::Duration::from_millis(500)).await;
@@ -87,43 +91,38 @@ pub async fn start_fps_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
         });
     }
 
-    for cpu_id in online_cpus().map_err(|e| anyhow::anyhow!("CPU access error: {:?}", e))? {
+    for cpu_id in cpus {
         let mut buf = perf_array.open(cpu_id, None)?;
         let tx_clone = tx.clone();
         let pid_arc = shared_pid.clone();
 
-        tokio::spawn(async move {
+        tasks.spawn(async move {
             let mut buffers = vec![BytesMut::with_capacity(1024); 10];
             loop {
-                match buf.read_events(&mut buffers).await {
-                    Ok(events) => {
-                        for i in 0..events.read {
-                            let data = &buffers[i];
-                            if data.len() < 12 { continue; }
-
-                            let event_pid = u32::from_ne_bytes(data[0..4].try_into().unwrap());
-                            let delta = u64::from_ne_bytes(data[4..12].try_into().unwrap());
-
-                            if delta == 0 || event_pid != pid_arc.load(Ordering::Relaxed) { continue; }
-
-                            let fps = 1_000_000_000.0 / (delta as f64);
-
-                            // 不再检查 package_name 或在热路径中做 String clone
-                            // FAS 控制器已通过 set_game() 持有自己的包名缓存
-                            // 只检查 PID 是否有效即可
-                            if event_pid == 0 { continue; }
-
-                            // FrameUpdate 不再携带 package_name，避免 144fps 下每帧一次 String clone
-                            if tx_clone.send(DaemonEvent::FrameUpdate {
-                                fps: fps as f32,
-                                frame_delta_ns: delta,
-                            }).is_err() {
-                                return;
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                let events = buf.read_events(&mut buffers).await
+                    .map_err(|e| anyhow::anyhow!("perf buffer read failed on CPU {}: {}", cpu_id, e))?;
+                for i in 0..events.read {
+                    let data = &buffers[i];
+                    if data.len() < 12 { continue; }
+
+                    let event_pid = u32::from_ne_bytes(data[0..4].try_into().unwrap());
+                    let delta = u64::from_ne_bytes(data[4..12].try_into().unwrap());
+
+                    if delta == 0 || event_pid != pid_arc.load(Ordering::Relaxed) { continue; }
+
+                    let fps = 1_000_000_000.0 / (delta as f64);
+
+                    // 不再检查 package_name 或在热路径中做 String clone
+                    // FAS 控制器已通过 set_game() 持有自己的包名缓存
+                    // 只检查 PID 是否有效即可
+                    if event_pid == 0 { continue; }
+
+                    // FrameUpdate 不再携带 package_name，避免 144fps 下每帧一次 String clone
+                    if tx_clone.send(DaemonEvent::FrameUpdate {
+                        fps: fps as f32,
+                        frame_delta_ns: delta,
+                    }).is_err() {
+                        return Ok(());
                     }
                 }
             }
@@ -132,7 +131,6 @@ pub async fn start_fps_loop(tx: Sender<DaemonEvent>) -> Result<(), anyhow::Error
 
     info!("eBPF FPS monitor started successfully (kernel PID filter: {}).",
         if has_kernel_filter { "active" } else { "disabled" });
-    
-    std::future::pending::<()>().await;
-    Ok(())
+    source.ready();
+    supervisor::join_readers(&mut tasks).await
 }
\ No newline at end of file
@@ -19,7 +19,7 @@ use std::error::Error;
 use std::thread;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::AtomicBool;
-use std::sync::mpsc::Sender;
+use crate::bus::Publisher;
 use log::{error, info};
 
 pub mod boot;
@@ -28,12 +28,12 @@ pub mod app_detect;
 pub mod screen_detect;
 pub mod fps_monitor;
 pub mod cpu_monitor;
+pub mod supervisor;
 
-use crate::common::DaemonEvent;
 use crate::i18n::t;
 
 // 启动函数
-pub fn start_monitor(tx: Sender<DaemonEvent>) -> Result<(), Box<dyn Error>> {
+pub fn start_monitor(tx: Publisher) -> Result<(), Box<dyn Error>> {
     info!("{}", t("monitor-starting"));
 
     // 2. 执行开机脚本
@@ -45,7 +45,7 @@ pub fn start_monitor(tx: Sender<DaemonEvent>) -> Result<(), Box<dyn Error>> {
     let rules_path = config::get_rules_path();
     
     // --- 初始化配置 ---
-    let initial_config = config::read_config(&rules_path) 
+    let initial_config = config::read_rules(&rules_path) 
                             .unwrap_or_else(|e| {
                                 log::warn!("[Main] Failed to read initial config: {}. Using default.", e);
                                 app_detect::get_default_rules()
@@ -86,39 +86,21 @@ pub fn start_monitor(tx: Sender<DaemonEvent>) -> Result<(), Box<dyn Error>> {
             }
         })?;
 
-    // 5. 启动 eBPF FPS 监控线程 (带有独立的 Tokio 运行时)
-    let tx_fps = tx.clone();
-    thread::Builder::new()
-        .name("fps_monitor_ebpf".to_string())
-        .spawn(move || {
-            if let Ok(rt) = tokio::runtime::Runtime::new() {
-                rt.block_on(async {
-                    if let Err(e) = fps_monitor::start_fps_loop(tx_fps).await {
-                        error!("FPS Monitor crashed: {}", e);
-                    }
-                });
-            } else {
-                error!("Failed to create Tokio runtime for FPS monitor");
-            }
-        })?;
+    // 5/6. 启动 eBPF FPS 与 CPU 负载数据源 (共享 Tokio 运行时，失败后退避重启)
+    let supervisor = supervisor::Supervisor::new(tx.clone())?;
+    supervisor.supervise(supervisor::FPS_SOURCE, fps_monitor::start_fps_loop)?;
+    supervisor.supervise(supervisor::CPU_SOURCE, cpu_monitor::start_cpu_loop)?;
 
-    // 6. 启动 eBPF CPU 负载监控线程
-    let tx_cpu = tx.clone();
-    thread::Builder::new()
-        .name("cpu_monitor_ebpf".to_string())
-        .spawn(move || {
-            if let Ok(rt) = tokio::runtime::Runtime::new() {
-                rt.block_on(async {
-                    if let Err(e) = cpu_monitor::start_cpu_loop(tx_cpu).await {
-                        error!("CPU Monitor crashed: {}", e);
-                    }
-                });
-            } else {
-                error!("Failed to create Tokio runtime for CPU monitor");
-            }
-        })?;
+    // 7. 启动本地控制 socket
+    if let Err(e) = crate::control::start_control_server(
+        tx.clone(),
+        Arc::clone(&config_arc),
+        Arc::clone(&force_refresh_arc),
+    ) {
+        error!("[Main] Control server failed to start: {}", e);
+    }
 
-    // 7. 启动应用检测主循环 (阻塞)
+    // 8. 启动应用检测主循环 (阻塞)
     app_detect::app_detection_loop(
         config_arc,
         screen_state_clone_for_app_detect,
@@ -31,7 +31,7 @@ use crate::fluent_args;
 
 fn read_int_file(path: &str) -> Result<i32, Box<dyn Error>> {
     let mut content = String::new();
-    File::open(path)?.read_to_string(&mut content)?;
+    File::open(crate::utils::host_path(path))?.read_to_string(&mut content)?;
     Ok(content.trim().parse()?)
 }
 
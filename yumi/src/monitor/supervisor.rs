@@ -0,0 +1,193 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! eBPF 数据源监管
+//!
+//! FPS / CPU 负载两个数据源共用一个 Tokio 运行时。每个数据源由一个轻量的
+//! 监管线程驱动：启动失败或中途退出后按指数退避重启，状态写入全局表
+//! (state.json 与 metrics 读取)，并通过 [`DaemonEvent::SourceHealth`]
+//! 通知 scheduler 数据源的上下线。
+//!
+//! 数据源的 future 在监管线程上用 `Handle::block_on` 驱动，持有 `Ebpf` 对象直到
+//! 返回；读取任务放在 [`JoinSet`] 中运行在共享运行时上，任一任务退出时
+//! [`join_readers`] 返回 Err，数据源随之退出，program 与剩余任务一并释放。
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use log::{error, info};
+
+use crate::bus::Publisher;
+use crate::common::DaemonEvent;
+use crate::fluent_args;
+use crate::i18n::t_with_args;
+use crate::status;
+
+pub const FPS_SOURCE: &str = "fps";
+pub const CPU_SOURCE: &str = "cpu_load";
+
+/// 首次重启前的等待时间，之后每次翻倍
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// 运行超过这么久才退出的，视为偶发故障，退避从头计算
+const BACKOFF_RESET_AFTER: Duration = Duration::from_secs(60);
+
+lazy_static::lazy_static! {
+    static ref SOURCES: Mutex<BTreeMap<&'static str, SourceStatus>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceState {
+    /// 正在加载 / attach eBPF 程序
+    Starting,
+    Running,
+    /// 已失败，等待下一次重启
+    Backoff,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub name: &'static str,
+    pub state: SourceState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// 进入当前状态的时间 (Unix 毫秒)
+    pub since_ms: u64,
+    /// Backoff 状态下预计的下一次重启时间
+    pub retry_at_ms: Option<u64>,
+}
+
+/// 所有数据源的当前状态
+pub fn snapshot() -> Vec<SourceStatus> {
+    SOURCES.lock().unwrap().values().cloned().collect()
+}
+
+fn update(name: &'static str, f: impl FnOnce(&mut SourceStatus)) {
+    let mut sources = SOURCES.lock().unwrap();
+    let entry = sources.entry(name).or_insert_with(|| SourceStatus {
+        name,
+        state: SourceState::Starting,
+        restarts: 0,
+        last_error: None,
+        since_ms: status::now_ms(),
+        retry_at_ms: None,
+    });
+    f(entry);
+}
+
+/// 交给数据源的句柄，初始化完成后调用 [`SourceHandle::ready`]
+pub struct SourceHandle {
+    name: &'static str,
+    tx: Publisher,
+}
+
+impl SourceHandle {
+    pub fn ready(&self) {
+        update(self.name, |s| {
+            s.state = SourceState::Running;
+            s.since_ms = status::now_ms();
+            s.retry_at_ms = None;
+        });
+        let _ = self.tx.send(DaemonEvent::SourceHealth { source: self.name.to_string(), healthy: true });
+    }
+}
+
+/// 等待数据源的读取任务，任一任务退出 (出错、panic 或通道关闭) 即返回 Err
+///
+/// 返回时 `tasks` 随调用方 drop，其余任务被 abort。
+pub async fn join_readers(tasks: &mut JoinSet<Result<()>>) -> Result<()> {
+    match tasks.join_next().await {
+        Some(Ok(Ok(()))) => Err(anyhow::anyhow!("reader task exited")),
+        Some(Ok(Err(e))) => Err(e),
+        Some(Err(e)) => Err(anyhow::anyhow!("reader task panicked: {}", e)),
+        None => Err(anyhow::anyhow!("no reader task running")),
+    }
+}
+
+pub struct Supervisor {
+    runtime: tokio::runtime::Runtime,
+    tx: Publisher,
+}
+
+impl Supervisor {
+    pub fn new(tx: Publisher) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("monitor_rt")
+            .enable_all()
+            .build()?;
+        Ok(Self { runtime, tx })
+    }
+
+    /// 在独立的监管线程上运行数据源，退出后按退避策略重启
+    pub fn supervise<F, Fut>(&self, name: &'static str, start: F) -> Result<()>
+    where
+        F: Fn(Publisher, SourceHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>>,
+    {
+        let handle = self.runtime.handle().clone();
+        let tx = self.tx.clone();
+        update(name, |_| {});
+
+        thread::Builder::new()
+            .name(format!("supervise_{}", name))
+            .spawn(move || {
+                let mut backoff = BACKOFF_INITIAL;
+                loop {
+                    update(name, |s| {
+                        s.state = SourceState::Starting;
+                        s.since_ms = status::now_ms();
+                        s.retry_at_ms = None;
+                    });
+                    let started = Instant::now();
+                    let source = SourceHandle { name, tx: tx.clone() };
+                    let error = match handle.block_on(start(tx.clone(), source)) {
+                        Ok(()) => "exited unexpectedly".to_string(),
+                        Err(e) => e.to_string(),
+                    };
+
+                    if started.elapsed() >= BACKOFF_RESET_AFTER {
+                        backoff = BACKOFF_INITIAL;
+                    }
+                    error!("{}", t_with_args("monitor-source-failed", &fluent_args!(
+                        "source" => name, "error" => error.as_str(), "secs" => backoff.as_secs()
+                    )));
+                    update(name, |s| {
+                        s.state = SourceState::Backoff;
+                        s.last_error = Some(error.clone());
+                        s.since_ms = status::now_ms();
+                        s.retry_at_ms = Some(s.since_ms + backoff.as_millis() as u64);
+                    });
+                    if tx.send(DaemonEvent::SourceHealth { source: name.to_string(), healthy: false }).is_err() {
+                        return;
+                    }
+
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(BACKOFF_MAX);
+                    update(name, |s| s.restarts += 1);
+                    info!("{}", t_with_args("monitor-source-restarting", &fluent_args!("source" => name)));
+                }
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! FAS 会话录制 (logs/traces/)
+//!
+//! 对 rules.yaml 中 `trace_recorder.packages` 列出的应用，在 FAS 会话期间把
+//! scheduler 喂给 FasController 的原始输入——每一帧的间隔、负载采样、温度与
+//! 模式变化——按 [`crate::trace`] 格式逐条写出，可以直接交给 `yumi replay` 复现。
+//!
+//! 录制在 scheduler 线程上同步进行，经 BufWriter 缓冲，每秒最多 flush 一次。
+//! 写入失败或文件达到大小上限时只结束本次录制，不影响调度。
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use log::{info, warn};
+
+use crate::common;
+use crate::monitor::config::TraceRecorderConfig;
+use crate::scheduler::fas::PolicyController;
+use crate::status;
+use crate::trace::{TraceHeader, TracePolicy, TraceRecord, TRACE_VERSION};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// trace 文件目录
+pub fn traces_dir() -> PathBuf {
+    common::get_module_root().join("logs/traces")
+}
+
+struct Session {
+    out: BufWriter<File>,
+    path: PathBuf,
+    package: String,
+    start: Instant,
+    last_flush: Instant,
+    /// 序列化缓冲，避免每帧分配
+    buf: Vec<u8>,
+    bytes: u64,
+    limit: u64,
+    frames: u64,
+    last_temp: Option<f64>,
+}
+
+impl Session {
+    fn t_ns(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    /// 写入一条记录；返回 false 表示本次录制应当结束
+    fn write(&mut self, record: &TraceRecord) -> bool {
+        self.buf.clear();
+        if serde_json::to_writer(&mut self.buf, record).is_err() {
+            return true;
+        }
+        self.buf.push(b'\n');
+        if self.bytes + self.buf.len() as u64 > self.limit {
+            warn!("Trace recorder: {} reached the {} MB limit, recording stopped",
+                self.path.display(), self.limit / (1024 * 1024));
+            return false;
+        }
+        if let Err(e) = self.out.write_all(&self.buf) {
+            warn!("Trace recorder: write to {} failed: {}", self.path.display(), e);
+            return false;
+        }
+        self.bytes += self.buf.len() as u64;
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.last_flush = Instant::now();
+            if let Err(e) = self.out.flush() {
+                warn!("Trace recorder: flush {} failed: {}", self.path.display(), e);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// scheduler 持有的录制器，同一时刻最多一个会话
+#[derive(Default)]
+pub struct SessionRecorder {
+    session: Option<Session>,
+}
+
+impl SessionRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// FAS 为 `package` 建立新会话时调用；不在录制列表中的应用直接忽略。
+    /// 进行中的录制会先被关闭。
+    pub fn start(&mut self, cfg: &TraceRecorderConfig, package: &str, policies: &[PolicyController], temperature: f64) {
+        self.start_in(&traces_dir(), cfg, package, policies, temperature);
+    }
+
+    fn start_in(&mut self, dir: &Path, cfg: &TraceRecorderConfig, package: &str,
+        policies: &[PolicyController], temperature: f64)
+    {
+        self.stop();
+        if package.is_empty() || !cfg.packages.iter().any(|p| p == package) {
+            return;
+        }
+
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("Trace recorder: cannot create {}: {}", dir.display(), e);
+            return;
+        }
+        // 先为新文件腾出位置
+        if cfg.max_files > 0 {
+            prune(dir, cfg.max_files - 1);
+        }
+
+        let started_ms = status::now_ms();
+        let path = dir.join(format!("{}-{}.jsonl", package, started_ms));
+        let file = match File::create(&path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Trace recorder: cannot create {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let mut session = Session {
+            out: BufWriter::new(file),
+            path,
+            package: package.to_string(),
+            start: now,
+            last_flush: now,
+            buf: Vec::with_capacity(256),
+            bytes: 0,
+            limit: cfg.max_file_mb.max(1) * 1024 * 1024,
+            frames: 0,
+            last_temp: None,
+        };
+        let header = TraceHeader {
+            version: TRACE_VERSION,
+            package: package.to_string(),
+            policies: policies.iter().map(|p| TracePolicy {
+                id: p.policy_id,
+                freqs: p.available_freqs.clone(),
+                capacity_weight: Some(p.cluster_profile.capacity_weight),
+            }).collect(),
+            started_ms: Some(started_ms),
+        };
+        if !session.write(&TraceRecord::Header(header)) {
+            return;
+        }
+        info!("Trace recorder: recording {} to {}", package, session.path.display());
+        self.session = Some(session);
+        self.mode("fas");
+        self.temp(temperature);
+    }
+
+    /// 结束录制并落盘
+    pub fn stop(&mut self) {
+        let Some(mut session) = self.session.take() else { return };
+        if let Err(e) = session.out.flush() {
+            warn!("Trace recorder: flush {} failed: {}", session.path.display(), e);
+        }
+        info!("Trace recorder: closed {} ({} frames, {:.1}s, {} KB)",
+            session.path.display(), session.frames,
+            session.start.elapsed().as_secs_f32(), session.bytes / 1024);
+    }
+
+    pub fn frame(&mut self, delta_ns: u64) {
+        self.record(|s| {
+            s.frames += 1;
+            Some(TraceRecord::Frame { t_ns: s.t_ns(), delta_ns })
+        });
+    }
+
+    pub fn load(&mut self, core_utils: &[f32], fg_util: f32) {
+        self.record(|s| Some(TraceRecord::Load { t_ns: s.t_ns(), core_utils: core_utils.to_vec(), fg_util }));
+    }
+
+    /// 温度没有变化时不写
+    pub fn temp(&mut self, celsius: f64) {
+        self.record(|s| {
+            if s.last_temp == Some(celsius) {
+                return None;
+            }
+            s.last_temp = Some(celsius);
+            Some(TraceRecord::Temp { t_ns: s.t_ns(), celsius })
+        });
+    }
+
+    pub fn mode(&mut self, mode: &str) {
+        self.record(|s| Some(TraceRecord::Mode { t_ns: s.t_ns(), mode: mode.to_string() }));
+    }
+
+    fn record(&mut self, make: impl FnOnce(&mut Session) -> Option<TraceRecord>) {
+        let Some(session) = self.session.as_mut() else { return };
+        let Some(record) = make(session) else { return };
+        if !session.write(&record) {
+            let package = session.package.clone();
+            self.stop();
+            warn!("Trace recorder: recording of {} ended early", package);
+        }
+    }
+}
+
+/// 只保留最新的 `keep` 个 trace 文件
+fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if files.len() <= keep {
+        return;
+    }
+    files.sort_by_key(|(mtime, _)| *mtime);
+    for (_, path) in &files[..files.len() - keep] {
+        if let Err(e) = fs::remove_file(path) {
+            warn!("Trace recorder: cannot remove old trace {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::config::{ClusterProfile, FasRulesConfig};
+    use crate::scheduler::fas::FasController;
+    use crate::trace::read_trace;
+
+    const PACKAGE: &str = "com.example.game";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yumi-recorder-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config() -> TraceRecorderConfig {
+        TraceRecorderConfig { packages: vec![PACKAGE.to_string()], ..Default::default() }
+    }
+
+    fn mock_policies() -> Vec<PolicyController> {
+        let mut fas = FasController::new();
+        fas.load_mock_policies(&FasRulesConfig::default(), &[
+            (0, vec![300_000, 1_000_000, 1_800_000], ClusterProfile { capacity_weight: 0.4 }),
+            (4, vec![600_000, 1_800_000, 2_800_000], ClusterProfile { capacity_weight: 1.0 }),
+        ]);
+        fas.policies
+    }
+
+    fn trace_files(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir).map(|entries| entries.flatten().map(|e| e.path()).collect()).unwrap_or_default()
+    }
+
+    #[test]
+    fn recorded_session_reads_back() {
+        let dir = temp_dir("round-trip");
+        let mut recorder = SessionRecorder::new();
+        recorder.start_in(&dir, &config(), PACKAGE, &mock_policies(), 38.0);
+        recorder.frame(16_666_667);
+        recorder.load(&[0.25, 0.5], 0.75);
+        recorder.temp(38.0);
+        recorder.temp(41.5);
+        recorder.mode("balance");
+        recorder.frame(33_333_333);
+        recorder.stop();
+
+        let files = trace_files(&dir);
+        assert_eq!(files.len(), 1);
+        let trace = read_trace(&files[0]).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(trace.header.version, TRACE_VERSION);
+        assert_eq!(trace.header.package, PACKAGE);
+        assert!(trace.header.started_ms.is_some());
+        let policies: Vec<(usize, Vec<u32>, Option<f32>)> = trace.header.policies.iter()
+            .map(|p| (p.id, p.freqs.clone(), p.capacity_weight))
+            .collect();
+        assert_eq!(policies, vec![
+            (0, vec![300_000, 1_000_000, 1_800_000], Some(0.4)),
+            (4, vec![600_000, 1_800_000, 2_800_000], Some(1.0)),
+        ]);
+
+        // 未变化的温度不重复写入；时间戳单调，读回后顺序不变
+        let records: Vec<String> = trace.records.iter().map(|r| match r {
+            TraceRecord::Frame { delta_ns, .. } => format!("frame {}", delta_ns),
+            TraceRecord::Load { core_utils, fg_util, .. } => format!("load {:?} {}", core_utils, fg_util),
+            TraceRecord::Temp { celsius, .. } => format!("temp {}", celsius),
+            TraceRecord::Mode { mode, .. } => format!("mode {}", mode),
+            TraceRecord::Header(_) => "header".to_string(),
+        }).collect();
+        assert_eq!(records, [
+            "mode fas", "temp 38", "frame 16666667", "load [0.25, 0.5] 0.75",
+            "temp 41.5", "mode balance", "frame 33333333",
+        ]);
+        assert!(trace.records.windows(2).all(|w| w[0].t_ns() <= w[1].t_ns()));
+    }
+
+    #[test]
+    fn unlisted_packages_are_not_recorded() {
+        let dir = temp_dir("unlisted");
+        let mut recorder = SessionRecorder::new();
+        recorder.start_in(&dir, &config(), "com.example.other", &mock_policies(), 38.0);
+        recorder.frame(16_666_667);
+        recorder.stop();
+        assert!(trace_files(&dir).is_empty());
+    }
+}
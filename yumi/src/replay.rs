@@ -0,0 +1,280 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 离线帧 trace 回放 (`yumi replay`)
+//!
+//! 不需要设备：按 trace 顺序调用 FasController 的 `update_frame` /
+//! `update_cpu_util` / `update_core_utils`，policy 为只记录频率的 mock，
+//! 时间由虚拟时钟按 trace 时间戳推进。每帧输出一行时间线，
+//! 用于调 fas_rules 以及对 `evaluate_gear` / `handle_loading` 做回归对比。
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::clock;
+use crate::migrate::{self, ConfigKind};
+use crate::monitor::config::{self as rules_config, ClusterProfile, FasRulesConfig, RulesConfig};
+use crate::scheduler::config::ClusterDef;
+use crate::scheduler::fas::FasController;
+use crate::trace::{self, Trace, TracePolicy, TraceRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug)]
+pub struct ReplayOptions {
+    pub trace: PathBuf,
+    /// 缺省使用模块目录下的 rules.yaml
+    pub rules: Option<PathBuf>,
+    /// JSON 数组形式的频率表，覆盖 trace header 中的 policies
+    pub freq_table: Option<PathBuf>,
+    pub format: OutputFormat,
+}
+
+/// 时间线中的一行 (每帧一行)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct TimelineRow {
+    t_ms: f64,
+    frame_ms: f32,
+    perf_index: f32,
+    target_fps: f32,
+    avg_fps: f32,
+    loading: bool,
+    /// 与 header 中 policies 顺序一致 (kHz)
+    freqs: Vec<u32>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+    frames: u64,
+    gear_switches: u64,
+    loading_entries: u64,
+    perf_sum: f64,
+}
+
+fn load_fas_rules(path: Option<&Path>) -> Result<FasRulesConfig> {
+    let path = path.map(Path::to_path_buf).unwrap_or_else(rules_config::get_rules_path);
+    // 解析失败直接报错，不像守护进程那样退回默认值
+    let content = migrate::read(ConfigKind::Rules, &path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let rules: RulesConfig = serde_yaml::from_str(&content)
+        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    Ok(rules.fas_rules)
+}
+
+fn load_policy_tables(opts: &ReplayOptions, header_policies: &[TracePolicy], fas_rules: &FasRulesConfig)
+    -> Result<Vec<(usize, Vec<u32>, ClusterProfile)>>
+{
+    let policies = match &opts.freq_table {
+        Some(path) => {
+            let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+            serde_json::from_str::<Vec<TracePolicy>>(&content)
+                .with_context(|| format!("{}: expected a JSON array of {{\"id\", \"freqs\"}}", path.display()))?
+        }
+        None => header_policies.to_vec(),
+    };
+    if policies.is_empty() {
+        bail!("no frequency table: the trace header has no policies and --freq-table was not given");
+    }
+
+    Ok(policies.into_iter().enumerate().map(|(idx, p)| {
+        let profile = match p.capacity_weight {
+            Some(w) => ClusterProfile { capacity_weight: w },
+            None => fas_rules.cluster_profiles.get(&ClusterDef::positional(idx, p.id as i32)),
+        };
+        (p.id, p.freqs, profile)
+    }).collect())
+}
+
+/// 用 mock policy 回放整个 trace，每帧调用一次 `on_row`
+///
+/// 切换当前线程到虚拟时钟；同一 trace 与 rules 的输出总是相同。
+fn replay(
+    trace: &Trace,
+    fas_rules: &FasRulesConfig,
+    tables: &[(usize, Vec<u32>, ClusterProfile)],
+    mut on_row: impl FnMut(&TimelineRow) -> Result<()>,
+) -> Result<Summary> {
+    // 必须在创建控制器之前切换，init_time 取自虚拟时钟
+    clock::use_virtual();
+    let mut fas = FasController::new();
+    fas.load_mock_policies(fas_rules, tables);
+    if !trace.header.package.is_empty() {
+        fas.set_game(0, &trace.header.package);
+    }
+
+    let mut summary = Summary::default();
+    let mut last_target = None;
+    let mut was_loading = false;
+
+    for record in &trace.records {
+        clock::advance_to(record.t_ns());
+        match record {
+            TraceRecord::Frame { t_ns, delta_ns } => {
+                fas.update_frame(*delta_ns);
+                let snap = fas.snapshot();
+                let row = TimelineRow {
+                    t_ms: *t_ns as f64 / 1e6,
+                    frame_ms: *delta_ns as f32 / 1e6,
+                    perf_index: snap.perf_index,
+                    target_fps: snap.target_fps,
+                    avg_fps: snap.avg_fps,
+                    loading: snap.is_loading,
+                    freqs: snap.policies.iter().map(|p| p.current_freq).collect(),
+                };
+
+                summary.frames += 1;
+                summary.perf_sum += row.perf_index as f64;
+                if last_target.is_some_and(|t| t != row.target_fps) {
+                    summary.gear_switches += 1;
+                }
+                last_target = Some(row.target_fps);
+                if row.loading && !was_loading {
+                    summary.loading_entries += 1;
+                }
+                was_loading = row.loading;
+
+                on_row(&row)?;
+            }
+            TraceRecord::Load { core_utils, fg_util, .. } => {
+                fas.update_cpu_util(*fg_util);
+                fas.update_core_utils(core_utils);
+            }
+            TraceRecord::Temp { celsius, .. } => fas.set_temperature(*celsius),
+            TraceRecord::Header(_) | TraceRecord::Mode { .. } => {}
+        }
+    }
+    Ok(summary)
+}
+
+pub fn run(opts: &ReplayOptions) -> Result<()> {
+    let trace = trace::read_trace(&opts.trace)?;
+    let fas_rules = load_fas_rules(opts.rules.as_deref())?;
+    let tables = load_policy_tables(opts, &trace.header.policies, &fas_rules)?;
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if opts.format == OutputFormat::Csv {
+        let policy_cols: Vec<String> = tables.iter().map(|(id, _, _)| format!("p{}_khz", id)).collect();
+        writeln!(out, "t_ms,frame_ms,perf_index,target_fps,avg_fps,loading,{}", policy_cols.join(","))?;
+    }
+
+    let summary = replay(&trace, &fas_rules, &tables, |row| {
+        match opts.format {
+            OutputFormat::Jsonl => writeln!(out, "{}", serde_json::to_string(row)?)?,
+            OutputFormat::Csv => {
+                let freqs: Vec<String> = row.freqs.iter().map(u32::to_string).collect();
+                writeln!(out, "{:.3},{:.3},{:.4},{:.0},{:.2},{},{}",
+                    row.t_ms, row.frame_ms, row.perf_index, row.target_fps,
+                    row.avg_fps, row.loading as u8, freqs.join(","))?;
+            }
+        }
+        Ok(())
+    })?;
+    out.flush()?;
+
+    let mean_perf = if summary.frames > 0 { summary.perf_sum / summary.frames as f64 } else { 0.0 };
+    eprintln!("replay: {} frames, {} gear switches, {} loading entries, mean perf_index {:.3}",
+        summary.frames, summary.gear_switches, summary.loading_entries, mean_perf);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::{TraceHeader, TRACE_VERSION};
+
+    const TABLE: [u32; 4] = [300_000, 1_000_000, 1_500_000, 2_000_000];
+    const FRAME_60: u64 = 16_666_667;
+
+    /// 60 fps 平稳运行 `steady_s` 秒后，帧时间涨到 `slow_ns` 持续 `slow_s` 秒
+    fn synthetic_trace(steady_s: u64, slow_s: u64, slow_ns: u64) -> Trace {
+        let mut records = Vec::new();
+        let mut t = 0u64;
+        let mut next_load = 200_000_000u64;
+        let mut push_frames = |records: &mut Vec<TraceRecord>, until: u64, delta: u64, util: f32| {
+            while t + delta <= until {
+                t += delta;
+                records.push(TraceRecord::Frame { t_ns: t, delta_ns: delta });
+                if t >= next_load {
+                    records.push(TraceRecord::Load { t_ns: t, core_utils: vec![util; 8], fg_util: util });
+                    next_load += 200_000_000;
+                }
+            }
+        };
+        push_frames(&mut records, steady_s * 1_000_000_000, FRAME_60, 0.4);
+        push_frames(&mut records, (steady_s + slow_s) * 1_000_000_000, slow_ns, 0.95);
+
+        Trace {
+            header: TraceHeader {
+                version: TRACE_VERSION,
+                package: "com.example.game".to_string(),
+                policies: Vec::new(),
+                started_ms: None,
+            },
+            records,
+        }
+    }
+
+    fn replay_rows(trace: &Trace) -> (Vec<TimelineRow>, Summary) {
+        let tables = vec![
+            (0, TABLE.to_vec(), ClusterProfile { capacity_weight: 0.5 }),
+            (4, TABLE.to_vec(), ClusterProfile { capacity_weight: 1.0 }),
+        ];
+        let mut rows = Vec::new();
+        let summary = replay(trace, &FasRulesConfig::default(), &tables, |row| {
+            rows.push(row.clone());
+            Ok(())
+        }).unwrap();
+        (rows, summary)
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let trace = synthetic_trace(15, 10, 25_000_000);
+        let (first, first_summary) = replay_rows(&trace);
+        let (second, second_summary) = replay_rows(&trace);
+        assert_eq!(first.len(), 1299);
+        assert_eq!(first, second);
+        assert_eq!(first_summary, second_summary);
+    }
+
+    #[test]
+    fn decisions_follow_trace_time() {
+        let trace = synthetic_trace(15, 10, 25_000_000);
+        let (rows, summary) = replay_rows(&trace);
+
+        // 冷启动窗口按 trace 时间计算：回放本身只需几毫秒，真实时钟下永远走不出这个窗口
+        assert_eq!(rows[0].avg_fps, 0.0);
+        let steady = rows.iter().rfind(|r| r.t_ms < 15_000.0).unwrap();
+        assert!((steady.avg_fps - 60.0).abs() < 0.5, "{:?}", steady);
+        assert!(steady.target_fps >= 60.0, "{:?}", steady);
+
+        // 帧时间涨到 25ms 后降档，频率随之回落
+        let last = rows.last().unwrap();
+        assert!(last.target_fps < 60.0, "{:?}", last);
+        assert!(last.freqs.iter().zip(&steady.freqs).all(|(now, before)| now < before),
+            "{:?} -> {:?}", steady.freqs, last.freqs);
+        assert!(summary.gear_switches > 0);
+        assert_eq!(summary.loading_entries, 0);
+    }
+}
@@ -0,0 +1,278 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 原始节点值日志与恢复
+//!
+//! yumi 第一次写某个 /sys、/proc、/dev 节点之前，先读出它的原值 (及文件权限)
+//! 记入 `restore.json`。SIGTERM/SIGINT、控制 socket 的 `restore` 请求以及
+//! `yumi restore` 都按这份日志把设备写回原状。
+//!
+//! 日志带有 boot_id：同一次开机内被 `kill -9` 后重启，仍沿用旧日志里的原值
+//! (此时节点上已经是 yumi 写过的值)；换了一次开机则丢弃。
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{info, warn, debug};
+
+use crate::common;
+use crate::utils::{self, host_path};
+
+/// 只记录内核节点，模块自己的文件 (current_mode.txt 等) 不需要恢复
+pub const TRACKED_PREFIXES: &[&str] = &["/sys/", "/proc/", "/dev/"];
+/// 写入的是 pid 而不是配置值，恢复没有意义
+const UNTRACKED_FILES: &[&str] = &["cgroup.procs", "tasks"];
+
+/// 进入关闭流程后，所有经由 utils / FastWriter 的写入都被丢弃，避免覆盖刚恢复的值
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref JOURNAL: Mutex<RestoreJournal> = Mutex::new(RestoreJournal::load_or_new());
+}
+
+pub fn get_journal_path() -> PathBuf { common::get_module_root().join("restore.json") }
+
+pub fn is_shutting_down() -> bool { SHUTTING_DOWN.load(Ordering::Relaxed) }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    path: String,
+    value: String,
+    /// 原始权限位，write_to_file 会把节点改成 0444
+    #[serde(default)]
+    mode: Option<u32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JournalFile {
+    boot_id: String,
+    entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreReport {
+    pub restored: usize,
+    pub failed: Vec<String>,
+}
+
+struct RestoreJournal {
+    file: JournalFile,
+    seen: HashSet<String>,
+}
+
+impl RestoreJournal {
+    fn load_or_new() -> Self {
+        let boot_id = current_boot_id();
+        let file = match read_journal_file() {
+            Ok(file) if file.boot_id == boot_id => {
+                info!("Restore: reusing {} original values recorded earlier this boot", file.entries.len());
+                file
+            }
+            _ => JournalFile { boot_id, entries: Vec::new() },
+        };
+        let seen = file.entries.iter().map(|e| e.path.clone()).collect();
+        Self { file, seen }
+    }
+
+    fn persist(&self) {
+        let path = get_journal_path();
+        let tmp_path = path.with_extension("json.tmp");
+        let result = serde_json::to_vec_pretty(&self.file)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| fs::write(&tmp_path, json).map_err(anyhow::Error::from))
+            .and_then(|_| fs::rename(&tmp_path, &path).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            warn!("Restore: failed to persist {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn current_boot_id() -> String {
+    fs::read_to_string(host_path("/proc/sys/kernel/random/boot_id"))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn read_journal_file() -> Result<JournalFile> {
+    let content = fs::read_to_string(get_journal_path())?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// 路径是否位于 [`TRACKED_PREFIXES`] 之下 (内核节点)
+pub fn is_device_path(path: &Path) -> bool {
+    path.to_str().is_some_and(|s| TRACKED_PREFIXES.iter().any(|p| s.starts_with(p)))
+}
+
+fn should_track(path: &Path) -> bool {
+    if !is_device_path(path) {
+        return false;
+    }
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    !UNTRACKED_FILES.contains(&file_name)
+}
+
+/// 读取节点当前值；形如 `none [mq-deadline] kyber` 的选择型节点只取方括号内的当前项
+fn read_current_value(path: &Path) -> Option<String> {
+    let raw = fs::read_to_string(host_path(path)).ok()?;
+    let raw = raw.trim();
+    if let (Some(start), Some(end)) = (raw.find('['), raw.find(']')) && start < end {
+        return Some(raw[start + 1..end].to_string());
+    }
+    Some(raw.to_string())
+}
+
+/// 在第一次写入 `path` 之前调用，记录其原值；之后的调用不做任何事
+pub fn record_original<P: AsRef<Path>>(path: P) {
+    let path = path.as_ref();
+    if !should_track(path) { return; }
+    let key = path.to_string_lossy().into_owned();
+
+    let mut journal = JOURNAL.lock().unwrap();
+    if journal.seen.contains(&key) { return; }
+    journal.seen.insert(key.clone());
+
+    let Some(value) = read_current_value(path) else { return };
+    let mode = fs::metadata(host_path(path)).ok().map(|m| m.permissions().mode() & 0o777);
+    debug!("Restore: recorded {} = {:?}", key, value);
+    journal.file.entries.push(JournalEntry { path: key, value, mode });
+    journal.persist();
+}
+
+fn apply_entries(entries: &[JournalEntry]) -> RestoreReport {
+    let write_entry = |entry: &JournalEntry| -> Result<()> {
+        let path = host_path(&entry.path);
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o664));
+        fs::write(&path, &entry.value)?;
+        if let Some(mode) = entry.mode {
+            let _ = fs::set_permissions(&path, fs::Permissions::from_mode(mode));
+        }
+        Ok(())
+    };
+
+    // scaling_min_freq / scaling_max_freq 之类的成对节点可能因顺序被内核拒绝，失败项再试一轮
+    let mut pending: Vec<&JournalEntry> = Vec::new();
+    let mut report = RestoreReport::default();
+    for entry in entries {
+        match write_entry(entry) {
+            Ok(()) => report.restored += 1,
+            Err(_) => pending.push(entry),
+        }
+    }
+    for entry in pending {
+        match write_entry(entry) {
+            Ok(()) => report.restored += 1,
+            Err(e) => {
+                warn!("Restore: failed to restore {} = {:?}: {}", entry.path, entry.value, e);
+                report.failed.push(entry.path.clone());
+            }
+        }
+    }
+    report
+}
+
+/// 把所有记录过的节点写回原值，日志保留 (守护进程继续运行时原值仍然有效)
+pub fn restore_all() -> RestoreReport {
+    // dry-run 没有改动过任何节点；磁盘上同一次开机的旧日志留给 `yumi restore`
+    if utils::is_dry_run() {
+        info!("Restore: dry-run, nothing to restore");
+        return RestoreReport::default();
+    }
+    let journal = JOURNAL.lock().unwrap();
+    let report = apply_entries(&journal.file.entries);
+    info!("Restore: restored {} nodes, {} failed", report.restored, report.failed.len());
+    report
+}
+
+/// 关闭流程：冻结后续写入，恢复原值并删除日志
+pub fn shutdown() -> RestoreReport {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    let report = restore_all();
+    if !utils::is_dry_run() {
+        let _ = fs::remove_file(get_journal_path());
+    }
+    report
+}
+
+/// 守护进程不在运行时 (例如被 kill -9) 直接按磁盘上的日志恢复
+pub fn restore_offline() -> Result<RestoreReport> {
+    let file = read_journal_file()?;
+    if file.boot_id != current_boot_id() {
+        let _ = fs::remove_file(get_journal_path());
+        bail!("journal was recorded during a previous boot; nothing to restore");
+    }
+    let report = apply_entries(&file.entries);
+    let _ = fs::remove_file(get_journal_path());
+    Ok(report)
+}
+
+/// 接管 SIGTERM / SIGINT：恢复原值后退出
+///
+/// 必须在创建其他线程之前调用，信号屏蔽字会被之后创建的线程继承，
+/// 保证信号只会由这里的等待线程收到。
+pub fn install_signal_handler() -> Result<()> {
+    use nix::sys::signal::{SigSet, Signal};
+
+    let mut set = SigSet::empty();
+    set.add(Signal::SIGTERM);
+    set.add(Signal::SIGINT);
+    set.thread_block()?;
+
+    std::thread::Builder::new()
+        .name("signal_handler".to_string())
+        .spawn(move || {
+            match set.wait() {
+                Ok(sig) => info!("Received {:?}, restoring original values before exit", sig),
+                Err(e) => {
+                    warn!("sigwait failed: {}", e);
+                    return;
+                }
+            }
+            let report = shutdown();
+            if !report.failed.is_empty() {
+                warn!("Restore: {} nodes could not be restored", report.failed.len());
+            }
+            let _ = fs::remove_file(crate::control::get_socket_path());
+            std::process::exit(0);
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_kernel_nodes_are_device_paths() {
+        assert!(is_device_path(Path::new("/sys/devices/system/cpu/cpufreq/policy0/scaling_max_freq")));
+        assert!(is_device_path(Path::new("/dev/cpuset/top-app/cpus")));
+        assert!(is_device_path(Path::new("/proc/sys/kernel/sched_util_clamp_min")));
+        assert!(!is_device_path(Path::new("/data/adb/modules/yumi/current_mode.txt")));
+        assert!(!is_device_path(Path::new("/system/devices")));
+    }
+
+    #[test]
+    fn pid_files_are_not_tracked() {
+        assert!(should_track(Path::new("/dev/cpuset/top-app/cpus")));
+        assert!(!should_track(Path::new("/dev/cpuset/top-app/cgroup.procs")));
+        assert!(!should_track(Path::new("/data/adb/modules/yumi/config/config.yaml")));
+    }
+}
@@ -0,0 +1,522 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! cpufreq 频率执行器
+//!
+//! FAS、CLG 与静态模式 / AppLaunchBoost 写 `scaling_min_freq` /
+//! `scaling_max_freq` 都经过 [`FreqActuator`]。min/max 的写入顺序、去重、
+//! 实际频率校验与强制重写只在这里实现一次 (trait 的默认方法)，
+//! 后端只需提供写单个节点与读 `scaling_cur_freq` 的原语：
+//!
+//! - [`SysfsActuator`]：常驻 fd 的 [`FastWriter`]，FAS / CLG 高频写入
+//! - [`StaticSysfsActuator`]：经 `utils::try_write_file` 写入并置只读，静态模式与 Boost 使用
+//! - [`MockActuator`]：只记录写入，回放、仿真与控制器测试使用
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::utils::{self, host_path};
+
+fn policy_node(policy_id: usize, node: &str) -> String {
+    format!("/sys/devices/system/cpu/cpufreq/policy{}/{}", policy_id, node)
+}
+
+// ════════════════════════════════════════════════════════════════
+//  FastWriter — 带去重 + unmount 的 sysfs 写入器
+// ════════════════════════════════════════════════════════════════
+
+pub struct FastWriter {
+    file: Option<File>,
+    last_value: Option<u32>,
+    buf: [u8; 20],
+    /// 逻辑路径，访问时再经 host_path 映射
+    path: PathBuf,
+    write_count: Arc<AtomicU64>,
+    /// dry-run 且节点存在：写入只打日志
+    dry_run: bool,
+}
+
+impl FastWriter {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path_ref = path.as_ref();
+        let host = host_path(path_ref);
+        Self::try_unmount(path_ref);
+        let write_count = crate::metrics::sysfs_write_counter(&path_ref.to_string_lossy());
+
+        if utils::is_dry_run() {
+            let dry_run = host.exists();
+            if !dry_run {
+                log::error!("FAS: failed to open {}: not found", path_ref.display());
+            }
+            return Self { file: None, last_value: None, buf: [0u8; 20], path: path_ref.to_path_buf(), write_count, dry_run };
+        }
+
+        crate::restore::record_original(path_ref);
+        let _ = crate::utils::enable_perm(path_ref);
+        let file = OpenOptions::new().write(true).open(&host)
+            .map_err(|e| log::error!("FAS: failed to open {}: {}", path_ref.display(), e))
+            .ok();
+        Self { file, last_value: None, buf: [0u8; 20], path: path_ref.to_path_buf(), write_count, dry_run: false }
+    }
+
+    fn try_unmount(path: &Path) {
+        if utils::is_dry_run() {
+            log::info!("[dry-run] umount2 {}", path.display());
+            return;
+        }
+        if let Some(path_str) = host_path(path).to_str()
+            && let Ok(cpath) = std::ffi::CString::new(path_str)
+        {
+            let ret = unsafe { libc::umount2(cpath.as_ptr(), libc::MNT_DETACH) };
+            if ret != 0 {
+                let errno = std::io::Error::last_os_error();
+                if errno.raw_os_error() != Some(libc::EINVAL)
+                    && errno.raw_os_error() != Some(libc::ENOENT) {
+                    log::debug!("FAS: umount2({}) = {}", path_str, errno);
+                }
+            }
+        }
+    }
+
+    pub fn re_unmount(&self) { Self::try_unmount(&self.path); }
+
+    #[allow(dead_code)]
+    pub fn write_value(&mut self, value: u32) {
+        if self.last_value == Some(value) { return; }
+        self.do_write(value);
+    }
+
+    pub fn write_value_force(&mut self, value: u32) {
+        self.do_write(value);
+    }
+
+    pub fn invalidate(&mut self) { self.last_value = None; }
+    pub fn is_valid(&self) -> bool { self.file.is_some() || self.dry_run }
+
+    fn do_write(&mut self, value: u32) {
+        if crate::restore::is_shutting_down() { return; }
+        if self.dry_run {
+            let len = Self::u32_to_buf(value, &mut self.buf);
+            utils::log_dry_write(&self.path, &self.buf[..len]);
+            self.write_count.fetch_add(1, Ordering::Relaxed);
+            self.last_value = Some(value);
+            return;
+        }
+        if let Some(file) = &mut self.file {
+            let len = Self::u32_to_buf(value, &mut self.buf);
+            let _ = file.seek(SeekFrom::Start(0));
+            if let Err(e) = file.write_all(&self.buf[..len]) {
+                log::error!("FAS: write freq {} failed: {}", value, e);
+            }
+            self.write_count.fetch_add(1, Ordering::Relaxed);
+            self.last_value = Some(value);
+        }
+    }
+
+    fn u32_to_buf(mut v: u32, buf: &mut [u8; 20]) -> usize {
+        if v == 0 { buf[0] = b'0'; buf[1] = b'\n'; return 2; }
+        let mut pos = 18;
+        while v > 0 { buf[pos] = b'0' + (v % 10) as u8; v /= 10; pos -= 1; }
+        let start = pos + 1;
+        let digit_len = 19 - start;
+        buf.copy_within(start..19, 0);
+        buf[digit_len] = b'\n';
+        digit_len + 1
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  FreqActuator — 统一的 min/max 写入逻辑
+// ════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreqNode {
+    Min,
+    Max,
+}
+
+/// 执行器的公共状态，由后端持有
+#[derive(Debug, Clone)]
+pub struct ActuatorState {
+    pub policy_id: usize,
+    /// 最近一次下发的 (min, max)；None 表示节点当前值未知
+    applied: Option<(u32, u32)>,
+}
+
+impl ActuatorState {
+    pub fn new(policy_id: usize) -> Self {
+        Self { policy_id, applied: None }
+    }
+}
+
+/// 实际频率超出下发范围
+#[derive(Debug, Clone, Copy)]
+pub struct FreqMismatch {
+    pub min_ok: u32,
+    pub max_ok: u32,
+    pub actual: u32,
+}
+
+pub trait FreqActuator: Send {
+    // ── 后端原语 ──
+
+    fn write_node(&mut self, node: FreqNode, khz: u32);
+    /// scaling_cur_freq；读不到 (或后端没有真实节点) 时返回 None，校验随之跳过
+    fn read_cur(&self) -> Option<u32>;
+    /// 夺回节点控制权 (解除其他进程的 bind mount 等)
+    fn reclaim(&mut self) {}
+    /// 节点是否可写
+    fn is_valid(&self) -> bool { true }
+    fn state(&self) -> &ActuatorState;
+    fn state_mut(&mut self) -> &mut ActuatorState;
+
+    // ── 统一逻辑 ──
+
+    /// 最近一次下发的 (min, max)
+    fn applied(&self) -> Option<(u32, u32)> {
+        self.state().applied
+    }
+
+    /// 设置频率范围，与上次相同时不写
+    ///
+    /// 上调时先写 max 再写 min，下调时先写 min 再写 max，避免内核以
+    /// min > max 为由拒绝写入。节点当前值未知时按 max → min → max 写，
+    /// 无论旧值如何都能落地。
+    fn set_range(&mut self, min: u32, max: u32) {
+        let min = min.min(max);
+        match self.state().applied {
+            Some((cur_min, cur_max)) if (cur_min, cur_max) == (min, max) => return,
+            Some((cur_min, cur_max)) if max >= cur_max => {
+                if max != cur_max { self.write_node(FreqNode::Max, max); }
+                if min != cur_min { self.write_node(FreqNode::Min, min); }
+            }
+            Some((cur_min, _)) => {
+                if min != cur_min { self.write_node(FreqNode::Min, min); }
+                self.write_node(FreqNode::Max, max);
+            }
+            None => {
+                self.write_node(FreqNode::Max, max);
+                self.write_node(FreqNode::Min, min);
+                self.write_node(FreqNode::Max, max);
+            }
+        }
+        self.state_mut().applied = Some((min, max));
+    }
+
+    /// 锁频 (min = max)
+    fn lock(&mut self, khz: u32) {
+        self.set_range(khz, khz);
+    }
+
+    /// 忘记上次下发的值，下一次 set_range 必定完整写入
+    fn invalidate(&mut self) {
+        self.state_mut().applied = None;
+    }
+
+    /// 夺回控制权并重新下发上次的范围 (被内核或其他进程覆盖后使用)
+    fn force_reapply(&mut self) {
+        self.reclaim();
+        if let Some((min, max)) = self.state_mut().applied.take() {
+            self.set_range(min, max);
+        }
+    }
+
+    /// 检查实际频率是否落在下发范围内 (按 `table` 向外取最近的档位)，
+    /// 不一致时强制重写并返回差异
+    fn verify(&mut self, table: &[u32]) -> Option<FreqMismatch> {
+        let (min, max) = self.state().applied?;
+        let actual = self.read_cur()?;
+        let min_ok = table.iter().take_while(|&&f| f <= min).last().copied().unwrap_or(min);
+        let max_ok = table.iter().find(|&&f| f >= max).copied().unwrap_or(max);
+        if actual >= min_ok && actual <= max_ok {
+            return None;
+        }
+        self.force_reapply();
+        Some(FreqMismatch { min_ok, max_ok, actual })
+    }
+}
+
+// ════════════════════════════════════════════════════════════════
+//  后端
+// ════════════════════════════════════════════════════════════════
+
+/// 常驻 fd 的 sysfs 后端
+pub struct SysfsActuator {
+    state: ActuatorState,
+    max_writer: FastWriter,
+    min_writer: FastWriter,
+}
+
+impl SysfsActuator {
+    pub fn open(policy_id: usize) -> Self {
+        Self {
+            state: ActuatorState::new(policy_id),
+            max_writer: FastWriter::new(policy_node(policy_id, "scaling_max_freq")),
+            min_writer: FastWriter::new(policy_node(policy_id, "scaling_min_freq")),
+        }
+    }
+}
+
+impl FreqActuator for SysfsActuator {
+    fn write_node(&mut self, node: FreqNode, khz: u32) {
+        match node {
+            FreqNode::Max => self.max_writer.write_value_force(khz),
+            FreqNode::Min => self.min_writer.write_value_force(khz),
+        }
+    }
+
+    fn read_cur(&self) -> Option<u32> {
+        read_cur_freq(self.state.policy_id)
+    }
+
+    fn reclaim(&mut self) {
+        self.max_writer.re_unmount();
+        self.min_writer.re_unmount();
+        self.max_writer.invalidate();
+        self.min_writer.invalidate();
+    }
+
+    fn is_valid(&self) -> bool {
+        self.max_writer.is_valid() && self.min_writer.is_valid()
+    }
+
+    fn state(&self) -> &ActuatorState { &self.state }
+    fn state_mut(&mut self) -> &mut ActuatorState { &mut self.state }
+}
+
+/// 每次写入都经 `utils::try_write_file`，写完把节点置为只读，
+/// 防止其他进程改回；用于低频的静态模式与 AppLaunchBoost
+pub struct StaticSysfsActuator {
+    state: ActuatorState,
+}
+
+impl StaticSysfsActuator {
+    pub fn new(policy_id: usize) -> Self {
+        Self { state: ActuatorState::new(policy_id) }
+    }
+}
+
+impl FreqActuator for StaticSysfsActuator {
+    fn write_node(&mut self, node: FreqNode, khz: u32) {
+        let name = match node {
+            FreqNode::Max => "scaling_max_freq",
+            FreqNode::Min => "scaling_min_freq",
+        };
+        let _ = utils::try_write_file(policy_node(self.state.policy_id, name), khz.to_string());
+    }
+
+    fn read_cur(&self) -> Option<u32> {
+        read_cur_freq(self.state.policy_id)
+    }
+
+    fn state(&self) -> &ActuatorState { &self.state }
+    fn state_mut(&mut self) -> &mut ActuatorState { &mut self.state }
+}
+
+/// dry-run 时节点上不是我们写的值，不参与校验
+fn read_cur_freq(policy_id: usize) -> Option<u32> {
+    if utils::is_dry_run() { return None; }
+    let path = policy_node(policy_id, "scaling_cur_freq");
+    fs::read_to_string(host_path(&path)).ok()?.trim().parse::<u32>().ok()
+}
+
+/// [`MockActuator`] 的可观察状态
+#[derive(Debug, Default)]
+pub struct MockRecord {
+    /// 按顺序记录的全部写入；只有 [`MockActuator::recording`] 创建的执行器会记录
+    pub writes: Vec<(FreqNode, u32)>,
+    /// 节点当前的值
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    /// read_cur 的返回值，测试可以改写它来模拟内核覆写
+    pub cur: Option<u32>,
+    keep_writes: bool,
+}
+
+pub type MockHandle = Arc<Mutex<MockRecord>>;
+
+/// 不接触任何节点的后端
+pub struct MockActuator {
+    state: ActuatorState,
+    record: MockHandle,
+}
+
+impl MockActuator {
+    pub fn new(policy_id: usize) -> Self {
+        Self { state: ActuatorState::new(policy_id), record: MockHandle::default() }
+    }
+
+    /// 记录每一次写入，返回用于检查的句柄 (供控制器的单元测试使用)
+    #[cfg(test)]
+    pub fn recording(policy_id: usize) -> (Self, MockHandle) {
+        let actuator = Self::new(policy_id);
+        actuator.record.lock().unwrap().keep_writes = true;
+        let handle = Arc::clone(&actuator.record);
+        (actuator, handle)
+    }
+}
+
+impl FreqActuator for MockActuator {
+    fn write_node(&mut self, node: FreqNode, khz: u32) {
+        let mut record = self.record.lock().unwrap();
+        match node {
+            FreqNode::Min => record.min = Some(khz),
+            FreqNode::Max => record.max = Some(khz),
+        }
+        if record.keep_writes {
+            record.writes.push((node, khz));
+        }
+    }
+
+    fn read_cur(&self) -> Option<u32> {
+        self.record.lock().unwrap().cur
+    }
+
+    fn state(&self) -> &ActuatorState { &self.state }
+    fn state_mut(&mut self) -> &mut ActuatorState { &mut self.state }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: [u32; 4] = [300_000, 1_000_000, 1_500_000, 2_000_000];
+
+    fn writes(handle: &MockHandle) -> Vec<(FreqNode, u32)> {
+        std::mem::take(&mut handle.lock().unwrap().writes)
+    }
+
+    #[test]
+    fn unknown_state_writes_max_min_max() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.set_range(1_000_000, 1_500_000);
+        assert_eq!(writes(&handle), [
+            (FreqNode::Max, 1_500_000),
+            (FreqNode::Min, 1_000_000),
+            (FreqNode::Max, 1_500_000),
+        ]);
+        assert_eq!(act.applied(), Some((1_000_000, 1_500_000)));
+    }
+
+    #[test]
+    fn raising_writes_max_before_min() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.set_range(300_000, 1_000_000);
+        writes(&handle);
+        act.set_range(1_500_000, 2_000_000);
+        assert_eq!(writes(&handle), [(FreqNode::Max, 2_000_000), (FreqNode::Min, 1_500_000)]);
+    }
+
+    #[test]
+    fn lowering_writes_min_before_max() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.set_range(1_500_000, 2_000_000);
+        writes(&handle);
+        act.set_range(300_000, 1_000_000);
+        assert_eq!(writes(&handle), [(FreqNode::Min, 300_000), (FreqNode::Max, 1_000_000)]);
+    }
+
+    #[test]
+    fn unchanged_range_is_not_rewritten() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.lock(1_000_000);
+        writes(&handle);
+        act.lock(1_000_000);
+        assert!(writes(&handle).is_empty());
+        // 只写变化的节点
+        act.set_range(300_000, 1_000_000);
+        assert_eq!(writes(&handle), [(FreqNode::Min, 300_000)]);
+        act.set_range(300_000, 2_000_000);
+        assert_eq!(writes(&handle), [(FreqNode::Max, 2_000_000)]);
+    }
+
+    #[test]
+    fn min_above_max_is_clamped() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.set_range(2_000_000, 1_000_000);
+        assert_eq!(act.applied(), Some((1_000_000, 1_000_000)));
+        let record = handle.lock().unwrap();
+        assert_eq!((record.min, record.max), (Some(1_000_000), Some(1_000_000)));
+    }
+
+    #[test]
+    fn invalidate_forces_full_write() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.lock(1_000_000);
+        writes(&handle);
+        act.invalidate();
+        act.lock(1_000_000);
+        assert_eq!(writes(&handle).len(), 3);
+    }
+
+    #[test]
+    fn force_reapply_rewrites_last_range() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.force_reapply();
+        assert!(writes(&handle).is_empty(), "nothing applied yet");
+
+        act.set_range(300_000, 1_500_000);
+        writes(&handle);
+        act.force_reapply();
+        assert_eq!(writes(&handle), [
+            (FreqNode::Max, 1_500_000),
+            (FreqNode::Min, 300_000),
+            (FreqNode::Max, 1_500_000),
+        ]);
+        assert_eq!(act.applied(), Some((300_000, 1_500_000)));
+    }
+
+    #[test]
+    fn verify_accepts_frequency_in_range() {
+        let (mut act, handle) = MockActuator::recording(0);
+        assert!(act.verify(&TABLE).is_none(), "nothing applied yet");
+        act.set_range(1_000_000, 1_500_000);
+        writes(&handle);
+
+        assert!(act.verify(&TABLE).is_none(), "no cur freq to compare");
+        handle.lock().unwrap().cur = Some(1_500_000);
+        assert!(act.verify(&TABLE).is_none());
+        assert!(writes(&handle).is_empty());
+    }
+
+    #[test]
+    fn verify_rounds_range_outward_to_table_steps() {
+        let (mut act, handle) = MockActuator::recording(0);
+        // 下发值不在表中时，内核会落在外侧最近的档位
+        act.set_range(1_200_000, 1_200_000);
+        handle.lock().unwrap().cur = Some(1_000_000);
+        assert!(act.verify(&TABLE).is_none());
+        handle.lock().unwrap().cur = Some(1_500_000);
+        assert!(act.verify(&TABLE).is_none());
+    }
+
+    #[test]
+    fn verify_mismatch_reapplies() {
+        let (mut act, handle) = MockActuator::recording(0);
+        act.lock(1_000_000);
+        writes(&handle);
+        handle.lock().unwrap().cur = Some(2_000_000);
+
+        let m = act.verify(&TABLE).expect("mismatch");
+        assert_eq!((m.min_ok, m.max_ok, m.actual), (1_000_000, 1_000_000, 2_000_000));
+        assert_eq!(writes(&handle).len(), 3);
+        assert_eq!(act.applied(), Some((1_000_000, 1_000_000)));
+    }
+}
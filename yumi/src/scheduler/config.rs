@@ -15,30 +15,46 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use serde::Deserialize;
-use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use serde::Deserializer;
 use std::fmt;
+use std::path::{Path, PathBuf};
+use super::freq_expr::{FreqExpr, MAX_SENTINEL};
 
 // de_util 模块保持不变
 mod de_util {
     use super::*;
+    use schemars::{json_schema, Schema, SchemaGenerator};
     use serde::de::{self, Visitor};
 
-    pub fn deserialize_freq<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    /// CoreFramework 的 policy 编号：整数 (-1 表示没有该档)，"auto" / "" / null 表示自动识别
+    pub fn deserialize_core_path<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(FreqVisitor)
+        deserializer.deserialize_any(CorePathVisitor)
     }
 
-    struct FreqVisitor;
+    pub fn core_path_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "policy 编号，-1 表示没有该档，\"auto\" 表示按 CPU 拓扑自动识别",
+            "anyOf": [
+                { "type": "integer", "minimum": -1 },
+                { "enum": ["auto", ""] },
+                { "type": "null" }
+            ]
+        })
+    }
+
+    struct CorePathVisitor;
 
-    impl<'de> Visitor<'de> for FreqVisitor {
-        type Value = u32;
+    impl<'de> Visitor<'de> for CorePathVisitor {
+        type Value = Option<i32>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a string 'min', 'max', or an integer")
+            formatter.write_str("a policy number, -1, or 'auto'")
         }
 
         fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
@@ -46,34 +62,62 @@ mod de_util {
             E: de::Error,
         {
             match value {
-                "min" => Ok(0),
-                "max" | "" => Ok(9999999), // 空字符串视为默认值 (max)
-                _ => {
-                    Err(de::Error::unknown_variant(value, &["min", "max", ""]))
-                }
+                "auto" | "" => Ok(None),
+                _ => Err(de::Error::unknown_variant(value, &["auto", ""])),
             }
         }
 
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            i32::try_from(value).map(Some).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Signed(value), &self)
+            })
+        }
+
         fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
         where
             E: de::Error,
         {
-            u32::try_from(value).map_err(|_| {
+            i32::try_from(value).map(Some).map_err(|_| {
                 de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)
             })
         }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Meta {
     // 同时支持 "loglevel" 和 "Loglevel"
     #[serde(default = "default_loglevel", alias = "Loglevel")]
+    #[schemars(extend("x-aliases" = ["Loglevel"]))]
     pub loglevel: String,
     
     // 同时支持 "language" 和 "Language"
     #[serde(default = "default_language", alias = "Language")]
+    #[schemars(extend("x-aliases" = ["Language"]))]
     pub language: String,
+
+    /// 配置名称与作者，仅供展示
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
 }
 
 fn default_loglevel() -> String {
@@ -84,58 +128,152 @@ fn default_language() -> String {
     "en".to_string()
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
-#[serde(rename_all = "PascalCase")]
+/// 各 cluster 的调速器
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
+#[serde(rename_all = "PascalCase", from = "GovernorSettingsRaw")]
 pub struct GovernorSettings {
-    #[serde(default = "default_governor")]
     pub global: String,
-    pub small_core: String,
-    pub medium_core: String,
-    pub big_core: String,
-    pub super_big_core: String,
+    /// cluster 名 (或 "policyN") → 调速器，未列出的 cluster 使用 Global
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub clusters: BTreeMap<String, String>,
+}
+
+/// 反序列化用：兼容旧格式的 SmallCore / MediumCore / BigCore / SuperBigCore 字段
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+struct GovernorSettingsRaw {
+    // 各 SoC 配置里写的是小写 "global"
+    #[serde(default = "default_governor", alias = "global")]
+    #[schemars(extend("x-aliases" = ["global"]))]
+    global: String,
+    #[serde(default)]
+    clusters: BTreeMap<String, String>,
+    #[serde(default)]
+    small_core: String,
+    #[serde(default)]
+    medium_core: String,
+    #[serde(default)]
+    big_core: String,
+    #[serde(default)]
+    super_big_core: String,
+}
+
+impl From<GovernorSettingsRaw> for GovernorSettings {
+    fn from(raw: GovernorSettingsRaw) -> Self {
+        let legacy = [raw.small_core, raw.medium_core, raw.big_core, raw.super_big_core];
+        // 旧格式中空字符串表示沿用 global；同一 cluster 两种写法都有时以 Clusters 为准
+        // (自定义模式中的旧格式字段在合并前已改写为 Clusters，见 legacy_to_clusters)
+        let mut clusters = raw.clusters;
+        for (name, gov) in TIER_NAMES.iter().zip(legacy) {
+            if !gov.is_empty() {
+                clusters.entry(name.to_string()).or_insert(gov);
+            }
+        }
+        Self { global: raw.global, clusters }
+    }
+}
+
+impl GovernorSettings {
+    pub fn governor_for(&self, cluster: &ClusterDef) -> &str {
+        cluster.lookup(|k| self.clusters.get(k))
+            .filter(|gov| !gov.is_empty())
+            .unwrap_or(&self.global)
+    }
 }
 
 fn default_governor() -> String {
     "schedutil".to_string()
 }
 
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "PascalCase")]
+/// 各 cluster 的频率范围
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
+#[serde(rename_all = "PascalCase", from = "FreqSettingsRaw")]
 pub struct FreqSettings {
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub small_core_min_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub small_core_max_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub medium_core_min_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub medium_core_max_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub big_core_min_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub big_core_max_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub super_big_core_min_freq: u32,
-    #[serde(deserialize_with = "de_util::deserialize_freq")]
-    pub super_big_core_max_freq: u32,
-}
-
-impl Default for FreqSettings {
+    /// cluster 名 (或 "policyN") → 频率范围，未列出的 cluster 不限频
+    pub clusters: BTreeMap<String, FreqRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FreqRange {
+    #[serde(default = "FreqRange::default_min")]
+    pub min: FreqExpr,
+    #[serde(default = "FreqRange::default_max")]
+    pub max: FreqExpr,
+}
+
+impl Default for FreqRange {
     fn default() -> Self {
-        Self {
-            small_core_min_freq: 0,
-            small_core_max_freq: 10_000_000,
-            medium_core_min_freq: 0,
-            medium_core_max_freq: 10_000_000,
-            big_core_min_freq: 0,
-            big_core_max_freq: 10_000_000,
-            super_big_core_min_freq: 0,
-            super_big_core_max_freq: 10_000_000,
+        Self { min: Self::default_min(), max: Self::default_max() }
+    }
+}
+
+impl FreqRange {
+    fn default_min() -> FreqExpr { FreqExpr::Min }
+    fn default_max() -> FreqExpr { FreqExpr::Max }
+
+    /// 按 policy 的频率表求出要写入的 (min, max)；无法求值的一端不限频，min 高于 max 时压到 max
+    pub fn resolve(&self, table: &[u32]) -> (u32, u32) {
+        let max = self.max.resolve(table).unwrap_or(MAX_SENTINEL);
+        let min = self.min.resolve(table).unwrap_or(0);
+        (min.min(max), max)
+    }
+}
+
+/// 反序列化用：兼容旧格式的 SmallCoreMinFreq ... SuperBigCoreMaxFreq 字段
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+struct FreqSettingsRaw {
+    #[serde(default)]
+    clusters: BTreeMap<String, FreqRange>,
+    #[serde(default)]
+    small_core_min_freq: Option<FreqExpr>,
+    #[serde(default)]
+    small_core_max_freq: Option<FreqExpr>,
+    #[serde(default)]
+    medium_core_min_freq: Option<FreqExpr>,
+    #[serde(default)]
+    medium_core_max_freq: Option<FreqExpr>,
+    #[serde(default)]
+    big_core_min_freq: Option<FreqExpr>,
+    #[serde(default)]
+    big_core_max_freq: Option<FreqExpr>,
+    #[serde(default)]
+    super_big_core_min_freq: Option<FreqExpr>,
+    #[serde(default)]
+    super_big_core_max_freq: Option<FreqExpr>,
+}
+
+impl From<FreqSettingsRaw> for FreqSettings {
+    fn from(raw: FreqSettingsRaw) -> Self {
+        let legacy = [
+            (raw.small_core_min_freq, raw.small_core_max_freq),
+            (raw.medium_core_min_freq, raw.medium_core_max_freq),
+            (raw.big_core_min_freq, raw.big_core_max_freq),
+            (raw.super_big_core_min_freq, raw.super_big_core_max_freq),
+        ];
+        // Clusters 中已有同名 cluster 时旧格式字段不生效 (见 GovernorSettingsRaw)
+        let mut clusters = raw.clusters;
+        for (name, (min, max)) in TIER_NAMES.iter().zip(legacy) {
+            if min.is_none() && max.is_none() {
+                continue;
+            }
+            clusters.entry(name.to_string()).or_insert(FreqRange {
+                min: min.unwrap_or_else(FreqRange::default_min),
+                max: max.unwrap_or_else(FreqRange::default_max),
+            });
         }
+        Self { clusters }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl FreqSettings {
+    pub fn range_for(&self, cluster: &ClusterDef) -> FreqRange {
+        cluster.lookup(|k| self.clusters.get(k)).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct UclampSettings {
     pub uclamp_top_app_min: String,
@@ -162,14 +300,14 @@ impl Default for UclampSettings {
     }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct Other {
     #[serde(rename = "ufsClkGate")]
     pub ufs_clk_gate: bool,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct Mode {
     #[serde(default)]
@@ -184,9 +322,13 @@ pub struct Mode {
     pub other: Other,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Config {
+    /// 文件格式版本，旧版本在加载时迁移 (见 migrate.rs)
+    #[serde(default)]
+    pub schema_version: u32,
     #[serde(default, alias = "Meta")]
+    #[schemars(extend("x-aliases" = ["Meta"]))]
     pub meta: Meta,
     #[serde(default)]
     pub function: FunctionToggles,
@@ -204,6 +346,8 @@ pub struct Config {
     pub cpu_idle: CpuIdle,
     #[serde(default, rename = "Cpuset")]
     pub cpu_set: Cpuset,
+    #[serde(default, rename = "Metrics")]
+    pub metrics: MetricsSettings,
     #[serde(default, rename = "pGovPath")]
     pub p_gov_path: HashMap<String, HashMap<String, String>>,
     #[serde(default)]
@@ -214,9 +358,29 @@ pub struct Config {
     pub performance: Mode,
     #[serde(default)]
     pub fast: Mode,
+    /// 自定义模式的原始写法
+    #[serde(default, rename = "modes", skip_serializing)]
+    pub mode_defs: BTreeMap<String, CustomMode>,
+    /// 展开继承后的自定义模式，由 [`Config::resolve_modes`] 填充
+    #[serde(skip_deserializing, rename = "modes", skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_modes: BTreeMap<String, Mode>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// `modes:` 下声明的自定义模式
+///
+/// `extends` 指定继承的模式 (内置模式或其他自定义模式)，其余键按 Mode 的写法只写需要覆盖的部分，
+/// 与父模式逐键深度合并。没有 `extends` 时从空模式开始。
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct CustomMode {
+    #[serde(default, alias = "Extends", skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("x-aliases" = ["Extends"]))]
+    pub extends: Option<String>,
+    #[serde(flatten)]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub overrides: serde_yaml::Mapping,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 pub struct FunctionToggles {
     #[serde(rename = "AffinitySetter")]
     pub affinity_setter: bool,
@@ -235,53 +399,173 @@ pub struct FunctionToggles {
     pub app_launch_boost: bool,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+/// 应用启动加速：启动期间各 cluster 锁定的频率
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase", from = "AppLaunchBoostSettingsRaw")]
 pub struct AppLaunchBoostSettings {
-    #[serde(default = "default_boost_rate")]
     pub boost_rate_ms: u64,
-    #[serde(default = "default_boost_freq", deserialize_with = "de_util::deserialize_freq")]
-    pub small_core_boost_freq: u32,
-    #[serde(default = "default_boost_freq", deserialize_with = "de_util::deserialize_freq")]
-    pub medium_core_boost_freq: u32,
-    #[serde(default = "default_boost_freq", deserialize_with = "de_util::deserialize_freq")]
-    pub big_core_boost_freq: u32,
-    #[serde(default = "default_boost_freq", deserialize_with = "de_util::deserialize_freq")]
-    pub super_big_core_boost_freq: u32,
+    /// cluster 名 (或 "policyN") → boost 频率，未列出的 cluster 为 "max"
+    pub clusters: BTreeMap<String, FreqExpr>,
 }
 
 impl Default for AppLaunchBoostSettings {
     fn default() -> Self {
-        Self {
-            boost_rate_ms: default_boost_rate(),
-            small_core_boost_freq: default_boost_freq(),
-            medium_core_boost_freq: default_boost_freq(),
-            big_core_boost_freq: default_boost_freq(),
-            super_big_core_boost_freq: default_boost_freq(),
+        Self { boost_rate_ms: default_boost_rate(), clusters: BTreeMap::new() }
+    }
+}
+
+/// 反序列化用：兼容旧格式的 SmallCoreBoostFreq ... SuperBigCoreBoostFreq 字段
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+struct AppLaunchBoostSettingsRaw {
+    #[serde(default = "default_boost_rate")]
+    boost_rate_ms: u64,
+    #[serde(default)]
+    clusters: BTreeMap<String, FreqExpr>,
+    #[serde(default)]
+    small_core_boost_freq: Option<FreqExpr>,
+    #[serde(default)]
+    medium_core_boost_freq: Option<FreqExpr>,
+    #[serde(default)]
+    big_core_boost_freq: Option<FreqExpr>,
+    #[serde(default)]
+    super_big_core_boost_freq: Option<FreqExpr>,
+}
+
+impl From<AppLaunchBoostSettingsRaw> for AppLaunchBoostSettings {
+    fn from(raw: AppLaunchBoostSettingsRaw) -> Self {
+        let legacy = [raw.small_core_boost_freq, raw.medium_core_boost_freq,
+                      raw.big_core_boost_freq, raw.super_big_core_boost_freq];
+        let mut clusters = raw.clusters;
+        for (name, freq) in TIER_NAMES.iter().zip(legacy) {
+            if let Some(freq) = freq {
+                clusters.entry(name.to_string()).or_insert(freq);
+            }
         }
+        Self { boost_rate_ms: raw.boost_rate_ms, clusters }
     }
 }
 
-/// 默认 boost 频率 = "max"，即内核允许的最高值
-fn default_boost_freq() -> u32 { 9999999 }
+impl AppLaunchBoostSettings {
+    /// 未列出的 cluster 为 "max"，即内核允许的最高值
+    pub fn freq_for(&self, cluster: &ClusterDef) -> FreqExpr {
+        cluster.lookup(|k| self.clusters.get(k)).copied().unwrap_or(FreqExpr::Max)
+    }
+}
 fn default_boost_rate() -> u64 { 200 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "PascalCase")]
 pub struct CoreAllocation {
     pub cpu_set_core: String,
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// 旧格式 CoreFramework 四个档位的名字，也是自动识别时前四档 cluster 的名字
+pub const TIER_NAMES: [&str; 4] = ["SmallCore", "MediumCore", "BigCore", "SuperBigCore"];
+
+/// CPU cluster 划分
+///
+/// 新格式在 `Clusters` 中按算力从小到大列出任意数量的 cluster；
+/// 旧格式的 `*CorePath` 四个字段仍然可用，对应名为 SmallCore ... SuperBigCore 的 cluster。
+/// 两者都省略 (或写 "auto") 时按 CPU 拓扑自动识别 (见 topology.rs)。
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct CoreFramework {
-    pub small_core_path: i32,
-    pub medium_core_path: i32,
-    pub big_core_path: i32,
-    pub super_big_core_path: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<ClusterDef>,
+    /// 旧格式：policy 编号，-1 表示没有该档，省略或 "auto" 表示自动识别
+    #[serde(default, deserialize_with = "de_util::deserialize_core_path", skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "de_util::core_path_schema")]
+    pub small_core_path: Option<i32>,
+    #[serde(default, deserialize_with = "de_util::deserialize_core_path", skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "de_util::core_path_schema")]
+    pub medium_core_path: Option<i32>,
+    #[serde(default, deserialize_with = "de_util::deserialize_core_path", skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "de_util::core_path_schema")]
+    pub big_core_path: Option<i32>,
+    #[serde(default, deserialize_with = "de_util::deserialize_core_path", skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "de_util::core_path_schema")]
+    pub super_big_core_path: Option<i32>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// 一个 cluster：各模式的 Freq / Governor / Govsets 以及 boost 设置用名字引用它
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClusterDef {
+    /// 缺省为 "policyN"
+    #[serde(default)]
+    pub name: String,
+    pub policy: i32,
+}
+
+impl ClusterDef {
+    pub fn new(name: Option<&str>, policy: i32) -> Self {
+        let name = name.filter(|n| !n.is_empty()).map_or_else(|| format!("policy{}", policy), str::to_string);
+        Self { name, policy }
+    }
+
+    /// 没有名字的 cluster 按位置取 SmallCore ... SuperBigCore，与旧格式的列表写法一致
+    pub fn positional(idx: usize, policy: i32) -> Self {
+        Self::new(TIER_NAMES.get(idx).copied(), policy)
+    }
+
+    /// 设置表中的键可以是 cluster 名、"policyN" 或 "N"
+    pub fn matches(&self, key: &str) -> bool {
+        key == self.name
+            || key.strip_prefix("policy").unwrap_or(key).parse::<i32>().is_ok_and(|id| id == self.policy)
+    }
+
+    /// 按 cluster 名、"policyN"、"N" 的顺序查找该 cluster 的设置
+    pub fn lookup<'a, V: 'a>(&self, get: impl Fn(&str) -> Option<&'a V>) -> Option<&'a V> {
+        get(&self.name)
+            .or_else(|| get(&format!("policy{}", self.policy)))
+            .or_else(|| get(&self.policy.to_string()))
+    }
+}
+
+impl CoreFramework {
+    /// 旧格式四个档位的显式值
+    pub fn legacy_paths(&self) -> [Option<i32>; 4] {
+        [self.small_core_path, self.medium_core_path, self.big_core_path, self.super_big_core_path]
+    }
+
+    /// 实际控制的 cluster，按算力从小到大
+    pub fn clusters(&self) -> Vec<ClusterDef> {
+        if !self.clusters.is_empty() {
+            return self.clusters.iter()
+                .filter(|c| c.policy >= 0)
+                .map(|c| ClusterDef::new(Some(&c.name), c.policy))
+                .collect();
+        }
+
+        let topology = super::topology::get();
+        let auto = topology.tiers();
+        let legacy = self.legacy_paths();
+        let mut clusters: Vec<ClusterDef> = TIER_NAMES.iter()
+            .zip(legacy.iter().zip(auto))
+            .map(|(name, (explicit, auto))| ClusterDef::new(Some(name), explicit.unwrap_or(auto)))
+            .filter(|c| c.policy >= 0)
+            .collect();
+
+        // 完全自动时，四档以外的 cluster (5、6 个 policy 的 SoC) 以 policyN 命名一并控制
+        if legacy.iter().all(Option::is_none) {
+            for c in &topology.clusters {
+                if !auto.contains(&c.policy_id) {
+                    clusters.push(ClusterDef::new(None, c.policy_id));
+                }
+            }
+            clusters.sort_by_key(|c| topology.clusters.iter().position(|t| t.policy_id == c.policy));
+        }
+        clusters
+    }
+
+    /// 以新格式展开后的副本，用于展示生效配置
+    pub fn resolved(&self) -> Self {
+        Self { clusters: self.clusters(), ..Self::default() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct IOSettings {
     /// IO 调度器，遍历 /sys/block/* 写入（如 "none", "mq-deadline", "bfq"）
     #[serde(default, rename = "Scheduler")]
@@ -312,7 +596,7 @@ fn default_read_ahead_kb() -> String { "128".to_string() }
 fn default_nomerges() -> String { "2".to_string() }
 fn default_iostats() -> String { "0".to_string() }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 pub struct CompletelyFairSchedulerValue {
     #[serde(rename = "sched_child_runs_first")]
     pub sched_child_runs_first: String,
@@ -322,13 +606,13 @@ pub struct CompletelyFairSchedulerValue {
     pub sched_rt_runtime_us: String,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct CpuIdle {
     pub current_governor: String,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Cpuset {
   pub top_app: String,
   pub foreground: String,
@@ -338,11 +622,44 @@ pub struct Cpuset {
 }
 
 
+/// OpenMetrics 导出设置 (修改后需重启守护进程生效)
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct MetricsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// TCP 地址 (如 "127.0.0.1:9464")，或以 '/' 开头的 Unix socket 路径
+    #[serde(default = "default_metrics_listen")]
+    pub listen: String,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self { enabled: false, listen: default_metrics_listen() }
+    }
+}
+
+fn default_metrics_listen() -> String { "127.0.0.1:9464".to_string() }
 
 impl Config {
-    pub fn from_file(path: &str) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
+    /// 内置的模式名，与 [`Config::get_mode`] 一致
+    pub const MODE_NAMES: &'static [&'static str] = &["powersave", "balance", "performance", "fast"];
+
+    /// 按模块目录下实际存在的各层加载 (见 [`layer_paths`])
+    pub fn load() -> anyhow::Result<Self> {
+        Self::from_layers(&layer_paths(&get_config_dir()))
+    }
+
+    /// 依次深度合并各层后解析；后面的层覆盖前面的层
+    ///
+    /// 无法展开继承的自定义模式只丢弃它自己，其余配置照常可用；原因由 `config_check` 逐个报告。
+    pub fn from_layers(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        Self::from_merged(merge_layers(paths)?)
+    }
+
+    fn from_merged(value: serde_yaml::Value) -> anyhow::Result<Self> {
+        let mut config: Config = serde_yaml::from_value(value)?;
+        config.resolve_modes();
         Ok(config)
     }
 
@@ -352,7 +669,364 @@ impl Config {
             "balance" => Some(&self.balance),
             "performance" => Some(&self.performance),
             "fast" => Some(&self.fast),
+            _ => self.custom_modes.get(mode_name),
+        }
+    }
+
+    /// 内置模式与已展开的自定义模式
+    pub fn mode_names(&self) -> Vec<&str> {
+        Self::MODE_NAMES.iter().copied().chain(self.custom_modes.keys().map(String::as_str)).collect()
+    }
+
+    /// 展开所有自定义模式的继承，返回无法展开的模式及原因 (这些模式不可用)
+    pub fn resolve_modes(&mut self) -> Vec<(String, String)> {
+        let mut resolved = BTreeMap::new();
+        let mut errors = Vec::new();
+        for name in self.mode_defs.keys() {
+            match self.mode_value(name).and_then(|v| serde_yaml::from_value::<Mode>(v).map_err(|e| e.to_string())) {
+                Ok(mode) => { resolved.insert(name.clone(), mode); }
+                Err(e) => errors.push((name.clone(), e)),
+            }
+        }
+        self.custom_modes = resolved;
+        errors
+    }
+
+    /// 自定义模式与父模式合并后的 YAML (尚未反序列化为 Mode)
+    pub fn mode_value(&self, name: &str) -> Result<serde_yaml::Value, String> {
+        self.mode_value_inner(name, &mut Vec::new())
+    }
+
+    fn mode_value_inner(&self, name: &str, chain: &mut Vec<String>) -> Result<serde_yaml::Value, String> {
+        if Self::MODE_NAMES.contains(&name) || name == "fas" {
+            return Err(format!("'{}' is a built-in mode name", name));
+        }
+        if chain.iter().any(|n| n == name) {
+            chain.push(name.to_string());
+            return Err(format!("extends cycle: {}", chain.join(" -> ")));
+        }
+        let def = self.mode_defs.get(name).ok_or_else(|| format!("unknown mode '{}'", name))?;
+        chain.push(name.to_string());
+
+        let mut value = match def.extends.as_deref() {
+            None => serde_yaml::to_value(Mode::default()).map_err(|e| e.to_string())?,
+            Some(parent) if Self::MODE_NAMES.contains(&parent) => {
+                serde_yaml::to_value(self.get_mode(parent)).map_err(|e| e.to_string())?
+            }
+            Some(parent) if self.mode_defs.contains_key(parent) => self.mode_value_inner(parent, chain)
+                .map_err(|e| if e.starts_with("extends cycle") { e } else { format!("extends '{}': {}", parent, e) })?,
+            Some(parent) => return Err(format!("extends unknown mode '{}'", parent)),
+        };
+        let mut overrides = def.overrides.clone();
+        legacy_to_clusters(&mut overrides);
+        merge_yaml(&mut value, serde_yaml::Value::Mapping(overrides));
+        Ok(value)
+    }
+}
+
+/// 自定义模式中旧格式的 cluster 字段改写为 Clusters 写法
+///
+/// 父模式序列化后只有 Clusters；不改写的话合并后两种写法并存，旧格式字段会被 Clusters 盖住。
+/// 同一模式里两种写法都有时保留 Clusters 中已有的项。
+fn legacy_to_clusters(overrides: &mut serde_yaml::Mapping) {
+    use serde_yaml::{Mapping, Value};
+    for (section, fields) in overrides.iter_mut() {
+        let (Some(section), Value::Mapping(fields)) = (section.as_str(), fields) else { continue };
+        let legacy: Vec<(Value, &str, Option<&str>)> = fields.keys()
+            .filter_map(|key| {
+                let (tier, entry) = legacy_cluster_key(section, key.as_str()?)?;
+                Some((key.clone(), tier, entry))
+            })
+            .collect();
+        for (key, tier, entry) in legacy {
+            let Some(value) = fields.remove(&key) else { continue };
+            // 旧格式的空调速器表示沿用 Global
+            if value.as_str() == Some("") {
+                continue;
+            }
+            let Value::Mapping(clusters) = fields.entry("Clusters".into()).or_insert_with(|| Value::Mapping(Mapping::new())) else { continue };
+            match entry {
+                None => { clusters.entry(tier.into()).or_insert(value); }
+                Some(entry) => {
+                    if let Value::Mapping(range) = clusters.entry(tier.into()).or_insert_with(|| Value::Mapping(Mapping::new())) {
+                        range.entry(entry.into()).or_insert(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 旧格式字段对应的 (cluster 名, Clusters 条目内的键)；Governor 的条目本身就是调速器名
+fn legacy_cluster_key(section: &str, field: &str) -> Option<(&'static str, Option<&'static str>)> {
+    TIER_NAMES.iter().find_map(|&tier| match section {
+        "Freq" => match field.strip_prefix(tier)? {
+            "MinFreq" => Some((tier, Some("Min"))),
+            "MaxFreq" => Some((tier, Some("Max"))),
             _ => None,
+        },
+        "Governor" => (field == tier).then_some((tier, None)),
+        _ => None,
+    })
+}
+
+/// 配置目录下的各层，优先级从低到高：随模块分发的 SoC 基础配置、设备 / 厂商层、用户覆盖层
+///
+/// 模块更新只会替换基础层；设备层与用户层只写与下层不同的键，安装时原样保留。
+pub const LAYER_FILES: [&str; 3] = ["config.yaml", "device.yaml", "user.yaml"];
+
+pub fn get_config_dir() -> PathBuf {
+    crate::common::get_module_root().join("config")
+}
+
+/// 参与合并的层：基础层总是包含 (缺失时加载报错)，其余层存在时才包含
+pub fn layer_paths(config_dir: &Path) -> Vec<PathBuf> {
+    LAYER_FILES.iter().enumerate()
+        .map(|(i, name)| (i, config_dir.join(name)))
+        .filter(|(i, path)| *i == 0 || path.exists())
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// 读取各层 (内存中迁移到当前版本) 并深度合并；列表整体替换，不逐项合并
+pub fn merge_layers(paths: &[PathBuf]) -> anyhow::Result<serde_yaml::Value> {
+    use anyhow::Context;
+    let mut layers = Vec::new();
+    for path in paths {
+        let content = crate::migrate::read(crate::migrate::ConfigKind::Main, path)
+            .with_context(|| path.display().to_string())?;
+        layers.push(serde_yaml::from_str(&content).with_context(|| path.display().to_string())?);
+    }
+    Ok(merge_values(layers))
+}
+
+/// 依次深度合并各层；空文件或只有注释的层解析为 null，视为没有改动
+pub fn merge_values(layers: impl IntoIterator<Item = serde_yaml::Value>) -> serde_yaml::Value {
+    let mut merged = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+    for layer in layers.into_iter().filter(|l| !l.is_null()) {
+        merge_yaml(&mut merged, layer);
+    }
+    merged
+}
+
+/// 把 patch 深度合并进 base；映射的键名大小写不敏感，与各处的 alias 写法一致
+///
+/// patch 中值为 null 的键表示删除 base 中的同名键 (WebUI 在用户删掉下层的键时这样写入 user.yaml)
+fn merge_yaml(base: &mut serde_yaml::Value, patch: serde_yaml::Value) {
+    use serde_yaml::Value;
+    match (base, patch) {
+        (Value::Mapping(base), Value::Mapping(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    base.retain(|k, _| !same_key(k, &key));
+                    continue;
+                }
+                match base.iter_mut().find(|(k, _)| same_key(k, &key)).map(|(_, v)| v) {
+                    Some(slot) => merge_yaml(slot, value),
+                    None => { base.insert(key, value); }
+                }
+            }
         }
+        (base, patch) => *base = patch,
+    }
+}
+
+fn same_key(a: &serde_yaml::Value, b: &serde_yaml::Value) -> bool {
+    match (a.as_str(), b.as_str()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => a == b,
+    }
+}
+
+/// merge_yaml 的逆运算：value 中与 base 不同的键，base 中有而 value 中没有的键记为 null；
+/// 没有差异时返回 None。用于把整份配置转换为只含改动的覆盖层
+pub fn diff_values(base: &serde_yaml::Value, value: &serde_yaml::Value) -> Option<serde_yaml::Value> {
+    use serde_yaml::{Mapping, Value};
+    let find = |map: &'_ Mapping, key: &Value| map.iter().find(|(k, _)| same_key(k, key)).map(|(_, v)| v.clone());
+    match (base, value) {
+        (Value::Mapping(base), Value::Mapping(value)) => {
+            let mut out = Mapping::new();
+            for (key, v) in value {
+                let diff = match find(base, key) {
+                    Some(b) => diff_values(&b, v),
+                    None => (!v.is_null()).then(|| v.clone()),
+                };
+                if let Some(diff) = diff {
+                    out.insert(key.clone(), diff);
+                }
+            }
+            for key in base.keys().filter(|k| find(value, k).is_none()) {
+                out.insert(key.clone(), Value::Null);
+            }
+            (!out.is_empty()).then_some(Value::Mapping(out))
+        }
+        _ => (base != value).then(|| value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn later_layers_override_case_insensitively() {
+        let merged = merge_values([
+            yaml("a: {x: 1, y: 2}\nlist: [1, 2]"),
+            yaml("A: {y: 3}\nlist: [3]"),
+        ]);
+        assert_eq!(merged, yaml("a: {x: 1, y: 3}\nlist: [3]"));
+    }
+
+    #[test]
+    fn null_removes_key_from_lower_layers() {
+        let merged = merge_values([
+            yaml("a: {x: 1, y: 2}\nb: 1"),
+            yaml("a: {X: null}\nb: ~\nc: null"),
+        ]);
+        assert_eq!(merged, yaml("a: {y: 2}"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn empty_layer_changes_nothing() {
+        let merged = merge_values([yaml("a: 1"), yaml("# only comments")]);
+        assert_eq!(merged, yaml("a: 1"));
+    }
+
+    #[test]
+    fn diff_is_the_inverse_of_merge() {
+        let base = yaml("a: {x: 1, y: 2}\nb: [1, 2]\nc: 1\nschema: {k: v}");
+        let edited = yaml("A: {x: 1, y: 3}\nb: [1, 2]\nd: {z: 1}\nschema: {k: v}");
+        let diff = diff_values(&base, &edited).unwrap();
+        assert_eq!(diff, yaml("A: {y: 3}\nd: {z: 1}\nc: null"));
+        assert_eq!(merge_values([base.clone(), diff]), merge_values([yaml("a: {x: 1, y: 3}\nb: [1, 2]\nschema: {k: v}\nd: {z: 1}")]));
+        assert_eq!(diff_values(&base, &base), None);
+    }
+
+    #[test]
+    fn unresolvable_modes_are_dropped_alone() {
+        let config = Config::from_merged(yaml("
+modes:
+  a: {extends: b}
+  b: {extends: a}
+  orphan: {extends: nope}
+  video: {extends: powersave}
+")).unwrap();
+        assert_eq!(config.mode_names(), ["powersave", "balance", "performance", "fast", "video"]);
+        assert!(config.get_mode("a").is_none());
+        assert!(config.get_mode("orphan").is_none());
+
+        let mut config = config;
+        let errors = config.resolve_modes();
+        assert_eq!(errors, [
+            ("a".to_string(), "extends cycle: a -> b -> a".to_string()),
+            ("b".to_string(), "extends cycle: b -> a -> b".to_string()),
+            ("orphan".to_string(), "extends unknown mode 'nope'".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn extends_chains_merge_every_level() {
+        let config = Config::from_merged(yaml("
+powersave:
+  Governor: {global: schedutil}
+  Uclamp:
+    UclampTopAppMin: '0'
+    UclampTopAppMax: '80'
+    UclampTopApplatency_sensitive: '0'
+    UclampForeGroundMin: '0'
+    UclampForeGroundMax: '80'
+    UclampBackGroundMin: '0'
+    UclampBackGroundMax: '50'
+modes:
+  video:
+    extends: powersave
+    Uclamp: {UclampTopAppMax: '60'}
+  reading:
+    extends: video
+    Governor: {global: powersave}
+")).unwrap();
+        let reading = config.get_mode("reading").unwrap();
+        assert_eq!(reading.governor.global, "powersave");
+        assert_eq!(reading.uclamp.uclamp_top_app_max, "60");
+        assert_eq!(reading.uclamp.uclamp_top_app_min, "0");
+        let video = config.get_mode("video").unwrap();
+        assert_eq!(video.governor.global, "schedutil");
+    }
+
+    fn from_str<T: serde::de::DeserializeOwned>(s: &str) -> T {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    fn range(min: FreqExpr, max: FreqExpr) -> FreqRange {
+        FreqRange { min, max }
+    }
+
+    #[test]
+    fn legacy_fields_deserialize_into_clusters() {
+        let freq: FreqSettings = from_str("SmallCoreMinFreq: 300000\nSmallCoreMaxFreq: 1500000\nBigCoreMaxFreq: \"step:-2\"");
+        assert_eq!(freq.clusters.len(), 2);
+        assert_eq!(freq.clusters["SmallCore"], range(FreqExpr::Khz(300_000), FreqExpr::Khz(1_500_000)));
+        assert_eq!(freq.clusters["BigCore"], range(FreqExpr::Min, FreqExpr::Step(-2)));
+
+        // 空字符串沿用 global
+        let governor: GovernorSettings = from_str("global: schedutil\nSmallCore: powersave\nBigCore: \"\"");
+        assert_eq!(governor.global, "schedutil");
+        assert_eq!(governor.clusters, BTreeMap::from([("SmallCore".to_string(), "powersave".to_string())]));
+
+        let boost: AppLaunchBoostSettings = from_str("SmallCoreBoostFreq: 1800000\nSuperBigCoreBoostFreq: max");
+        assert_eq!(boost.clusters, BTreeMap::from([
+            ("SmallCore".to_string(), FreqExpr::Khz(1_800_000)),
+            ("SuperBigCore".to_string(), FreqExpr::Max),
+        ]));
+
+        let framework: CoreFramework = from_str("SmallCorePath: 0\nMediumCorePath: 4\nBigCorePath: 7\nSuperBigCorePath: -1");
+        assert_eq!(framework.clusters(), [
+            ClusterDef::new(Some("SmallCore"), 0),
+            ClusterDef::new(Some("MediumCore"), 4),
+            ClusterDef::new(Some("BigCore"), 7),
+        ]);
+    }
+
+    #[test]
+    fn clusters_win_over_legacy_fields() {
+        let freq: FreqSettings = from_str("
+SmallCoreMaxFreq: 1000000
+MediumCoreMaxFreq: 1200000
+Clusters:
+  SmallCore: {Max: 1500000}
+");
+        assert_eq!(freq.clusters["SmallCore"], range(FreqExpr::Min, FreqExpr::Khz(1_500_000)));
+        assert_eq!(freq.clusters["MediumCore"], range(FreqExpr::Min, FreqExpr::Khz(1_200_000)));
+
+        let governor: GovernorSettings = from_str("SmallCore: powersave\nClusters: {SmallCore: performance}");
+        assert_eq!(governor.clusters["SmallCore"], "performance");
+
+        let boost: AppLaunchBoostSettings = from_str("BigCoreBoostFreq: 1800000\nClusters: {BigCore: max}");
+        assert_eq!(boost.clusters["BigCore"], FreqExpr::Max);
+
+        let framework: CoreFramework = from_str("SmallCorePath: 0\nClusters: [{Name: little, Policy: 2}]");
+        assert_eq!(framework.clusters(), [ClusterDef::new(Some("little"), 2)]);
+    }
+
+    #[test]
+    fn custom_modes_override_legacy_fields_of_the_parent() {
+        // 父模式序列化后是 Clusters 写法，子模式的旧格式字段仍要逐项覆盖它
+        let config = Config::from_merged(yaml("
+powersave:
+  Governor: {global: schedutil, SmallCore: powersave}
+  Freq: {SmallCoreMinFreq: 300000, SmallCoreMaxFreq: 1500000}
+modes:
+  video:
+    extends: powersave
+    Governor: {SmallCore: performance}
+    Freq: {SmallCoreMaxFreq: 1200000}
+")).unwrap();
+        let video = config.get_mode("video").unwrap();
+        assert_eq!(video.freq.clusters["SmallCore"], range(FreqExpr::Khz(300_000), FreqExpr::Khz(1_200_000)));
+        assert_eq!(video.governor.clusters["SmallCore"], "performance");
+    }
+}
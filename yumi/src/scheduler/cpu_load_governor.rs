@@ -23,8 +23,11 @@
 
 use crate::scheduler::config::Config;
 use crate::monitor::config::CpuLoadGovernorConfig;
-use super::fas::FastWriter;
+use super::actuator::{FreqActuator, MockActuator, SysfsActuator};
+use crate::journal::{self, JournalEvent};
+use crate::utils::host_path;
 use log::{info, debug, warn};
+use serde::Serialize;
 use std::fs;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -43,8 +46,7 @@ struct ClusterState {
     cached_ratios: Vec<f32>,
     _freq_min: f32,
     _freq_max: f32,
-    max_writer: FastWriter,
-    min_writer: FastWriter,
+    actuator: Box<dyn FreqActuator>,
     /// 当前 perf 指数 (0.0~1.0)，平滑后的值
     current_perf: f32,
     /// 当前写入的频率值
@@ -72,15 +74,7 @@ impl ClusterState {
     /// 写入频率 (min=max 锁频模式)
     fn write_freq(&mut self, freq: u32) {
         if freq == self.current_freq { return; }
-        if freq >= self.current_freq {
-            // 升频：先拉 max 再拉 min
-            self.max_writer.write_value_force(freq);
-            self.min_writer.write_value_force(freq);
-        } else {
-            // 降频：先降 min 再降 max
-            self.min_writer.write_value_force(freq);
-            self.max_writer.write_value_force(freq);
-        }
+        self.actuator.lock(freq);
         self.current_freq = freq;
     }
 
@@ -93,6 +87,21 @@ impl ClusterState {
     }
 }
 
+/// 单 cluster 状态快照 (控制 socket `clg_state` 请求与 state.json)
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterSnapshot {
+    pub policy_id: i32,
+    pub cpus: Vec<usize>,
+    pub current_perf: f32,
+    pub current_freq: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClgSnapshot {
+    pub active: bool,
+    pub clusters: Vec<ClusterSnapshot>,
+}
+
 // ════════════════════════════════════════════════════════════════
 //  CpuLoadGovernor — 主控制器
 // ════════════════════════════════════════════════════════════════
@@ -127,6 +136,18 @@ impl CpuLoadGovernor {
         self.active
     }
 
+    pub fn snapshot(&self) -> ClgSnapshot {
+        ClgSnapshot {
+            active: self.active,
+            clusters: self.clusters.iter().map(|c| ClusterSnapshot {
+                policy_id: c.policy_id,
+                cpus: c.affected_cpus.clone(),
+                current_perf: c.current_perf,
+                current_freq: c.current_freq,
+            }).collect(),
+        }
+    }
+
     // ────────────────────────────────────────────────────────────
     //  初始化 / 释放
     // ────────────────────────────────────────────────────────────
@@ -137,12 +158,8 @@ impl CpuLoadGovernor {
         self.release();
         self.cfg = gov_cfg.clone();
 
-        let ci = &config.core_framework;
-        let clusters = [ci.small_core_path, ci.medium_core_path,
-                        ci.big_core_path, ci.super_big_core_path];
-
-        for &pid in &clusters {
-            if pid == -1 { continue; }
+        for cluster in config.core_framework.clusters() {
+            let pid = cluster.policy;
 
             // 1. 设置 scaling_governor 为 performance，夺取频率控制权
             let gov_path = format!(
@@ -152,7 +169,7 @@ impl CpuLoadGovernor {
             // 2. 读取可用频率表
             let freq_path = format!(
                 "/sys/devices/system/cpu/cpufreq/policy{}/scaling_available_frequencies", pid);
-            let mut freqs: Vec<u32> = fs::read_to_string(&freq_path)
+            let mut freqs: Vec<u32> = fs::read_to_string(host_path(&freq_path))
                 .unwrap_or_default()
                 .split_whitespace()
                 .filter_map(|s| s.parse().ok())
@@ -165,61 +182,89 @@ impl CpuLoadGovernor {
             let affected = Self::read_affected_cpus(pid);
             if affected.is_empty() { continue; }
 
-            // 4. 构建 ratio 缓存
-            let fmin = *freqs.first().unwrap() as f32;
-            let fmax = *freqs.last().unwrap() as f32;
-            let range = (fmax - fmin).max(1.0);
-            let cached_ratios: Vec<f32> = freqs.iter()
-                .map(|&f| (f as f32 - fmin) / range)
-                .collect();
+            // 4. 接管 scaling_min/max_freq
+            let actuator = Box::new(SysfsActuator::open(pid as usize));
+            self.push_cluster(pid, affected, freqs, actuator);
+        }
 
-            // 5. 创建 sysfs 写入器
-            let max_writer = FastWriter::new(format!(
-                "/sys/devices/system/cpu/cpufreq/policy{}/scaling_max_freq", pid));
-            let min_writer = FastWriter::new(format!(
-                "/sys/devices/system/cpu/cpufreq/policy{}/scaling_min_freq", pid));
-
-            // 6. 初始频率设置为中间值 (perf_init)
-            let init_perf = self.cfg.perf_init.clamp(self.cfg.perf_floor, self.cfg.perf_ceil);
-            let mut cluster = ClusterState {
-                policy_id: pid,
-                affected_cpus: affected.clone(),
-                available_freqs: freqs,
-                cached_ratios,
-                _freq_min: fmin,
-                _freq_max: fmax,
-                max_writer,
-                min_writer,
-                current_perf: init_perf,
-                current_freq: 0,
-                down_wait: 0,
-            };
-
-            let init_freq = cluster.find_nearest_freq(init_perf);
-            cluster.max_writer.write_value_force(init_freq);
-            cluster.min_writer.write_value_force(init_freq);
-            cluster.current_freq = init_freq;
-
-            info!("CLG[P{}] init | cpus={:?} | freqs={}-{} MHz | P={:.2} -> {} kHz",
-                pid, affected,
-                (fmin / 1000.0) as u32, (fmax / 1000.0) as u32,
-                init_perf, init_freq / 1000);
-
-            self.clusters.push(cluster);
+        self.finish_init();
+    }
+
+    /// 仿真用：按给定拓扑构建不接触 sysfs 的 cluster
+    ///
+    /// `tables` 为 (policy_id, affected_cpus, 可用频率表)。
+    pub fn load_mock_policies(&mut self, gov_cfg: &CpuLoadGovernorConfig, tables: &[(i32, Vec<usize>, Vec<u32>)]) {
+        self.release();
+        self.cfg = gov_cfg.clone();
+        for (pid, affected, freqs) in tables {
+            let mut freqs = freqs.clone();
+            freqs.sort_unstable();
+            freqs.dedup();
+            if freqs.is_empty() || affected.is_empty() { continue; }
+            self.push_cluster(*pid, affected.clone(), freqs, Box::new(MockActuator::new(*pid as usize)));
         }
+        self.finish_init();
+    }
 
+    /// 构建 ratio 缓存并以 perf_init 对应的频率接管 cluster；`freqs` 须已排序去重
+    fn push_cluster(&mut self, pid: i32, affected: Vec<usize>, freqs: Vec<u32>, actuator: Box<dyn FreqActuator>) {
+        let fmin = *freqs.first().unwrap() as f32;
+        let fmax = *freqs.last().unwrap() as f32;
+        let range = (fmax - fmin).max(1.0);
+        let cached_ratios: Vec<f32> = freqs.iter()
+            .map(|&f| (f as f32 - fmin) / range)
+            .collect();
+
+        // 初始频率设置为中间值 (perf_init)
+        let init_perf = self.cfg.perf_init.clamp(self.cfg.perf_floor, self.cfg.perf_ceil);
+        let mut cluster = ClusterState {
+            policy_id: pid,
+            affected_cpus: affected,
+            available_freqs: freqs,
+            cached_ratios,
+            _freq_min: fmin,
+            _freq_max: fmax,
+            actuator,
+            current_perf: init_perf,
+            current_freq: 0,
+            down_wait: 0,
+        };
+
+        let init_freq = cluster.find_nearest_freq(init_perf);
+        cluster.actuator.lock(init_freq);
+        cluster.current_freq = init_freq;
+
+        info!("CLG[P{}] init | cpus={:?} | freqs={}-{} MHz | P={:.2} -> {} kHz",
+            pid, cluster.affected_cpus,
+            (fmin / 1000.0) as u32, (fmax / 1000.0) as u32,
+            init_perf, init_freq / 1000);
+
+        self.clusters.push(cluster);
+    }
+
+    fn finish_init(&mut self) {
         self.active = !self.clusters.is_empty();
         if self.active {
             info!("CPU Load Governor activated with {} cluster(s)", self.clusters.len());
+            journal::record(JournalEvent::ClgActivate { clusters: self.clusters.len() });
         } else {
             warn!("CPU Load Governor: no valid clusters found, staying inactive");
         }
     }
 
+    /// 某个 policy 最近一次下发的 scaling_max_freq
+    pub fn written_freq(&self, policy_id: i32) -> Option<u32> {
+        self.clusters.iter()
+            .find(|c| c.policy_id == policy_id)
+            .and_then(|c| c.actuator.applied())
+            .map(|(_, max)| max)
+    }
+
     /// 释放频率控制权 (不重置频率——调用方自行处理后续)
     pub fn release(&mut self) {
         if self.active {
             info!("CPU Load Governor deactivated");
+            journal::record(JournalEvent::ClgRelease);
         }
         self.clusters.clear();
         self.active = false;
@@ -241,6 +286,7 @@ impl CpuLoadGovernor {
     /// 每个 eBPF 采样周期调用一次 (约 200ms)
     pub fn on_load_update(&mut self, core_utils: &[f32]) {
         if !self.active { return; }
+        crate::metrics::CLG_TICKS.inc();
 
         // Boost 期间暂停写频率，避免和 AppLaunchBoost 互相覆盖 sysfs
         // 但仍然更新内部 perf 指数，这样 Boost 结束后能立刻用正确的 perf 值恢复
@@ -304,10 +350,10 @@ impl CpuLoadGovernor {
         if !self.active { return; }
         for cluster in &mut self.clusters {
             let target_freq = cluster.find_nearest_freq(cluster.current_perf);
-            // 强制使 last_value 失效，确保下次一定会写入
-            cluster.max_writer.invalidate();
-            cluster.min_writer.invalidate();
-            cluster.write_freq(target_freq);
+            // Boost 期间节点被改写过，忘掉上次下发的值，确保完整写入
+            cluster.actuator.invalidate();
+            cluster.actuator.lock(target_freq);
+            cluster.current_freq = target_freq;
             debug!("CLG[P{}] resync after boost: perf={:.2} -> freq={}kHz",
                 cluster.policy_id, cluster.current_perf, target_freq / 1000);
         }
@@ -320,10 +366,93 @@ impl CpuLoadGovernor {
     fn read_affected_cpus(policy_id: i32) -> Vec<usize> {
         let path = format!(
             "/sys/devices/system/cpu/cpufreq/policy{}/affected_cpus", policy_id);
-        fs::read_to_string(&path)
+        fs::read_to_string(host_path(&path))
             .unwrap_or_default()
             .split_whitespace()
             .filter_map(|s| s.parse::<usize>().ok())
             .collect()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::actuator::{FreqNode, MockHandle};
+
+    const TABLE: [u32; 4] = [300_000, 1_000_000, 1_500_000, 2_000_000];
+
+    fn governor() -> (CpuLoadGovernor, MockHandle) {
+        let mut clg = CpuLoadGovernor::new();
+        let (actuator, handle) = MockActuator::recording(0);
+        clg.push_cluster(0, vec![0, 1], TABLE.to_vec(), Box::new(actuator));
+        clg.finish_init();
+        (clg, handle)
+    }
+
+    fn writes(handle: &MockHandle) -> Vec<(FreqNode, u32)> {
+        std::mem::take(&mut handle.lock().unwrap().writes)
+    }
+
+    #[test]
+    fn init_locks_perf_init_frequency() {
+        let (clg, handle) = governor();
+        // perf_init 0.5 最接近 1000000 (ratio 0.41)
+        assert_eq!(writes(&handle), [
+            (FreqNode::Max, 1_000_000),
+            (FreqNode::Min, 1_000_000),
+            (FreqNode::Max, 1_000_000),
+        ]);
+        assert_eq!(clg.written_freq(0), Some(1_000_000));
+    }
+
+    #[test]
+    fn load_increase_raises_max_first() {
+        let (mut clg, handle) = governor();
+        writes(&handle);
+        clg.on_load_update(&[1.0, 0.2]);
+        assert_eq!(writes(&handle), [(FreqNode::Max, 1_500_000), (FreqNode::Min, 1_500_000)]);
+        assert_eq!(clg.snapshot().clusters[0].current_freq, 1_500_000);
+    }
+
+    #[test]
+    fn load_decrease_lowers_min_first() {
+        let (mut clg, handle) = governor();
+        clg.on_load_update(&[1.0]);
+        writes(&handle);
+        for _ in 0..10 {
+            clg.on_load_update(&[0.0]);
+        }
+        let w = writes(&handle);
+        assert!(!w.is_empty());
+        assert_eq!(w[0].0, FreqNode::Min);
+        assert_eq!(handle.lock().unwrap().max, Some(300_000));
+    }
+
+    #[test]
+    fn unchanged_frequency_is_not_rewritten() {
+        let (mut clg, handle) = governor();
+        writes(&handle);
+        // perf 已在 floor 以上，低负载但未到降频等待次数
+        clg.on_load_update(&[0.45]);
+        assert!(writes(&handle).is_empty());
+    }
+
+    #[test]
+    fn boost_pauses_writes_and_resync_rewrites() {
+        let (mut clg, handle) = governor();
+        let flag = Arc::new(AtomicBool::new(true));
+        clg.set_boost_flag(Arc::clone(&flag));
+        writes(&handle);
+
+        clg.on_load_update(&[1.0]);
+        assert!(writes(&handle).is_empty());
+        assert!(clg.snapshot().clusters[0].current_perf > 0.5);
+
+        flag.store(false, Ordering::Relaxed);
+        clg.resync_after_boost();
+        assert_eq!(writes(&handle), [
+            (FreqNode::Max, 1_500_000),
+            (FreqNode::Min, 1_500_000),
+            (FreqNode::Max, 1_500_000),
+        ]);
+    }
+}
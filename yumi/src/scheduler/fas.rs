@@ -19,94 +19,21 @@ use crate::scheduler::config::Config;
 use crate::monitor::config::{
     FasRulesConfig, ClusterProfile, PerAppProfile,
 };
-use std::fs::{self, File, OpenOptions};
-use std::io::{Write, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use serde::Serialize;
 use std::time::Instant;
-use log::{info, warn, debug};
-
-// ════════════════════════════════════════════════════════════════
-//  FastWriter — 带去重 + unmount 的 sysfs 写入器
-// ════════════════════════════════════════════════════════════════
-
-pub struct FastWriter {
-    file: Option<File>,
-    last_value: Option<u32>,
-    buf: [u8; 20],
-    path: PathBuf,
-}
-
-impl FastWriter {
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        let path_ref = path.as_ref();
-        Self::try_unmount(path_ref);
-        let _ = crate::utils::enable_perm(path_ref);
-        let file = OpenOptions::new().write(true).open(path_ref)
-            .map_err(|e| log::error!("FAS: failed to open {}: {}", path_ref.display(), e))
-            .ok();
-        Self { file, last_value: None, buf: [0u8; 20], path: path_ref.to_path_buf() }
-    }
-
-    fn try_unmount(path: &Path) {
-        if let Some(path_str) = path.to_str() {
-            if let Ok(cpath) = std::ffi::CString::new(path_str) {
-                let ret = unsafe { libc::umount2(cpath.as_ptr(), libc::MNT_DETACH) };
-                if ret != 0 {
-                    let errno = std::io::Error::last_os_error();
-                    if errno.raw_os_error() != Some(libc::EINVAL)
-                        && errno.raw_os_error() != Some(libc::ENOENT) {
-                        log::debug!("FAS: umount2({}) = {}", path_str, errno);
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn re_unmount(&self) { Self::try_unmount(&self.path); }
-
-    #[allow(dead_code)]
-    pub fn write_value(&mut self, value: u32) {
-        if self.last_value == Some(value) { return; }
-        self.do_write(value);
-    }
-
-    pub fn write_value_force(&mut self, value: u32) {
-        self.do_write(value);
-    }
-
-    pub fn invalidate(&mut self) { self.last_value = None; }
-    pub fn is_valid(&self) -> bool { self.file.is_some() }
-
-    fn do_write(&mut self, value: u32) {
-        if let Some(file) = &mut self.file {
-            let len = Self::u32_to_buf(value, &mut self.buf);
-            let _ = file.seek(SeekFrom::Start(0));
-            if let Err(e) = file.write_all(&self.buf[..len]) {
-                log::error!("FAS: write freq {} failed: {}", value, e);
-            }
-            self.last_value = Some(value);
-        }
-    }
-
-    fn u32_to_buf(mut v: u32, buf: &mut [u8; 20]) -> usize {
-        if v == 0 { buf[0] = b'0'; buf[1] = b'\n'; return 2; }
-        let mut pos = 18;
-        while v > 0 { buf[pos] = b'0' + (v % 10) as u8; v /= 10; pos -= 1; }
-        let start = pos + 1;
-        let digit_len = 19 - start;
-        buf.copy_within(start..19, 0);
-        buf[digit_len] = b'\n';
-        digit_len + 1
-    }
-}
+use super::actuator::{FreqActuator, MockActuator, SysfsActuator};
+use super::topology;
+use crate::clock;
+use crate::journal::{self, JournalEvent};
+use crate::metrics;
+use log::{info, warn};
 
 // ════════════════════════════════════════════════════════════════
 //  PolicyController — 单个 cpufreq policy 的频率控制
 // ════════════════════════════════════════════════════════════════
 
 pub struct PolicyController {
-    pub max_writer: FastWriter,
-    pub min_writer: FastWriter,
+    pub freq: Box<dyn FreqActuator>,
     pub available_freqs: Vec<u32>,
     cached_ratios: Vec<f32>,
     pub current_freq: u32,
@@ -116,7 +43,6 @@ pub struct PolicyController {
     freq_min: f32,
     freq_max: f32,
 
-    verify_freq: Option<u32>,
     verify_timer: Instant,
 
     pub ignore_write: bool,
@@ -124,8 +50,7 @@ pub struct PolicyController {
 
 impl PolicyController {
     pub fn new(
-        max_writer: FastWriter,
-        min_writer: FastWriter,
+        freq: Box<dyn FreqActuator>,
         available_freqs: Vec<u32>,
         policy_id: usize,
         cluster_profile: ClusterProfile,
@@ -138,11 +63,10 @@ impl PolicyController {
             .map(|&f| (f as f32 - freq_min) / range)
             .collect();
         Self {
-            max_writer, min_writer, available_freqs, cached_ratios,
+            freq, available_freqs, cached_ratios,
             current_freq, policy_id, cluster_profile,
             freq_hold_frames: 0, freq_min, freq_max,
-            verify_freq: None,
-            verify_timer: Instant::now(),
+            verify_timer: clock::now(),
             ignore_write: false,
         }
     }
@@ -169,87 +93,45 @@ impl PolicyController {
     /// 锁频写入 (min=max)，用于关键 cluster
     pub fn apply_freq_locked(&mut self, target_freq: u32) {
         if self.ignore_write { return; }
-        if target_freq >= self.current_freq {
-            self.max_writer.write_value_force(target_freq);
-            self.min_writer.write_value_force(target_freq);
-        } else {
-            self.min_writer.write_value_force(target_freq);
-            self.max_writer.write_value_force(target_freq);
-        }
+        // 先校验上一次下发的频率是否生效，再写新的
+        self.verify_if_due();
+        self.freq.lock(target_freq);
         self.current_freq = target_freq;
         self.freq_hold_frames = 2;
-        self.do_verify_freq(target_freq);
     }
 
     /// 松散写入 (min=lowest, max=target)，用于非关键 cluster 或负载较低时
     #[allow(dead_code)]
     pub fn apply_freq_relaxed(&mut self, target_freq: u32) {
         if self.ignore_write { return; }
-        let min_f = self.available_freqs[0];
-        self.min_writer.write_value_force(min_f);
-        self.max_writer.write_value_force(target_freq);
+        self.freq.set_range(self.available_freqs[0], target_freq);
         self.current_freq = target_freq;
         self.freq_hold_frames = 2;
     }
 
-    fn do_verify_freq(&mut self, write_freq: u32) {
+    fn verify_if_due(&mut self) {
         // [Fix] 缩短校验间隔：3秒→1.5秒，更快发现内核频率覆写
         // 日志中104次freq mismatch说明内核覆写非常频繁
         let verify_interval = std::time::Duration::from_millis(1500);
-        if self.verify_timer.elapsed() >= verify_interval {
-            self.verify_timer = Instant::now();
-            if let Some(expected) = self.verify_freq {
-                if let Some(actual) = self.read_current_freq() {
-                    let min_ok = self.available_freqs.iter()
-                        .take_while(|&&f| f <= expected).last().copied().unwrap_or(expected);
-                    let max_ok = self.available_freqs.iter()
-                        .find(|&&f| f >= expected).copied().unwrap_or(expected);
-                    if actual < min_ok || actual > max_ok {
-                        warn!("FAS[P{}]: freq mismatch! expected {}-{}, actual {} → emergency reapply",
-                            self.policy_id, min_ok, max_ok, actual);
-                        self.max_writer.re_unmount();
-                        self.min_writer.re_unmount();
-                        self.max_writer.invalidate();
-                        self.min_writer.invalidate();
-                        if write_freq >= actual {
-                            self.max_writer.write_value_force(write_freq);
-                            self.min_writer.write_value_force(write_freq);
-                        } else {
-                            self.min_writer.write_value_force(write_freq);
-                            self.max_writer.write_value_force(write_freq);
-                        }
-                    }
-                }
-            }
+        if clock::elapsed(self.verify_timer) < verify_interval { return; }
+        self.verify_timer = clock::now();
+        if let Some(m) = self.freq.verify(&self.available_freqs) {
+            metrics::FAS_FREQ_MISMATCHES.inc();
+            warn!("FAS[P{}]: freq mismatch! expected {}-{}, actual {} → emergency reapply",
+                self.policy_id, m.min_ok, m.max_ok, m.actual);
         }
-        self.verify_freq = Some(write_freq);
-    }
-
-    fn read_current_freq(&self) -> Option<u32> {
-        let path = format!(
-            "/sys/devices/system/cpu/cpufreq/policy{}/scaling_cur_freq",
-            self.policy_id
-        );
-        fs::read_to_string(&path).ok()?.trim().parse::<u32>().ok()
     }
 
     pub fn force_reapply(&mut self) {
         if self.ignore_write { return; }
-        self.max_writer.re_unmount();
-        self.min_writer.re_unmount();
-        self.max_writer.invalidate();
-        self.min_writer.invalidate();
-        self.min_writer.write_value_force(self.current_freq);
-        self.max_writer.write_value_force(self.current_freq);
+        self.freq.force_reapply();
     }
 
     pub fn reset(&mut self) {
         let min_f = self.available_freqs[0];
         let max_f = *self.available_freqs.last().unwrap();
-        self.max_writer.write_value_force(max_f);
-        self.min_writer.write_value_force(min_f);
+        self.freq.set_range(min_f, max_f);
         self.current_freq = max_f;
-        self.verify_freq = None;
     }
 }
 
@@ -376,21 +258,10 @@ impl PidController {
 //  工具函数
 // ════════════════════════════════════════════════════════════════
 
-fn probe_policy_capacity(policy_id: i32) -> Option<u32> {
-    let related_str = fs::read_to_string(
-        format!("/sys/devices/system/cpu/cpufreq/policy{}/related_cpus", policy_id))
-        .or_else(|_| fs::read_to_string(
-            format!("/sys/devices/system/cpu/cpufreq/policy{}/affected_cpus", policy_id)))
-        .ok()?;
-    let first_cpu: u32 = related_str.split_whitespace().next()?.parse().ok()?;
-    fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpu_capacity", first_cpu))
-        .ok()?.trim().parse::<u32>().ok()
-}
-
 fn auto_compute_capacity_weights(policy_ids: &[i32]) -> Option<Vec<(i32, f32)>> {
     let caps: Vec<(i32, u32)> = policy_ids.iter()
         .filter(|&&pid| pid != -1)
-        .filter_map(|&pid| probe_policy_capacity(pid).map(|c| (pid, c)))
+        .filter_map(|&pid| topology::probe_capacity(pid).map(|c| (pid, c)))
         .collect();
     if caps.is_empty() || caps.iter().any(|&(_, c)| c == 0) { return None; }
     let min_cap = caps.iter().map(|&(_, c)| c).min().unwrap() as f32;
@@ -420,6 +291,31 @@ enum GearDecision {
     Downgrade { target: f32, perf: f32, dampen: u32 },
 }
 
+// ════════════════════════════════════════════════════════════════
+//  FasSnapshot — 供控制 socket 与 state.json 使用的运行时快照
+// ════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicySnapshot {
+    pub policy_id: usize,
+    pub current_freq: u32,
+    pub min_freq: u32,
+    pub max_freq: u32,
+    pub ignore_write: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FasSnapshot {
+    pub package: String,
+    pub target_fps: f32,
+    pub avg_fps: f32,
+    pub perf_index: f32,
+    pub fps_margin: f32,
+    pub is_loading: bool,
+    pub temperature: f64,
+    pub policies: Vec<PolicySnapshot>,
+}
+
 // ════════════════════════════════════════════════════════════════
 //  FasController — 主控制器 (重构版)
 //
@@ -532,7 +428,7 @@ impl FasController {
             downgrade_boost_perf_saved: 0.0,
             jank_cooldown: 0,
             jank_streak: 0,
-            init_time: Instant::now(),
+            init_time: clock::now(),
             freq_force_counter: 0,
             cached_norm: 1.0,
             cached_budget_ms: 16.67,
@@ -630,6 +526,7 @@ impl FasController {
     fn do_gear_switch(&mut self, new_fps: f32, perf: f32, dampen: u32) {
         let old = self.current_target_fps;
         self.current_target_fps = new_fps;
+        metrics::FAS_GEAR_SWITCHES.inc();
         self.refresh_cached_values();
         self.upgrade_confirm_frames = 0;
         self.downgrade_confirm_frames = 0;
@@ -649,6 +546,12 @@ impl FasController {
         self.downgrade_boost_remaining = 0;
         self.floor_stuck_frames = 0;
         info!("FAS: gear {:.0} -> {:.0}fps | P -> {:.2}", old, new_fps, final_perf);
+        journal::record(JournalEvent::GearSwitch {
+            package: &self.current_package,
+            from_fps: old,
+            to_fps: new_fps,
+            perf: final_perf,
+        });
     }
 
     fn reset_runtime(&mut self) {
@@ -737,6 +640,26 @@ impl FasController {
     }
 
     pub fn set_temperature(&mut self, temp: f64) { self.current_temperature = temp; }
+
+    /// 当前运行时状态快照 (控制 socket `fas_state` 请求与 state.json)
+    pub fn snapshot(&self) -> FasSnapshot {
+        FasSnapshot {
+            package: self.current_package.clone(),
+            target_fps: self.current_target_fps,
+            avg_fps: self.fps_window.mean(),
+            perf_index: self.perf_index,
+            fps_margin: self.fps_margin,
+            is_loading: self.is_loading,
+            temperature: self.current_temperature,
+            policies: self.policies.iter().map(|p| PolicySnapshot {
+                policy_id: p.policy_id,
+                current_freq: p.current_freq,
+                min_freq: p.available_freqs.first().copied().unwrap_or(0),
+                max_freq: p.available_freqs.last().copied().unwrap_or(0),
+                ignore_write: p.ignore_write,
+            }).collect(),
+        }
+    }
     pub fn set_temp_threshold(&mut self, thresh: f64) { self.temp_threshold = thresh; }
 
     #[allow(dead_code)]
@@ -918,11 +841,10 @@ impl FasController {
     //  load_policies — 初始化
     // ════════════════════════════════════════════════════════════
 
-    pub fn load_policies(&mut self, config: &Config, fas_rules: &FasRulesConfig) {
+    /// 全量初始化前的规则装载 (load_policies 与 load_mock_policies 共用)
+    fn apply_rules(&mut self, fas_rules: &FasRulesConfig) {
         self.policies.clear();
         self.cfg = fas_rules.clone();
-        // 迁移旧的 per_app_margins
-        self.cfg.migrate_legacy_margins();
 
         self.pid = PidController::new(fas_rules.pid.kp, fas_rules.pid.ki, fas_rules.pid.kd);
 
@@ -930,54 +852,80 @@ impl FasController {
             self.fps_gears = fas_rules.fps_gears.clone();
         }
         if let Ok(m) = fas_rules.fps_margin.parse::<f32>() { self.fps_margin = m; }
+    }
+
+    /// policies 就绪后重置运行时状态并下发初始频率
+    fn start_session(&mut self, fas_rules: &FasRulesConfig) {
+        self.current_target_fps = *self.fps_gears.iter()
+            .reduce(|a, b| if a > b { a } else { b }).unwrap_or(&60.0);
+        self.reset_runtime();
+        self.refresh_cached_values();
+        self.init_time = clock::now();
+        self.perf_index = self.cfg.perf_cold_boot;
+        self.temp_threshold = fas_rules.core_temp_threshold;
+        self.apply_freqs();
+
+        info!("FAS init | {:.0}fps margin:{:.1} clusters:{} P:{:.2} profiles:{}",
+            self.current_target_fps, self.fps_margin, self.policies.len(),
+            self.perf_index, self.cfg.per_app_profiles.len());
+        info!("FAS PID  | Kp={:.4} Ki={:.4} Kd={:.4}",
+            self.cfg.pid.kp, self.cfg.pid.ki, self.cfg.pid.kd);
+    }
+
+    /// 离线回放：用给定频率表构建不接触 sysfs 的 mock policy
+    pub fn load_mock_policies(&mut self, fas_rules: &FasRulesConfig, tables: &[(usize, Vec<u32>, ClusterProfile)]) {
+        self.apply_rules(fas_rules);
+        for (policy_id, freqs, profile) in tables {
+            let mut freqs = freqs.clone();
+            freqs.sort_unstable();
+            freqs.dedup();
+            let Some(&max_f) = freqs.last() else { continue };
+            self.policies.push(PolicyController::new(
+                Box::new(MockActuator::new(*policy_id)), freqs, *policy_id, profile.clone(), max_f,
+            ));
+        }
+        self.start_session(fas_rules);
+    }
+
+    pub fn load_policies(&mut self, config: &Config, fas_rules: &FasRulesConfig) {
+        self.apply_rules(fas_rules);
 
         let _ = crate::utils::try_write_file("/sys/module/perfmgr/parameters/perfmgr_enable", "0");
         let _ = crate::utils::try_write_file("/sys/module/mtk_fpsgo/parameters/perfmgr_enable", "0");
 
-        let ci = &config.core_framework;
-        let clusters = [ci.small_core_path, ci.medium_core_path,
-                        ci.big_core_path, ci.super_big_core_path];
+        let clusters = config.core_framework.clusters();
+        let policy_ids: Vec<i32> = clusters.iter().map(|c| c.policy).collect();
 
         let auto_w = if fas_rules.auto_capacity_weight {
-            auto_compute_capacity_weights(&clusters).map(|w| {
+            auto_compute_capacity_weights(&policy_ids).map(|w| {
                 info!("FAS: auto capacity:");
                 for &(pid, wt) in &w {
                     info!("  P{}: cap={} → w={:.2}", pid,
-                        probe_policy_capacity(pid).unwrap_or(0), wt);
+                        topology::probe_capacity(pid).unwrap_or(0), wt);
                 }
                 w
             })
         } else { None };
 
-        for (idx, &pid) in clusters.iter().enumerate() {
-            if pid == -1 { continue; }
+        for cluster in &clusters {
+            let pid = cluster.policy;
             let _ = crate::utils::try_write_file(
                 &format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_governor", pid),
                 "performance");
 
-            let mut freqs: Vec<u32> = fs::read_to_string(
-                format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_available_frequencies", pid))
-                .unwrap_or_default()
-                .split_whitespace()
-                .filter_map(|s| s.parse().ok()).collect();
+            let freqs = topology::available_freqs(pid);
             if freqs.is_empty() { continue; }
-            freqs.sort_unstable();
-            freqs.dedup();
 
             let max_f = *freqs.last().unwrap();
-            let mut mw = FastWriter::new(format!(
-                "/sys/devices/system/cpu/cpufreq/policy{}/scaling_max_freq", pid));
-            let mut nw = FastWriter::new(format!(
-                "/sys/devices/system/cpu/cpufreq/policy{}/scaling_min_freq", pid));
-            mw.write_value_force(max_f);
-            nw.write_value_force(max_f);
+            let mut freq: Box<dyn FreqActuator> = Box::new(SysfsActuator::open(pid as usize));
+            freq.lock(max_f);
 
             let profile = auto_w.as_ref()
                 .and_then(|aw| aw.iter().find(|&&(p, _)| p == pid))
                 .map(|&(_, w)| ClusterProfile { capacity_weight: w })
-                .unwrap_or_else(|| fas_rules.cluster_profiles.get(idx).cloned().unwrap_or_default());
+                .unwrap_or_else(|| fas_rules.cluster_profiles.get(cluster));
 
-            if !mw.is_valid() || !nw.is_valid() {
+            if !freq.is_valid() {
                 warn!("FAS[P{}] sysfs writer invalid, freq control may fail!", pid);
             }
 
@@ -986,24 +934,11 @@ impl FasController {
                 profile.capacity_weight);
 
             self.policies.push(PolicyController::new(
-                mw, nw, freqs, pid as usize, profile, max_f,
+                freq, freqs, pid as usize, profile, max_f,
             ));
         }
 
-        self.current_target_fps = *self.fps_gears.iter()
-            .reduce(|a, b| if a > b { a } else { b }).unwrap_or(&60.0);
-        self.reset_runtime();
-        self.refresh_cached_values();
-        self.init_time = Instant::now();
-        self.perf_index = self.cfg.perf_cold_boot;
-        self.temp_threshold = fas_rules.core_temp_threshold;
-        self.apply_freqs();
-
-        info!("FAS init | {:.0}fps margin:{:.1} clusters:{} P:{:.2} profiles:{}",
-            self.current_target_fps, self.fps_margin, self.policies.len(),
-            self.perf_index, self.cfg.per_app_profiles.len());
-        info!("FAS PID  | Kp={:.4} Ki={:.4} Kd={:.4}",
-            self.cfg.pid.kp, self.cfg.pid.ki, self.cfg.pid.kd);
+        self.start_session(fas_rules);
     }
 
     // ════════════════════════════════════════════════════════════
@@ -1011,7 +946,7 @@ impl FasController {
     // ════════════════════════════════════════════════════════════
 
     fn handle_early_exit(&mut self, actual_ms: f32) -> bool {
-        if self.init_time.elapsed().as_millis() < self.cfg.cold_boot_ms as u128 {
+        if clock::elapsed(self.init_time).as_millis() < self.cfg.cold_boot_ms as u128 {
             if self.perf_index < self.cfg.perf_cold_boot {
                 self.perf_index = self.cfg.perf_cold_boot;
                 self.apply_freqs();
@@ -1043,12 +978,19 @@ impl FasController {
             self.loading_normal_tolerance = 0;
             if !self.is_loading && self.loading_cumulative_ms > self.cfg.loading_cumulative_ms {
                 self.is_loading = true;
+                metrics::FAS_LOADING_ENTRIES.inc();
                 let old = self.perf_index;
                 self.perf_index = self.perf_index
                     .clamp(self.cfg.loading_perf_floor, self.cfg.loading_perf_ceiling);
                 if old != self.perf_index { self.apply_freqs(); }
                 info!("FAS: loading ({} frames, {:.0}ms) | P {:.2} → {:.2}",
                     self.loading_frames, self.loading_cumulative_ms, old, self.perf_index);
+                journal::record(JournalEvent::LoadingEnter {
+                    package: &self.current_package,
+                    frames: self.loading_frames,
+                    cumulative_ms: self.loading_cumulative_ms,
+                    perf: self.perf_index,
+                });
             }
             return true;
         }
@@ -1077,6 +1019,7 @@ impl FasController {
             self.post_loading_downgrade_guard = self.cfg.post_loading_downgrade_guard;
             self.apply_freqs();
             info!("FAS: exit loading | P → {:.2}", self.perf_index);
+            journal::record(JournalEvent::LoadingExit { package: &self.current_package, perf: self.perf_index });
         }
 
         false
@@ -1263,6 +1206,7 @@ impl FasController {
             let inc = if damped { 0.050 * fps_urgency } else { 0.080 * fps_urgency };
             self.perf_index += inc * jank_scale * streak_m;
             act = "crit";
+            metrics::FAS_JANK_CRIT.inc();
             self.consecutive_normal_frames = 0;
             self.jank_cooldown = scale_frames(self.cfg.jank_cooldown_frames * 3, self.current_target_fps);
         } else if ema_err < -heavy_ms {
@@ -1272,6 +1216,7 @@ impl FasController {
             let inc = if damped { 0.025 * fps_urgency } else { 0.040 * fps_urgency };
             self.perf_index += inc * jank_scale * streak_m;
             act = "heavy";
+            metrics::FAS_JANK_HEAVY.inc();
             self.consecutive_normal_frames = 0;
             let fps_cd_scale = (self.current_target_fps / 60.0).clamp(1.0, 2.5);
             self.jank_cooldown = self.jank_cooldown.max(
@@ -1301,7 +1246,7 @@ impl FasController {
             && avg < self.current_target_fps * 0.65
             && self.perf_index < 0.50
             && !self.is_loading
-            && self.init_time.elapsed().as_millis() > self.cfg.cold_boot_ms as u128
+            && clock::elapsed(self.init_time).as_millis() > self.cfg.cold_boot_ms as u128
         {
             let deficit_ratio = 1.0 - (avg / self.current_target_fps.max(1.0));
             let emergency_inc = (0.06 * deficit_ratio * norm).clamp(0.02, 0.10);
@@ -1322,8 +1267,16 @@ impl FasController {
                 self.pid.reset();
                 self.floor_stuck_frames = 0;
                 act = "floor-rescue";
+                metrics::FAS_FLOOR_RESCUES.inc();
                 info!("FAS: floor-rescue | stuck {}frames at P={:.2}, avg:{:.1} → P:{:.2}",
                     stuck_threshold, old, avg, self.perf_index);
+                journal::record(JournalEvent::FloorRescue {
+                    package: &self.current_package,
+                    stuck_frames: stuck_threshold,
+                    avg_fps: avg,
+                    perf_before: old,
+                    perf_after: self.perf_index,
+                });
             }
         } else {
             self.floor_stuck_frames = 0;
@@ -1365,7 +1318,7 @@ impl FasController {
             && self.perf_index > dynamic_decay_threshold
             && self.jank_cooldown == 0
             && !self.downgrade_boost_active
-            && self.init_time.elapsed().as_millis() > self.cfg.cold_boot_ms as u128
+            && clock::elapsed(self.init_time).as_millis() > self.cfg.cold_boot_ms as u128
         {
             let fps_dampen = (60.0 / self.current_target_fps.max(30.0)).powf(0.40);
             // 衰减步长额外乘以 0.6，降低高刷下的衰减激进度
@@ -1410,6 +1363,7 @@ impl FasController {
 
     pub fn update_frame(&mut self, frame_delta_ns: u64) {
         if frame_delta_ns == 0 || self.policies.is_empty() { return; }
+        metrics::FAS_FRAMES.inc();
 
         let actual_ms = frame_delta_ns as f32 / 1_000_000.0;
         let is_heavy = actual_ms > self.cfg.heavy_frame_threshold_ms;
@@ -1511,4 +1465,114 @@ impl FasController {
             policy.reset();
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::actuator::{FreqNode, MockHandle};
+    use std::time::Duration;
+
+    const TABLE: [u32; 4] = [300_000, 1_000_000, 1_500_000, 2_000_000];
+
+    fn controller() -> (PolicyController, MockHandle) {
+        let (actuator, handle) = MockActuator::recording(0);
+        let pc = PolicyController::new(
+            Box::new(actuator), TABLE.to_vec(), 0, ClusterProfile::default(), 2_000_000);
+        (pc, handle)
+    }
+
+    fn writes(handle: &MockHandle) -> Vec<(FreqNode, u32)> {
+        std::mem::take(&mut handle.lock().unwrap().writes)
+    }
+
+    /// 让下一次写入前的校验立即到期
+    fn expire_verify(pc: &mut PolicyController) {
+        if let Some(t) = clock::now().checked_sub(Duration::from_secs(2)) {
+            pc.verify_timer = t;
+        }
+    }
+
+    #[test]
+    fn find_nearest_freq_snaps_to_table() {
+        let (pc, _) = controller();
+        assert_eq!(pc.find_nearest_freq(0.0), 300_000);
+        assert_eq!(pc.find_nearest_freq(0.5), 1_000_000);
+        assert_eq!(pc.find_nearest_freq(0.6), 1_500_000);
+        assert_eq!(pc.find_nearest_freq(1.2), 2_000_000);
+    }
+
+    #[test]
+    fn locked_writes_follow_direction() {
+        let (mut pc, handle) = controller();
+        pc.apply_freq_locked(1_000_000);
+        assert_eq!(writes(&handle).len(), 3, "first write goes max → min → max");
+
+        pc.apply_freq_locked(1_500_000);
+        assert_eq!(writes(&handle), [(FreqNode::Max, 1_500_000), (FreqNode::Min, 1_500_000)]);
+
+        pc.apply_freq_locked(300_000);
+        assert_eq!(writes(&handle), [(FreqNode::Min, 300_000), (FreqNode::Max, 300_000)]);
+        assert_eq!(pc.current_freq, 300_000);
+    }
+
+    #[test]
+    fn repeated_lock_is_deduplicated() {
+        let (mut pc, handle) = controller();
+        pc.apply_freq_locked(1_000_000);
+        writes(&handle);
+        pc.apply_freq_locked(1_000_000);
+        assert!(writes(&handle).is_empty());
+    }
+
+    #[test]
+    fn ignore_write_skips_actuator() {
+        let (mut pc, handle) = controller();
+        pc.ignore_write = true;
+        pc.apply_freq_locked(1_000_000);
+        pc.force_reapply();
+        assert!(writes(&handle).is_empty());
+        assert_eq!(pc.current_freq, 2_000_000);
+    }
+
+    #[test]
+    fn force_reapply_rewrites_last_lock() {
+        let (mut pc, handle) = controller();
+        pc.apply_freq_locked(1_500_000);
+        writes(&handle);
+        pc.force_reapply();
+        assert_eq!(writes(&handle), [
+            (FreqNode::Max, 1_500_000),
+            (FreqNode::Min, 1_500_000),
+            (FreqNode::Max, 1_500_000),
+        ]);
+    }
+
+    #[test]
+    fn due_verify_reapplies_on_mismatch() {
+        let (mut pc, handle) = controller();
+        pc.apply_freq_locked(1_000_000);
+        writes(&handle);
+
+        // 内核把频率改回了最高档
+        handle.lock().unwrap().cur = Some(2_000_000);
+        expire_verify(&mut pc);
+        pc.apply_freq_locked(1_000_000);
+        assert_eq!(writes(&handle).len(), 3, "mismatch triggers a full rewrite");
+
+        // 频率已生效时不重写
+        handle.lock().unwrap().cur = Some(1_000_000);
+        expire_verify(&mut pc);
+        pc.apply_freq_locked(1_000_000);
+        assert!(writes(&handle).is_empty());
+    }
+
+    #[test]
+    fn reset_restores_full_range() {
+        let (mut pc, handle) = controller();
+        pc.apply_freq_locked(1_000_000);
+        writes(&handle);
+        pc.reset();
+        assert_eq!(writes(&handle), [(FreqNode::Max, 2_000_000), (FreqNode::Min, 300_000)]);
+        assert_eq!(pc.current_freq, 2_000_000);
+    }
+}
@@ -0,0 +1,365 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 频率表达式
+//!
+//! config.yaml 中的频率除了 kHz 整数与 "min" / "max" 外，还可以写成：
+//!
+//! | 写法 | 含义 |
+//! | --- | --- |
+//! | `"1.8GHz"` / `"1804MHz"` / `"1500000kHz"` | 绝对频率，原样写入，由内核选择附近的档位 |
+//! | `"70%"` | policy 频率表最低到最高之间的 70%，取最接近的档位 |
+//! | `"step:-2"` / `"step:+1"` | 从最高档往下数 2 档 / 从最低档往上数 1 档 |
+//! | `"nearest:1500MHz"` | 频率表中最接近 1500MHz 的档位 |
+//!
+//! 表达式在应用时按该 policy 的 `scaling_available_frequencies` 求值，
+//! 同一份配置可以用在频率表不同的 SoC 版本上。
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// 大于等于此值的 kHz 数表示 "max"；频率表为空时 "max" 也按此值写入，由内核压到上限
+pub const MAX_SENTINEL: u32 = 9_999_999;
+
+/// JSON Schema 中字符串写法的 pattern，与 FromStr 接受的写法一致 (数值上下界除外)
+const PATTERN: &str = r"^\s*(min|max|(nearest:\s*)?[0-9]+(\.[0-9]+)?\s*([gGmMkK][hH][zZ])?|0*(100(\.0+)?|[0-9]{1,2}(\.[0-9]+)?)\s*%|step:\s*[+-]?0*[1-9][0-9]*)?\s*$";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FreqExpr {
+    /// 最低档
+    Min,
+    /// 最高档
+    Max,
+    /// 绝对频率 (kHz)
+    Khz(u32),
+    /// 频率范围的百分比 (0~100)
+    Percent(f32),
+    /// 负数从最高档往下数，正数从最低档往上数
+    Step(i32),
+    /// 最接近给定频率 (kHz) 的档位
+    Nearest(u32),
+}
+
+impl FreqExpr {
+    /// 按升序的频率表求值；表达式依赖频率表而表为空时返回 None
+    pub fn resolve(&self, table: &[u32]) -> Option<u32> {
+        let (lowest, highest) = (table.first().copied(), table.last().copied());
+        match *self {
+            Self::Min => Some(lowest.unwrap_or(0)),
+            Self::Max => Some(highest.unwrap_or(MAX_SENTINEL)),
+            Self::Khz(khz) => Some(khz),
+            Self::Percent(p) => {
+                let (lo, hi) = (lowest?, highest?);
+                let target = lo as f64 + (hi - lo) as f64 * p as f64 / 100.0;
+                nearest(table, target.round() as u32)
+            }
+            Self::Step(n) => {
+                let last = table.len().checked_sub(1)?;
+                let idx = if n < 0 {
+                    last.saturating_sub(n.unsigned_abs() as usize)
+                } else {
+                    (n as usize).min(last)
+                };
+                Some(table[idx])
+            }
+            Self::Nearest(khz) => nearest(table, khz),
+        }
+    }
+
+    /// 是否只有在读到频率表时才能求值
+    pub fn needs_table(&self) -> bool {
+        matches!(self, Self::Percent(_) | Self::Step(_) | Self::Nearest(_))
+    }
+
+    /// 不依赖频率表的 kHz 值；"min" / "max" 与相对写法为 None
+    pub fn absolute(&self) -> Option<u32> {
+        match *self {
+            Self::Khz(khz) => Some(khz),
+            _ => None,
+        }
+    }
+
+    fn from_khz(khz: u64) -> Self {
+        match khz {
+            0 => Self::Min,
+            k if k >= MAX_SENTINEL as u64 => Self::Max,
+            k => Self::Khz(k as u32),
+        }
+    }
+}
+
+/// 距离相同时取较低的档位
+fn nearest(table: &[u32], khz: u32) -> Option<u32> {
+    table.iter().copied().min_by_key(|f| f.abs_diff(khz))
+}
+
+/// 不带符号与指数的十进制数 ("1.8"、"1500000")
+fn parse_decimal(s: &str) -> Option<f64> {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let (int, frac) = s.split_once('.').unwrap_or((s, "0"));
+    if digits(int) && digits(frac) { s.parse().ok() } else { None }
+}
+
+/// "1.8GHz"、"1804MHz"、"1500000kHz" 或不带单位的 kHz 数
+fn parse_khz(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let lower = s.to_ascii_lowercase();
+    let (number, scale) = if let Some(n) = lower.strip_suffix("ghz") {
+        (n, 1_000_000.0)
+    } else if let Some(n) = lower.strip_suffix("mhz") {
+        (n, 1_000.0)
+    } else if let Some(n) = lower.strip_suffix("khz") {
+        (n, 1.0)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+    let khz = (parse_decimal(number.trim())? * scale).round();
+    (khz <= u32::MAX as f64).then_some(khz as u64)
+}
+
+impl FromStr for FreqExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let invalid = || format!(
+            "invalid frequency '{}', expected kHz, \"1.8GHz\", \"1804MHz\", \"70%\", \"step:-2\", \"nearest:1500MHz\", \"min\" or \"max\"",
+            s);
+
+        if value == "min" {
+            return Ok(Self::Min);
+        }
+        if value == "max" || value.is_empty() {
+            // 空字符串视为默认值 (max)
+            return Ok(Self::Max);
+        }
+        if let Some(p) = value.strip_suffix('%') {
+            let p = parse_decimal(p.trim()).ok_or_else(invalid)?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(format!("invalid frequency '{}', percentage must be between 0% and 100%", s));
+            }
+            return Ok(Self::Percent(p as f32));
+        }
+        if let Some(n) = value.strip_prefix("step:") {
+            let n: i32 = n.trim().parse().map_err(|_| invalid())?;
+            if n == 0 {
+                return Err(format!("invalid frequency '{}', use \"min\" or \"max\" instead of step:0", s));
+            }
+            return Ok(Self::Step(n));
+        }
+        if let Some(khz) = value.strip_prefix("nearest:") {
+            let khz = parse_khz(khz).ok_or_else(invalid)?;
+            return Ok(Self::Nearest(khz.min(u32::MAX as u64) as u32));
+        }
+        parse_khz(value).map(Self::from_khz).ok_or_else(invalid)
+    }
+}
+
+/// 整 GHz / MHz 的值用对应单位显示
+fn fmt_khz(f: &mut fmt::Formatter, khz: u32) -> fmt::Result {
+    match khz {
+        k if k > 0 && k % 1_000_000 == 0 => write!(f, "{}GHz", k / 1_000_000),
+        k if k > 0 && k % 1_000 == 0 => write!(f, "{}MHz", k / 1_000),
+        k => write!(f, "{}kHz", k),
+    }
+}
+
+impl fmt::Display for FreqExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Min => f.write_str("min"),
+            Self::Max => f.write_str("max"),
+            Self::Khz(khz) => fmt_khz(f, khz),
+            Self::Percent(p) => write!(f, "{}%", p),
+            Self::Step(n) => write!(f, "step:{:+}", n),
+            Self::Nearest(khz) => {
+                f.write_str("nearest:")?;
+                fmt_khz(f, khz)
+            }
+        }
+    }
+}
+
+/// 绝对频率写成 kHz 整数，其余写成能被重新解析的字符串 (自定义模式的合并依赖往返)
+impl Serialize for FreqExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Self::Khz(khz) => serializer.serialize_u32(khz),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FreqExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FreqVisitor;
+
+        impl<'de> Visitor<'de> for FreqVisitor {
+            type Value = FreqExpr;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a frequency in kHz, a string such as \"1.8GHz\", \"70%\", \"step:-2\", \"nearest:1500MHz\", or 'min' / 'max'")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(FreqExpr::from_khz(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                u64::try_from(value).map(FreqExpr::from_khz)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                if value >= 0.0 && value.fract() == 0.0 && value <= u64::MAX as f64 {
+                    Ok(FreqExpr::from_khz(value as u64))
+                } else {
+                    Err(E::invalid_value(de::Unexpected::Float(value), &self))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(FreqVisitor)
+    }
+}
+
+/// 与 Deserialize 一致
+impl JsonSchema for FreqExpr {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "FreqExpr".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "频率：kHz 整数，带单位的 \"1.8GHz\" / \"1804MHz\"，频率范围的百分比 \"70%\"，\
+                            相对最高 / 最低档的 \"step:-2\" / \"step:+1\"，最接近的档位 \"nearest:1500MHz\"，\
+                            或 \"min\" / \"max\" (空字符串等同 \"max\")",
+            "anyOf": [
+                { "type": "integer", "minimum": 0 },
+                { "enum": ["min", "max", ""] },
+                {
+                    "type": "string",
+                    "pattern": PATTERN
+                }
+            ]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: [u32; 4] = [300_000, 600_000, 900_000, 1_200_000];
+
+    fn parse(s: &str) -> FreqExpr {
+        s.parse().unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(parse("1.8GHz"), FreqExpr::Khz(1_800_000));
+        assert_eq!(parse("1804MHz"), FreqExpr::Khz(1_804_000));
+        assert_eq!(parse("1500000kHz"), FreqExpr::Khz(1_500_000));
+        assert_eq!(parse(" 1.8 ghz "), FreqExpr::Khz(1_800_000));
+        assert_eq!(parse("1500000"), FreqExpr::Khz(1_500_000));
+        assert_eq!(parse("0"), FreqExpr::Min);
+        assert_eq!(parse("9999999"), FreqExpr::Max);
+        assert_eq!(parse(""), FreqExpr::Max);
+        for bad in ["1e6", "+5", ".5GHz", "5.", "-1", "1.8THz", "5000GHz"] {
+            assert!(bad.parse::<FreqExpr>().is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn percent() {
+        assert_eq!(parse("0%").resolve(&TABLE), Some(300_000));
+        assert_eq!(parse("100%").resolve(&TABLE), Some(1_200_000));
+        // 300000 + 900000 * 0.7 = 930000，最接近 900000
+        assert_eq!(parse("70 %").resolve(&TABLE), Some(900_000));
+        assert!("100.5%".parse::<FreqExpr>().is_err());
+        assert!("-5%".parse::<FreqExpr>().is_err());
+    }
+
+    #[test]
+    fn step_is_clamped_to_the_table() {
+        assert_eq!(parse("step:-1").resolve(&TABLE), Some(900_000));
+        assert_eq!(parse("step:-10").resolve(&TABLE), Some(300_000));
+        assert_eq!(parse("step:+1").resolve(&TABLE), Some(600_000));
+        assert_eq!(parse("step: 10").resolve(&TABLE), Some(1_200_000));
+        assert!("step:0".parse::<FreqExpr>().is_err());
+    }
+
+    #[test]
+    fn nearest_prefers_the_lower_step_on_ties() {
+        assert_eq!(parse("nearest:750MHz").resolve(&TABLE), Some(600_000));
+        assert_eq!(parse("nearest: 760MHz").resolve(&TABLE), Some(900_000));
+        assert_eq!(parse("nearest:5GHz").resolve(&TABLE), Some(1_200_000));
+    }
+
+    #[test]
+    fn empty_table() {
+        assert_eq!(FreqExpr::Min.resolve(&[]), Some(0));
+        assert_eq!(FreqExpr::Max.resolve(&[]), Some(MAX_SENTINEL));
+        assert_eq!(FreqExpr::Khz(1_000_000).resolve(&[]), Some(1_000_000));
+        for expr in ["50%", "step:-1", "nearest:1GHz"] {
+            let expr = parse(expr);
+            assert!(expr.needs_table());
+            assert_eq!(expr.resolve(&[]), None);
+        }
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let exprs = [
+            FreqExpr::Min,
+            FreqExpr::Max,
+            FreqExpr::Khz(1_804_000),
+            FreqExpr::Khz(1_234_567),
+            FreqExpr::Percent(70.5),
+            FreqExpr::Step(-2),
+            FreqExpr::Step(1),
+            FreqExpr::Nearest(1_500_000),
+        ];
+        for expr in exprs {
+            let yaml = serde_yaml::to_string(&expr).unwrap();
+            assert_eq!(serde_yaml::from_str::<FreqExpr>(&yaml).unwrap(), expr, "{}", yaml);
+        }
+        assert_eq!(serde_yaml::to_string(&FreqExpr::Khz(1_804_000)).unwrap(), "1804000\n");
+    }
+
+    #[test]
+    fn schema_pattern_matches_parser() {
+        let pattern = regex::Regex::new(PATTERN).unwrap();
+        let samples = [
+            "min", " max ", "", "  ", "1.8GHz", "1804 MHz", "1500000kHz", "1500000", "nearest:1500MHz",
+            "nearest: 1.5GHz", "70%", "70.5 %", "0%", "100%", "100.0%", "007%", "step:-2", "step:+1",
+            "step: 3", "1e6", "+5", ".5GHz", "5.", "-1", "nearest:", "nearest:1e6", "101%", "100.5%",
+            "-5%", "1e1%", "step:0", "step:-0", "step:", "step:1.5", "Min", "fast",
+        ];
+        for s in samples {
+            assert_eq!(pattern.is_match(s), s.parse::<FreqExpr>().is_ok(), "'{}'", s);
+        }
+    }
+}
@@ -16,31 +16,55 @@
  */
 
 use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::path::Path;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use std::sync::atomic::{AtomicBool, Ordering};
+pub mod actuator;
 pub mod config;
 pub mod scheduler;
 pub mod fas;
 pub mod cpu_load_governor;
+pub mod topology;
+pub mod freq_expr;
 use crate::i18n::{t, load_language, t_with_args};
 use crate::fluent_args; 
 use crate::utils; 
 use crate::common::DaemonEvent; 
+use crate::monitor::config::RulesConfig;
+use crate::bus::{Publisher, Subscriber};
+use crate::control::{ControlRequest, ControlResponse};
 use config::Config;
 use scheduler::CpuScheduler;
+use fas::FasController;
+use cpu_load_governor::CpuLoadGovernor;
 use crate::logger;
 use crate::common;
+use crate::config_check;
+use crate::journal::{self, JournalEvent};
+use crate::metrics;
+use crate::recorder::SessionRecorder;
+use crate::monitor::supervisor;
+use crate::status::{self, DaemonStatus, StatusPublisher};
 
-pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
+/// `config` 与 `rules` 为启动时校验加载的结果 (见 [`config_check`])。任一为 `Err` 时先用默认值占位，
+/// 总开关保持关闭，不写任何节点；修正后重载成功再按 rules.yaml 的 `yumi_scheduler` 接管。
+/// `tx` 用于主配置修正后向自身补发一次规则重载。
+pub fn start_scheduler_thread(
+    rx: Subscriber,
+    tx: Publisher,
+    config: Result<Config, String>,
+    rules: Result<RulesConfig, String>,
+) -> Result<()> {
     // 获取动态路径
     let root = common::get_module_root();
-    let config_path = root.join("config/config.yaml");
     let config_dir = root.join("config"); 
 
-    // 1. 加载配置
-    let config = Config::from_file(config_path.to_str().unwrap()).unwrap_or_default();
+    // 1. 启动时已加载的配置 (合并 config/ 下的各层)
+    let config_ok = Arc::new(AtomicBool::new(config.is_ok()));
+    let loaded = config.is_ok() && rules.is_ok();
+    let config = config.unwrap_or_default();
 
     // 2. 初始化共享状态
     let shared_config = Arc::new(RwLock::new(config));
@@ -49,6 +73,10 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
     let is_boosting = Arc::new(AtomicBool::new(false));
     let fas_suspended = Arc::new(AtomicBool::new(false));
 
+    // rules.yaml 的 yumi_scheduler 总开关，关闭时所有写节点的路径都停止工作；配置有错误时同样关闭
+    let initial_rules = rules.unwrap_or_else(|_| crate::monitor::app_detect::get_default_rules());
+    let scheduler_enabled = Arc::new(AtomicBool::new(loaded && initial_rules.yumi_scheduler));
+
     // 3. 启动 AppLaunchBoost 线程
     if shared_config.read().unwrap().function.app_launch_boost {
         let config_clone = shared_config.clone();
@@ -56,11 +84,12 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
         let sys_path_clone = sys_path_exist.clone();
         let boost_clone = is_boosting.clone();
         let fas_suspended_clone = fas_suspended.clone();
+        let enabled_clone = scheduler_enabled.clone();
         
         thread::Builder::new()
             .name("applaunch_boost".to_string())
             .spawn(move || {
-                let scheduler = CpuScheduler::new(config_clone, mode_clone, sys_path_clone, boost_clone, fas_suspended_clone);
+                let scheduler = CpuScheduler::new(config_clone, mode_clone, sys_path_clone, boost_clone, fas_suspended_clone, enabled_clone);
                 scheduler.app_launch_boost_loop();
             })?;
         
@@ -68,52 +97,39 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
     }
 
     // 4. 启动 Config Watcher
+    let config_dir_ipc = config_dir.clone();
     let config_clone = shared_config.clone();
     let mode_clone = shared_mode_name.clone();
     let sys_path_clone = sys_path_exist.clone();
     let boost_clone = is_boosting.clone();
     let fas_suspended_clone = fas_suspended.clone();
+    let enabled_clone = scheduler_enabled.clone();
+    let config_ok_clone = config_ok.clone();
+    let tx_clone = tx.clone();
     
     thread::Builder::new()
         .name("config_watcher".to_string())
         .spawn(move || {
+            let watcher = match utils::DirWatcher::new(&config_dir, &config::LAYER_FILES) {
+                Ok(watcher) => watcher,
+                Err(e) => {
+                    log::error!("{}", t_with_args("config-watch-error", &fluent_args!("error" => e.to_string())));
+                    return;
+                }
+            };
             loop {
-                if let Err(e) = utils::watch_path(&config_dir) {
+                if let Err(e) = watcher.wait() {
                     log::error!("{}", t_with_args("config-watch-error", &fluent_args!("error" => e.to_string())));
+                    thread::sleep(Duration::from_secs(1));
                     continue;
                 }
                 log::info!("{}", t("config-reloading"));
 
-                let old_lang = config_clone.read().unwrap().meta.language.clone();
-                
-                match Config::from_file(config_path.to_str().unwrap()) {
-                    Ok(new_config) => {
-                        logger::update_level(&new_config.meta.loglevel);
-                        *config_clone.write().unwrap() = new_config;
-                        
-                        let new_lang = config_clone.read().unwrap().meta.language.clone();
-                        if old_lang != new_lang {
-                            load_language(&new_lang);
-                        }
-
-                        log::info!("{}", t("config-reloaded-success"));
-
-                        if boost_clone.load(Ordering::SeqCst) {
-                            log::info!("{}", t("boost-active-defer-config-apply"));
-                            continue; 
-                        }
-                        
-                        let scheduler = CpuScheduler::new(config_clone.clone(), mode_clone.clone(), sys_path_clone.clone(), boost_clone.clone(), fas_suspended_clone.clone());
-                        if let Err(e) = scheduler.apply_all_settings() {
-                            log::error!("{}", t_with_args("config-apply-mode-failed", &fluent_args!("error" => e.to_string())));
-                        }
-                        if let Err(e) = scheduler.apply_system_tweaks() {
-                            log::error!("{}", t_with_args("config-apply-tweaks-failed", &fluent_args!("error" => e.to_string())));
-                        }
-                    }
-                    Err(load_err) => {
-                        log::error!("{}", t_with_args("config-reload-fail", &fluent_args!("error" => load_err.to_string())));
-                    }
+                if let Err(load_err) = reload_main_config(
+                    &config_dir, &config_clone, &mode_clone, &sys_path_clone,
+                    &boost_clone, &fas_suspended_clone, &enabled_clone, &config_ok_clone, &tx_clone,
+                ) {
+                    log::error!("{}", t_with_args("config-reload-fail", &fluent_args!("error" => load_err.to_string())));
                 }
             }
         })?;
@@ -126,6 +142,8 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
     let sys_path_clone = sys_path_exist.clone();
     let boost_clone = is_boosting.clone();
     let fas_suspended_clone = fas_suspended.clone();
+    let enabled_clone = scheduler_enabled.clone();
+    let config_ok_clone = config_ok.clone();
 
     thread::Builder::new()
         .name("scheduler_ipc".to_string())
@@ -141,9 +159,10 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
             let mut cpu_governor = crate::scheduler::cpu_load_governor::CpuLoadGovernor::new();
             // 将 is_boosting 标志传给 CLG，使其在 Boost 期间暂停写 sysfs
             cpu_governor.set_boost_flag(boost_clone.clone());
+            // rules.yaml trace_recorder.packages 中应用的 FAS 会话录制
+            let mut recorder = SessionRecorder::new();
 
-            let rules_path = crate::monitor::config::get_rules_path();
-            let mut current_rules = crate::monitor::config::read_config::<crate::monitor::config::RulesConfig, _>(&rules_path).unwrap_or_default();
+            let mut current_rules = initial_rules;
 
             let mut fas_suspended_at: Option<Instant> = None;
             let mut fas_suspended_package = String::new();
@@ -153,18 +172,23 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
             let temp_sensor_path = crate::utils::find_cpu_temp_path().unwrap_or_default();
             let mut last_temp_update = Instant::now();
             let mut was_boosting = false;
+            // eBPF 数据源下线期间，依赖它的控制器不接管频率
+            let mut fps_source_down = false;
+            let mut cpu_source_down = false;
 
             let apply_static_mode = |config: &Arc<RwLock<Config>>,
                                       mode: &Arc<Mutex<String>>,
                                       sys_path: &Arc<utils::SysPathExist>,
                                       boost: &Arc<AtomicBool>,
-                                      fas_sus: &Arc<AtomicBool>| {
+                                      fas_sus: &Arc<AtomicBool>,
+                                      enabled: &Arc<AtomicBool>| {
                 let scheduler = CpuScheduler::new(
                     config.clone(),
                     mode.clone(),
                     sys_path.clone(),
                     boost.clone(),
                     fas_sus.clone(),
+                    enabled.clone(),
                 );
                 if let Err(e) = scheduler.apply_all_settings() {
                     log::error!("{}", t_with_args("scheduler-apply-failed", &fluent_args!("error" => e.to_string())));
@@ -176,14 +200,105 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
             // 导致 old_mode != mode 判断为 false，init_policies() 永远不会被调用
             {
                 let current_mode = mode_clone.lock().unwrap().clone();
-                if current_mode != "fas" && current_rules.cpu_load_governor.enabled {
+                if current_mode != "fas" && current_rules.cpu_load_governor.enabled
+                    && enabled_clone.load(Ordering::SeqCst)
+                {
                     let config_lock = config_clone.read().unwrap();
                     cpu_governor.init_policies(&config_lock, &current_rules.cpu_load_governor);
                     log::info!("CPU Load Governor: initialized at startup (mode={})", current_mode);
                 }
             }
             
-            for msg in rx {
+            let mut status_publisher = StatusPublisher::new();
+            let mut status_dirty = true;
+
+            loop {
+                // 每秒以及每次状态迁移后刷新 state.json
+                if status_dirty || status_publisher.is_due() {
+                    let current_mode = mode_clone.lock().unwrap().clone();
+                    let snapshot = collect_status(
+                        &current_mode, &fas_controller, &cpu_governor,
+                        &boost_clone, &fas_suspended_clone, &enabled_clone, &temp_sensor_path,
+                    );
+                    if let Err(e) = status_publisher.publish(&snapshot) {
+                        log::debug!("Failed to publish state.json: {}", e);
+                    }
+                    status_dirty = false;
+                }
+
+                let msg = match rx.recv_timeout(status::PUBLISH_INTERVAL) {
+                    Ok(msg) => msg,
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+
+                // 控制请求：查询类直接回复，set_mode 转换为 ModeChange 走正常切换流程
+                let msg = match msg {
+                    DaemonEvent::Control { request, reply } => {
+                        let (response, event) = match request {
+                            ControlRequest::Status => {
+                                let current_mode = mode_clone.lock().unwrap().clone();
+                                let snapshot = collect_status(
+                                    &current_mode, &fas_controller, &cpu_governor,
+                                    &boost_clone, &fas_suspended_clone, &enabled_clone, &temp_sensor_path,
+                                );
+                                (ControlResponse::ok(serde_json::to_value(snapshot).unwrap_or_default()), None)
+                            }
+                            ControlRequest::FasState => {
+                                (ControlResponse::ok(serde_json::to_value(fas_controller.snapshot()).unwrap_or_default()), None)
+                            }
+                            ControlRequest::ClgState => {
+                                (ControlResponse::ok(serde_json::to_value(cpu_governor.snapshot()).unwrap_or_default()), None)
+                            }
+                            ControlRequest::Restore => {
+                                recorder.stop();
+                                cpu_governor.release();
+                                fas_controller.clear_game();
+                                fas_controller.policies.clear();
+                                fas_suspended_at = None;
+                                fas_suspended_package.clear();
+                                fas_suspended_clone.store(false, Ordering::SeqCst);
+                                let report = crate::restore::restore_all();
+                                status_dirty = true;
+                                (ControlResponse::ok(serde_json::to_value(report).unwrap_or_default()), None)
+                            }
+                            ControlRequest::Reload => {
+                                match reload_main_config(
+                                    &config_dir_ipc, &config_clone, &mode_clone, &sys_path_clone,
+                                    &boost_clone, &fas_suspended_clone, &enabled_clone, &config_ok_clone, &tx,
+                                ) {
+                                    Ok(()) => (ControlResponse::ok(serde_json::json!({ "reloaded": true })), None),
+                                    Err(e) => (ControlResponse::error(format!("{}: {}", config_dir_ipc.display(), e)), None),
+                                }
+                            }
+                            // 已由 control 线程处理，不会转发到这里
+                            ControlRequest::ClearOverride => (ControlResponse::ok(serde_json::Value::Null), None),
+                            ControlRequest::SetMode { mode, .. } => {
+                                if mode != "fas" && config_clone.read().unwrap().get_mode(&mode).is_none() {
+                                    (ControlResponse::error(format!("unknown mode: {}", mode)), None)
+                                } else {
+                                    let temperature = crate::utils::read_f64_from_file(&temp_sensor_path)
+                                        .map(|raw| raw / 1000.0)
+                                        .unwrap_or(0.0);
+                                    let event = DaemonEvent::ModeChange {
+                                        package_name: crate::monitor::app_detect::get_current_package(),
+                                        pid: crate::monitor::app_detect::get_current_pid(),
+                                        mode: mode.clone(),
+                                        temperature,
+                                    };
+                                    (ControlResponse::ok(serde_json::json!({ "mode": mode })), Some(event))
+                                }
+                            }
+                        };
+                        let _ = reply.send(response);
+                        match event {
+                            Some(event) => event,
+                            None => continue,
+                        }
+                    }
+                    other => other,
+                };
+
                 match msg {
                     // ModeChange 现在携带 pid 字段
                     DaemonEvent::ModeChange { package_name, pid, mode, temperature } => {
@@ -191,9 +306,18 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                         let old_mode = current_mode_lock.clone();
                         
                         if old_mode != mode {
+                            status_dirty = true;
+                            metrics::MODE_CHANGES.inc();
                             log::info!("{}", t_with_args("scheduler-mode-change-request", &fluent_args!(
                                 "old" => old_mode.clone(), "new" => mode.as_str(), "pkg" => package_name.as_str(), "temp" => temperature
                             )));
+                            journal::record(JournalEvent::ModeChange {
+                                from: &old_mode,
+                                to: &mode,
+                                package: &package_name,
+                                pid,
+                                temperature,
+                            });
                             
                             *current_mode_lock = mode.clone();
                             drop(current_mode_lock); 
@@ -202,8 +326,11 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                  log::error!("Failed to update mode.txt: {}", e);
                             }
 
+                            if !enabled_clone.load(Ordering::SeqCst) {
+                                log::debug!("yumi_scheduler is off, recorded mode {} without applying it", mode);
+                            }
                             // ===== 进入 FAS 模式 =====
-                            if mode == "fas" {
+                            else if mode == "fas" {
                                 // FAS 接管频率控制，先释放负载调频器
                                 cpu_governor.release();
 
@@ -228,6 +355,8 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                     fas_controller.set_game(pid, &package_name);
                                     fas_controller.set_temperature(temperature);
                                     fas_controller.set_temp_threshold(current_rules.fas_rules.core_temp_threshold);
+                                    recorder.mode("fas");
+                                    recorder.temp(temperature);
                                     log::info!("FAS: resumed from suspend (pkg={}, pid={}, policies intact, sysfs reapplied)",
                                         package_name, pid);
                                 } else {
@@ -240,14 +369,21 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                     fas_controller.set_game(pid, &package_name);
                                     fas_controller.set_temperature(temperature);
                                     fas_controller.set_temp_threshold(current_rules.fas_rules.core_temp_threshold);
+                                    recorder.start(&current_rules.trace_recorder, &package_name, &fas_controller.policies, temperature);
                                     log::info!("Entered FAS mode (pkg={}, pid={}), FAS controller is now taking over CPU frequencies.",
                                         package_name, pid);
                                 }
+                                if fps_source_down {
+                                    // 没有帧数据 FAS 会停在初始档位，先放开频率，等数据源恢复
+                                    log::warn!("FAS: fps source is down, leaving frequencies unconstrained until it recovers");
+                                    fas_controller.reset_all_freqs();
+                                }
                             }
                             // ===== 离开 FAS 模式，进入静态模式 =====
                             else {
                                 if fas_suspended_at.is_some() {
                                     log::info!("FAS: clearing stale suspend state before applying static mode");
+                                    recorder.stop();
                                     fas_controller.reset_all_freqs();
                                     fas_controller.clear_game();
                                     fas_controller.policies.clear();
@@ -256,6 +392,9 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                     fas_suspended_clone.store(false, Ordering::SeqCst);
                                 }
 
+                                if old_mode == "fas" {
+                                    recorder.mode(&mode);
+                                }
                                 if old_mode == "fas" && !fas_controller.policies.is_empty() {
                                     fas_suspended_at = Some(Instant::now());
                                     fas_suspended_package = package_name.clone();
@@ -263,6 +402,7 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                     log::info!("FAS: suspended (pkg={}, grace={}s, in-memory state preserved)",
                                         package_name, FAS_SUSPEND_GRACE_SECS);
                                 } else if old_mode == "fas" {
+                                    recorder.stop();
                                     fas_controller.clear_game();
                                     fas_controller.policies.clear();
                                     fas_suspended_at = None;
@@ -281,10 +421,11 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                     &sys_path_clone,
                                     &boost_clone,
                                     &fas_suspended_clone,
+                                    &enabled_clone,
                                 );
 
                                 // 静态模式应用完毕后，如果负载调频器已启用则接管频率
-                                if current_rules.cpu_load_governor.enabled {
+                                if current_rules.cpu_load_governor.enabled && !cpu_source_down {
                                     let config_lock = config_clone.read().unwrap();
                                     cpu_governor.init_policies(&config_lock, &current_rules.cpu_load_governor);
                                 } else {
@@ -295,6 +436,7 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                             // 即使模式没变，也更新温度（温度可能变化了）
                             if mode == "fas" {
                                 fas_controller.set_temperature(temperature);
+                                recorder.temp(temperature);
                             }
                         }
                     },
@@ -305,9 +447,13 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                             fas_controller.update_cpu_util(foreground_max_util);
                             // [Fix] Drive scene detection + populate core_utils for FAS
                             fas_controller.update_core_utils(&core_utils);
+                            recorder.load(&core_utils, foreground_max_util);
                         }
                         // [Fix] Detect boost end -> resync CLG frequencies
                         let boosting_now = boost_clone.load(std::sync::atomic::Ordering::Relaxed);
+                        if was_boosting != boosting_now {
+                            status_dirty = true;
+                        }
                         if was_boosting && !boosting_now {
                             cpu_governor.resync_after_boost();
                             log::info!("CLG: resync after app-launch-boost ended");
@@ -321,23 +467,85 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                     // FrameUpdate 不再携带 package_name
                     DaemonEvent::FrameUpdate { fps: _, frame_delta_ns } => {
                         let current_mode = mode_clone.lock().unwrap().clone();
-                        if current_mode == "fas" {
+                        if current_mode == "fas" && enabled_clone.load(Ordering::Relaxed) {
                             // 每 3 秒更新一次温度（低开销，仅读 sysfs 文件）
                             if !temp_sensor_path.is_empty() && last_temp_update.elapsed().as_secs() >= 3 {
                                 if let Ok(raw_temp) = crate::utils::read_f64_from_file(&temp_sensor_path) {
                                     fas_controller.set_temperature(raw_temp / 1000.0);
+                                    recorder.temp(raw_temp / 1000.0);
                                 }
                                 last_temp_update = Instant::now();
                             }
+                            recorder.frame(frame_delta_ns);
                             fas_controller.update_frame(frame_delta_ns);
                         }
                     }
                     // 热重载使用 reload_rules，不重建 policies，不重置运行时状态
                     DaemonEvent::ConfigReload(new_rules) => {
                         log::info!("Scheduler received config reload event. Updating in-memory rules...");
+                        status_dirty = true;
+                        metrics::RULES_RELOADS.inc();
                         current_rules = new_rules;
-                        
+                        journal::record(JournalEvent::RulesReload {
+                            yumi_scheduler: current_rules.yumi_scheduler,
+                            dynamic_enabled: current_rules.dynamic_enabled,
+                            global_mode: &current_rules.global_mode,
+                        });
+
                         let current_mode = mode_clone.lock().unwrap().clone();
+                        // 主配置仍有错误时保持关闭
+                        let wanted = current_rules.yumi_scheduler && config_ok_clone.load(Ordering::SeqCst);
+                        let was_enabled = enabled_clone.swap(wanted, Ordering::SeqCst);
+                        if !wanted {
+                            if was_enabled {
+                                // 总开关关闭：释放所有控制器并恢复原始节点值
+                                log::info!("yumi_scheduler switched off, releasing FAS/CLG and restoring original values");
+                                recorder.stop();
+                                cpu_governor.release();
+                                fas_controller.clear_game();
+                                fas_controller.policies.clear();
+                                fas_suspended_at = None;
+                                fas_suspended_package.clear();
+                                fas_suspended_clone.store(false, Ordering::SeqCst);
+                                crate::restore::restore_all();
+                            }
+                            continue;
+                        }
+
+                        if !was_enabled {
+                            // 总开关重新打开：按当前模式完整地重新接管
+                            log::info!("yumi_scheduler switched on, re-applying mode {}", current_mode);
+                            // 关闭时 restore_all 连同 cpuset、IO、调度器等一次性调整一起恢复了，这里一并重新下发
+                            let scheduler = CpuScheduler::new(
+                                config_clone.clone(), mode_clone.clone(), sys_path_clone.clone(),
+                                boost_clone.clone(), fas_suspended_clone.clone(), enabled_clone.clone(),
+                            );
+                            if let Err(e) = scheduler.apply_system_tweaks() {
+                                log::error!("{}", t_with_args("config-apply-tweaks-failed", &fluent_args!("error" => e.to_string())));
+                            }
+                            if current_mode == "fas" {
+                                let config_lock = config_clone.read().unwrap();
+                                let package = crate::monitor::app_detect::get_current_package();
+                                fas_controller.load_policies(&config_lock, &current_rules.fas_rules);
+                                fas_controller.set_game(crate::monitor::app_detect::get_current_pid(), &package);
+                                fas_controller.set_temp_threshold(current_rules.fas_rules.core_temp_threshold);
+                                let temperature = crate::utils::read_f64_from_file(&temp_sensor_path)
+                                    .map(|raw| raw / 1000.0)
+                                    .unwrap_or(0.0);
+                                recorder.start(&current_rules.trace_recorder, &package, &fas_controller.policies, temperature);
+                            } else {
+                                apply_static_mode(
+                                    &config_clone, &mode_clone, &sys_path_clone,
+                                    &boost_clone, &fas_suspended_clone, &enabled_clone,
+                                );
+                                if current_rules.cpu_load_governor.enabled && !cpu_source_down {
+                                    let config_lock = config_clone.read().unwrap();
+                                    cpu_governor.init_policies(&config_lock, &current_rules.cpu_load_governor);
+                                }
+                            }
+                            continue;
+                        }
+
                         if current_mode == "fas" {
                             if fas_controller.policies.is_empty() {
                                 // policies 尚未初始化，做全量加载
@@ -351,7 +559,7 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                             }
                         } else {
                             // 非 FAS 模式：热重载负载调频器配置
-                            if current_rules.cpu_load_governor.enabled {
+                            if current_rules.cpu_load_governor.enabled && !cpu_source_down {
                                 if cpu_governor.is_active() {
                                     cpu_governor.reload_config(&current_rules.cpu_load_governor);
                                 } else {
@@ -364,27 +572,173 @@ pub fn start_scheduler_thread(rx: mpsc::Receiver<DaemonEvent>) -> Result<()> {
                                 cpu_governor.release();
                                 apply_static_mode(
                                     &config_clone, &mode_clone, &sys_path_clone,
-                                    &boost_clone, &fas_suspended_clone,
+                                    &boost_clone, &fas_suspended_clone, &enabled_clone,
                                 );
                             }
                         }
                     }
+                    DaemonEvent::SourceHealth { source, healthy } => {
+                        status_dirty = true;
+                        let current_mode = mode_clone.lock().unwrap().clone();
+                        let active = enabled_clone.load(Ordering::SeqCst) && !boost_clone.load(Ordering::SeqCst);
+                        match source.as_str() {
+                            supervisor::FPS_SOURCE => {
+                                let was_down = std::mem::replace(&mut fps_source_down, !healthy);
+                                if !healthy && !was_down && current_mode == "fas" && !fas_controller.policies.is_empty() {
+                                    log::warn!("FAS: fps source is down, leaving frequencies unconstrained until it recovers");
+                                    fas_controller.reset_all_freqs();
+                                } else if healthy && was_down && current_mode == "fas" {
+                                    log::info!("FAS: fps source recovered");
+                                    for policy in &mut fas_controller.policies {
+                                        policy.force_reapply();
+                                    }
+                                }
+                            }
+                            supervisor::CPU_SOURCE => {
+                                let was_down = std::mem::replace(&mut cpu_source_down, !healthy);
+                                if !healthy && !was_down && cpu_governor.is_active() {
+                                    log::warn!("CLG: cpu load source is down, falling back to static mode {}", current_mode);
+                                    cpu_governor.release();
+                                    if active {
+                                        apply_static_mode(
+                                            &config_clone, &mode_clone, &sys_path_clone,
+                                            &boost_clone, &fas_suspended_clone, &enabled_clone,
+                                        );
+                                    }
+                                } else if healthy && was_down && active && current_mode != "fas"
+                                    && current_rules.cpu_load_governor.enabled && !cpu_governor.is_active()
+                                {
+                                    log::info!("CLG: cpu load source recovered, re-initialising");
+                                    let config_lock = config_clone.read().unwrap();
+                                    cpu_governor.init_policies(&config_lock, &current_rules.cpu_load_governor);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    // 已在上方转换或回复
+                    DaemonEvent::Control { .. } => {}
                 }
 
                 if let Some(suspended_at) = fas_suspended_at {
                     if suspended_at.elapsed().as_secs() >= FAS_SUSPEND_GRACE_SECS {
                         log::info!("FAS: suspend grace expired, clearing FAS in-memory state");
+                        recorder.stop();
                         fas_controller.reset_all_freqs();
                         fas_controller.clear_game();
                         fas_controller.policies.clear();
                         fas_suspended_at = None;
                         fas_suspended_package.clear();
                         fas_suspended_clone.store(false, Ordering::SeqCst);
+                        status_dirty = true;
                     }
                 }
             }
+            recorder.stop();
             log::warn!("{}", t("scheduler-channel-closed"));
         })?;
 
     Ok(())
-}
\ No newline at end of file
+}
+/// 重新读取 config/ 下的各层配置并应用到当前模式与系统参数
+///
+/// 被 config_watcher 的 inotify 重载与控制 socket 的 `reload` 请求共用。
+/// 每次重载重新确定参与合并的层，覆盖层新建或删除后立即生效。
+/// 解析失败或校验出错时返回错误，内存中的旧配置保持不变。
+/// 启动时主配置有错误、这是第一次成功加载时，向 scheduler 补发一次规则重载，由它按总开关重新接管。
+#[allow(clippy::too_many_arguments)]
+fn reload_main_config(
+    config_dir: &Path,
+    config: &Arc<RwLock<Config>>,
+    mode: &Arc<Mutex<String>>,
+    sys_path: &Arc<utils::SysPathExist>,
+    boost: &Arc<AtomicBool>,
+    fas_sus: &Arc<AtomicBool>,
+    enabled: &Arc<AtomicBool>,
+    config_ok: &AtomicBool,
+    tx: &Publisher,
+) -> Result<()> {
+    let old_lang = config.read().unwrap().meta.language.clone();
+    let new_config = match config_check::load_config_checked(&config::layer_paths(config_dir)) {
+        Ok(c) => c,
+        Err(error) => {
+            journal::record(JournalEvent::ConfigReload { ok: false, error: Some(error.clone()) });
+            return Err(anyhow::anyhow!(error));
+        }
+    };
+
+    logger::update_level(&new_config.meta.loglevel);
+    config_check::set_running_config(&new_config);
+    *config.write().unwrap() = new_config;
+
+    let new_lang = config.read().unwrap().meta.language.clone();
+    if old_lang != new_lang {
+        load_language(&new_lang);
+    }
+
+    log::info!("{}", t("config-reloaded-success"));
+    metrics::CONFIG_RELOADS.inc();
+    journal::record(JournalEvent::ConfigReload { ok: true, error: None });
+
+    if !config_ok.swap(true, Ordering::SeqCst) {
+        match config_check::load_rules_checked(&crate::monitor::config::get_rules_path()) {
+            Ok(rules) => {
+                if tx.send(DaemonEvent::ConfigReload(rules)).is_err() {
+                    log::warn!("[Config] Failed to send ConfigReload event: scheduler channel closed");
+                }
+            }
+            Err(e) => log::warn!("[Config] rules.yaml still has errors, tunables stay off: {}", e),
+        }
+        return Ok(());
+    }
+
+    if boost.load(Ordering::SeqCst) {
+        log::info!("{}", t("boost-active-defer-config-apply"));
+        return Ok(());
+    }
+
+    let scheduler = CpuScheduler::new(config.clone(), mode.clone(), sys_path.clone(), boost.clone(), fas_sus.clone(), enabled.clone());
+    if let Err(e) = scheduler.apply_all_settings() {
+        log::error!("{}", t_with_args("config-apply-mode-failed", &fluent_args!("error" => e.to_string())));
+    }
+    if let Err(e) = scheduler.apply_system_tweaks() {
+        log::error!("{}", t_with_args("config-apply-tweaks-failed", &fluent_args!("error" => e.to_string())));
+    }
+    Ok(())
+}
+
+/// 汇总各线程的运行时状态 (state.json 与控制 socket `status` 请求共用)
+fn collect_status(
+    mode: &str,
+    fas: &FasController,
+    clg: &CpuLoadGovernor,
+    boost: &Arc<AtomicBool>,
+    fas_sus: &Arc<AtomicBool>,
+    enabled: &Arc<AtomicBool>,
+    temp_sensor_path: &str,
+) -> DaemonStatus {
+    let temperature = if temp_sensor_path.is_empty() {
+        None
+    } else {
+        utils::read_f64_from_file(temp_sensor_path).ok().map(|raw| raw / 1000.0)
+    };
+
+    DaemonStatus {
+        timestamp_ms: status::now_ms(),
+        daemon_pid: std::process::id(),
+        enabled: enabled.load(Ordering::Relaxed),
+        dry_run: utils::is_dry_run(),
+        config_errors: status::config_errors(),
+        mode: mode.to_string(),
+        package: crate::monitor::app_detect::get_current_package(),
+        pid: crate::monitor::app_detect::get_current_pid(),
+        screen_on: status::is_screen_on(),
+        mode_override: crate::monitor::app_detect::get_mode_override(),
+        temperature,
+        boosting: boost.load(Ordering::Relaxed),
+        fas_suspended: fas_sus.load(Ordering::Relaxed),
+        fas: (!fas.policies.is_empty()).then(|| fas.snapshot()),
+        clg: clg.is_active().then(|| clg.snapshot()),
+        sources: supervisor::snapshot(),
+    }
+}
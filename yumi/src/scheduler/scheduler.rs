@@ -15,16 +15,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use super::config::{Config, Mode};
+use super::actuator::{FreqActuator, StaticSysfsActuator};
+use super::config::{ClusterDef, Config, Mode};
+use super::freq_expr::MAX_SENTINEL;
+use super::topology;
 use super::utils::{self, SysPathExist};
 use anyhow::Result;
 use std::fs;
-use std::os::unix::fs::DirBuilderExt;
 use std::process::Command;
 use std::sync::{Arc, Mutex, RwLock};
 
 use crate::i18n::{t, t_with_args};
 use crate::fluent_args; 
+use crate::journal::{self, JournalEvent};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 pub struct CpuScheduler {
@@ -34,6 +37,8 @@ pub struct CpuScheduler {
     is_boosting: Arc<AtomicBool>,
     // FAS 挂起标志：boost 线程通过此标志感知 FAS 暂停
     fas_suspended: Arc<AtomicBool>,
+    // yumi_scheduler 总开关：关闭时不再写任何节点
+    scheduler_enabled: Arc<AtomicBool>,
 }
 
 impl CpuScheduler {
@@ -43,6 +48,7 @@ impl CpuScheduler {
         sys_path_exist: Arc<SysPathExist>,
         is_boosting: Arc<AtomicBool>,
         fas_suspended: Arc<AtomicBool>,
+        scheduler_enabled: Arc<AtomicBool>,
     ) -> Self {
         Self {
             config,
@@ -50,6 +56,7 @@ impl CpuScheduler {
             sys_path_exist,
             is_boosting,
             fas_suspended,
+            scheduler_enabled,
         }
     }
 
@@ -65,6 +72,11 @@ impl CpuScheduler {
 
     /// 应用所有与当前性能模式相关的设置
     pub fn apply_all_settings(&self) -> Result<()> {
+        if !self.scheduler_enabled.load(Ordering::SeqCst) {
+            log::debug!("yumi_scheduler is off, skipping static settings application.");
+            return Ok(());
+        }
+
         if self.is_boosting.load(Ordering::SeqCst) {
             log::info!("{}", t("boost-active-skipping-apply-all-settings"));
             return Ok(());
@@ -111,6 +123,9 @@ impl CpuScheduler {
 
     /// 应用所有一次性的、与模式无关的系统调整
     pub fn apply_system_tweaks(&self) -> Result<()> {
+        if !self.scheduler_enabled.load(Ordering::SeqCst) {
+            return Ok(());
+        }
         self.load_balancing()?;
         self.apply_cpuset()?;
         self.apply_cpu_idle_governor()?;
@@ -164,20 +179,14 @@ impl CpuScheduler {
         // 注意：gov_settings 来自参数 current_mode，config 来自 self.config
         let gov_settings = &current_mode.governor;
         let config = self.config.read().unwrap();
-        let core_info = &config.core_framework;
-
-        let cores_to_process = [
-            (core_info.small_core_path, if !gov_settings.small_core.is_empty() { &gov_settings.small_core } else { &gov_settings.global }, "SmallCore"),
-            (core_info.medium_core_path, if !gov_settings.medium_core.is_empty() { &gov_settings.medium_core } else { &gov_settings.global }, "MediumCore"),
-            (core_info.big_core_path, if !gov_settings.big_core.is_empty() { &gov_settings.big_core } else { &gov_settings.global }, "BigCore"),
-            (core_info.super_big_core_path, if !gov_settings.super_big_core.is_empty() { &gov_settings.super_big_core } else { &gov_settings.global }, "SuperBigCore"),
-        ];
-
-        for (core_path, governor, core_name) in cores_to_process {
-            if core_path == -1 { continue; }
-            let path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_governor", core_path);
+        let clusters = config.core_framework.clusters();
+        drop(config);
+
+        for cluster in &clusters {
+            let governor = gov_settings.governor_for(cluster);
+            let path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_governor", cluster.policy);
             let _ = utils::try_write_file(path, governor);
-            self.apply_gov_sets(current_mode, core_path, core_name, governor)?;
+            self.apply_gov_sets(current_mode, cluster, governor)?;
         }
         Ok(())
     }
@@ -185,8 +194,7 @@ impl CpuScheduler {
     fn apply_gov_sets(
         &self,
         current_mode: &Mode,
-        core_policy_id: i32,
-        core_type_str: &str,
+        cluster: &ClusterDef,
         governor_name: &str,
     ) -> Result<()> {
         let config = self.config.read().unwrap();
@@ -197,7 +205,7 @@ impl CpuScheduler {
             return Ok(());
         };
         for (path_alias, core_map) in settings_for_this_gov {
-            let Some(value_to_set) = core_map.get(core_type_str) else {
+            let Some(value_to_set) = cluster.lookup(|k| core_map.get(k)) else {
                 continue;
             };
             let Some(filename) = paths_for_this_gov.get(path_alias) else {
@@ -208,10 +216,10 @@ impl CpuScheduler {
             }
             let final_path = format!(
                 "/sys/devices/system/cpu/cpufreq/policy{}/{}/{}",
-                core_policy_id, governor_name, filename
+                cluster.policy, governor_name, filename
             );
 
-            if std::path::Path::new(&final_path).exists() {
+            if utils::host_path(&final_path).exists() {
                 let _ = utils::try_write_file(&final_path, value_to_set);
             }
         }
@@ -226,24 +234,19 @@ impl CpuScheduler {
         }
 
         let freq_settings = &current_mode.freq;
-        let core_info = &self.config.read().unwrap().core_framework;
-
-        let set_frequency = |core_path_id: i32, min_freq: u32, max_freq: u32, _core_name: &str| -> Result<()> {
-            if core_path_id == -1 { return Ok(()); }
-            let final_min_freq = std::cmp::min(min_freq, max_freq);
-            
-            let min_path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_min_freq", core_path_id);
-            let max_path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_max_freq", core_path_id);
-            
-            let _ = utils::try_write_file(max_path, max_freq.to_string());
-            let _ = utils::try_write_file(min_path, final_min_freq.to_string());
-            Ok(())
-        };
-
-        set_frequency(core_info.small_core_path, freq_settings.small_core_min_freq, freq_settings.small_core_max_freq, "Small")?;
-        set_frequency(core_info.medium_core_path, freq_settings.medium_core_min_freq, freq_settings.medium_core_max_freq, "Medium")?;
-        set_frequency(core_info.big_core_path, freq_settings.big_core_min_freq, freq_settings.big_core_max_freq, "Big")?;
-        set_frequency(core_info.super_big_core_path, freq_settings.super_big_core_min_freq, freq_settings.super_big_core_max_freq, "SuperBig")?;
+        let clusters = self.config.read().unwrap().core_framework.clusters();
+
+        for cluster in &clusters {
+            let range = freq_settings.range_for(cluster);
+            // "70%"、"step:-2" 等写法按本机的频率表求值
+            let table = topology::available_freqs(cluster.policy);
+            if table.is_empty() && (range.min.needs_table() || range.max.needs_table()) {
+                log::warn!("policy{}: no scaling_available_frequencies, cannot resolve {} - {}, leaving it unlimited",
+                    cluster.policy, range.min, range.max);
+            }
+            let (min, max) = range.resolve(&table);
+            StaticSysfsActuator::new(cluster.policy as usize).set_range(min, max);
+        }
         Ok(())
     }
 
@@ -259,7 +262,7 @@ impl CpuScheduler {
             // 1. 在开启加速状态前，先记录当前的模式名称
             let mode_name_before = self.current_mode_name.lock().unwrap().clone();
 
-            if mode_name_before == "fas" {
+            if mode_name_before == "fas" || !self.scheduler_enabled.load(Ordering::SeqCst) {
                 continue;
             }
 
@@ -278,19 +281,22 @@ impl CpuScheduler {
             let config_lock = self.config.read().unwrap();
             let boost_settings = &config_lock.app_launch_boost_settings;
 
-            let boosted_small_max = boost_settings.small_core_boost_freq;
-            let boosted_medium_max = boost_settings.medium_core_boost_freq;
-            let boosted_big_max = boost_settings.big_core_boost_freq;
-            let boosted_super_big_max = boost_settings.super_big_core_boost_freq;
+            let boost_freqs: Vec<(i32, u32)> = config_lock.core_framework.clusters().iter()
+                .map(|cluster| {
+                    let freq = boost_settings.freq_for(cluster);
+                    let khz = freq.resolve(&topology::available_freqs(cluster.policy)).unwrap_or_else(|| {
+                        log::warn!("policy{}: no scaling_available_frequencies, cannot resolve boost {}, using max",
+                            cluster.policy, freq);
+                        MAX_SENTINEL
+                    });
+                    (cluster.policy, khz)
+                })
+                .collect();
             let boost_duration = boost_settings.boost_rate_ms;
             drop(config_lock);
+            journal::record(JournalEvent::BoostStart { mode: &mode_name_before, duration_ms: boost_duration });
 
-            if let Err(e) = self.set_max_cpu_freq_boost(
-                boosted_small_max,
-                boosted_medium_max,
-                boosted_big_max,
-                boosted_super_big_max,
-            ) {
+            if let Err(e) = self.set_max_cpu_freq_boost(&boost_freqs) {
                 log::error!("{}", t_with_args("boost-apply-failed", &fluent_args!("error" => e.to_string())));
             }
 
@@ -300,6 +306,7 @@ impl CpuScheduler {
 
             // 5. 清除加速状态
             self.is_boosting.store(false, Ordering::SeqCst);
+            journal::record(JournalEvent::BoostEnd { mode: &mode_name_before });
 
             // 6. 恢复：获取当前模式，并与加速前的进行对比
             let mode_name_after = self.current_mode_name.lock().unwrap().clone();
@@ -328,24 +335,11 @@ impl CpuScheduler {
         }
     }
 
-    fn set_max_cpu_freq_boost(&self, small_freq: u32, medium_freq: u32, big_freq: u32, super_big_freq: u32) -> Result<()> {
-        let core_info = &self.config.read().unwrap().core_framework;
-        let cores_to_boost = [
-            (core_info.small_core_path, small_freq),
-            (core_info.medium_core_path, medium_freq),
-            (core_info.big_core_path, big_freq),
-            (core_info.super_big_core_path, super_big_freq),
-        ];
-
-        for (core_path, freq) in cores_to_boost {
-            if core_path != -1 {
-                // 先写 max 再写 min，避免 min > 旧 max 被内核拒绝
-                let max_path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_max_freq", core_path);
-                let min_path = format!("/sys/devices/system/cpu/cpufreq/policy{}/scaling_min_freq", core_path);
-                let _ = utils::try_write_file(&max_path, freq.to_string());
-                // 同时拉高 min_freq，强制 CPU 频率不低于 boost 值
-                let _ = utils::try_write_file(&min_path, freq.to_string());
-            }
+    /// boost_freqs: (policy 编号, boost 频率)
+    fn set_max_cpu_freq_boost(&self, boost_freqs: &[(i32, u32)]) -> Result<()> {
+        for &(policy, freq) in boost_freqs {
+            // min 与 max 一起拉到 boost 值，强制 CPU 频率不低于 boost 值
+            StaticSysfsActuator::new(policy as usize).lock(freq);
         }
         Ok(())
     }
@@ -428,45 +422,45 @@ impl CpuScheduler {
 
         let io = &config.io_settings;
         let block_dir = std::path::Path::new("/sys/block");
-        if !block_dir.exists() {
+        if !utils::host_path(block_dir).exists() {
             log::warn!("IOOptimization: /sys/block does not exist, skipping");
             log::info!("{}", t("apply-io-settings-start"));
             return Ok(());
         }
 
-        if let Ok(entries) = fs::read_dir(block_dir) {
+        if let Ok(entries) = fs::read_dir(utils::host_path(block_dir)) {
             for entry in entries.flatten() {
-                let dev_path = entry.path();
+                let dev_path = block_dir.join(entry.file_name());
                 let queue_path = dev_path.join("queue");
-                if !queue_path.exists() {
+                if !utils::host_path(&queue_path).exists() {
                     continue;
                 }
 
                 // scheduler
                 if !io.scheduler.is_empty() {
                     let p = queue_path.join("scheduler");
-                    if p.exists() {
+                    if utils::host_path(&p).exists() {
                         let _ = utils::try_write_file(&p, &io.scheduler);
                     }
                 }
                 // read_ahead_kb
                 if !io.read_ahead_kb.is_empty() {
                     let p = queue_path.join("read_ahead_kb");
-                    if p.exists() {
+                    if utils::host_path(&p).exists() {
                         let _ = utils::try_write_file(&p, &io.read_ahead_kb);
                     }
                 }
                 // nomerges
                 if !io.nomerges.is_empty() {
                     let p = queue_path.join("nomerges");
-                    if p.exists() {
+                    if utils::host_path(&p).exists() {
                         let _ = utils::try_write_file(&p, &io.nomerges);
                     }
                 }
                 // iostats
                 if !io.iostats.is_empty() {
                     let p = queue_path.join("iostats");
-                    if p.exists() {
+                    if utils::host_path(&p).exists() {
                         let _ = utils::try_write_file(&p, &io.iostats);
                     }
                 }
@@ -519,15 +513,15 @@ impl CpuScheduler {
 
     fn mount_cpuset_and_cpuctl(&self) -> Result<()> {
         let config = self.config.read().unwrap();
-        fs::DirBuilder::new().mode(0o666).recursive(true).create("/dev/cpuset/top-app/yumi")?;
+        utils::create_dir("/dev/cpuset/top-app/yumi", 0o666)?;
         utils::write_to_file("/dev/cpuset/top-app/yumi/cpus", &config.core_allocation.cpu_set_core)?;
         utils::write_to_file("/dev/cpuset/top-app/yumi/mems", "0")?;
 
-        fs::DirBuilder::new().mode(0o666).recursive(true).create("/dev/cpuset/Rubbish")?;
+        utils::create_dir("/dev/cpuset/Rubbish", 0o666)?;
         utils::write_to_file("/dev/cpuset/Rubbish/cpus", "1-2")?;
         utils::write_to_file("/dev/cpuset/Rubbish/mems", "0")?;
         
-        fs::DirBuilder::new().mode(0o666).recursive(true).create("/dev/cpuctl/yumi")?;
+        utils::create_dir("/dev/cpuctl/yumi", 0o666)?;
         utils::write_to_file("/dev/cpuctl/yumi/cpu.uclamp.min", "0")?;
         utils::write_to_file("/dev/cpuctl/yumi/cpu.uclamp.max", "max")?;
 
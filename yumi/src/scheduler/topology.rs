@@ -0,0 +1,268 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! CPU 拓扑自动识别
+//!
+//! 枚举 `/sys/devices/system/cpu/cpufreq/policy*`，读取 `related_cpus`、
+//! `cpu_capacity` 与频率范围，按算力从小到大排列 cluster，对应到
+//! CoreFramework 的 小核 / 中核 / 大核 / 超大核。config.yaml 中显式写出的
+//! `*CorePath` 优先于这里的结果。
+//!
+//! policy 目录在运行期间不会变化，拓扑只在第一次使用时探测一次。
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fs;
+use log::{info, warn};
+
+use crate::utils::host_path;
+
+const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    pub policy_id: i32,
+    pub cpus: Vec<usize>,
+    /// 第一个核心的 cpu_capacity (0~1024)，内核未导出时为 None
+    pub capacity: Option<u32>,
+    /// kHz
+    pub min_freq: u32,
+    pub max_freq: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Topology {
+    /// 按算力升序
+    pub clusters: Vec<Cluster>,
+}
+
+static TOPOLOGY: OnceCell<Topology> = OnceCell::new();
+
+/// 当前设备的拓扑 (首次调用时探测并写日志)
+pub fn get() -> &'static Topology {
+    TOPOLOGY.get_or_init(|| {
+        let topology = Topology::discover();
+        topology.log();
+        topology
+    })
+}
+
+impl Topology {
+    pub fn discover() -> Self {
+        let Ok(entries) = fs::read_dir(host_path(CPUFREQ_DIR)) else { return Self::default() };
+        let mut clusters: Vec<Cluster> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.strip_prefix("policy")?.parse::<i32>().ok())
+            .filter_map(probe_cluster)
+            .collect();
+
+        // 任一 cluster 缺少 cpu_capacity 时退回按最高频率排序
+        let by_capacity = clusters.iter().all(|c| c.capacity.is_some_and(|cap| cap > 0));
+        clusters.sort_by_key(|c| {
+            let primary = if by_capacity { c.capacity.unwrap_or(0) } else { c.max_freq };
+            (primary, c.max_freq, c.policy_id)
+        });
+        Self { clusters }
+    }
+
+    /// 对应 CoreFramework 的 [小核, 中核, 大核, 超大核]，没有的档位为 -1
+    ///
+    /// 与各 SoC 配置的写法一致：cluster 按算力依次填入小、中、大、超大核；
+    /// 超过 4 个时最强的 cluster 归为超大核，其余中间档不受控。
+    pub fn tiers(&self) -> [i32; 4] {
+        let ids: Vec<i32> = self.clusters.iter().map(|c| c.policy_id).collect();
+        let mut tiers = [-1; 4];
+        if ids.len() <= 4 {
+            tiers[..ids.len()].copy_from_slice(&ids);
+        } else {
+            tiers[..3].copy_from_slice(&ids[..3]);
+            tiers[3] = ids[ids.len() - 1];
+        }
+        tiers
+    }
+
+    fn log(&self) {
+        if self.clusters.is_empty() {
+            warn!("Topology: no cpufreq policies found under {}", CPUFREQ_DIR);
+            return;
+        }
+        for c in &self.clusters {
+            info!("Topology: policy{} cpus {:?} capacity {} freq {}-{} kHz",
+                c.policy_id, c.cpus, c.capacity.map_or("-".to_string(), |v| v.to_string()), c.min_freq, c.max_freq);
+        }
+        if self.clusters.len() > 4 {
+            warn!("Topology: {} clusters found, only 4 can be mapped to CoreFramework", self.clusters.len());
+        }
+        info!("Topology: auto CoreFramework (small, medium, big, super big) = {:?}", self.tiers());
+    }
+}
+
+fn read_u32(path: &str) -> Option<u32> {
+    fs::read_to_string(host_path(path)).ok()?.trim().parse().ok()
+}
+
+fn probe_cluster(policy_id: i32) -> Option<Cluster> {
+    let dir = format!("{}/policy{}", CPUFREQ_DIR, policy_id);
+    let cpus = related_cpus(policy_id);
+    if cpus.is_empty() {
+        return None;
+    }
+    let freqs = available_freqs(policy_id);
+    let min_freq = read_u32(&format!("{}/cpuinfo_min_freq", dir)).or_else(|| freqs.iter().copied().min()).unwrap_or(0);
+    let max_freq = read_u32(&format!("{}/cpuinfo_max_freq", dir)).or_else(|| freqs.iter().copied().max()).unwrap_or(0);
+    let capacity = read_u32(&format!("/sys/devices/system/cpu/cpu{}/cpu_capacity", cpus[0]));
+    Some(Cluster { policy_id, cpus, capacity, min_freq, max_freq })
+}
+
+/// scaling_available_frequencies，升序去重；读不到或内核未导出时为空
+pub fn available_freqs(policy_id: i32) -> Vec<u32> {
+    let mut freqs: Vec<u32> = fs::read_to_string(host_path(&format!("{}/policy{}/scaling_available_frequencies", CPUFREQ_DIR, policy_id)))
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    freqs.sort_unstable();
+    freqs.dedup();
+    freqs
+}
+
+/// related_cpus (或 affected_cpus) 中的核心编号
+pub fn related_cpus(policy_id: i32) -> Vec<usize> {
+    let dir = format!("{}/policy{}", CPUFREQ_DIR, policy_id);
+    fs::read_to_string(host_path(&format!("{}/related_cpus", dir)))
+        .or_else(|_| fs::read_to_string(host_path(&format!("{}/affected_cpus", dir))))
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+/// policy 第一个核心的 cpu_capacity
+pub fn probe_capacity(policy_id: i32) -> Option<u32> {
+    let first_cpu = *related_cpus(policy_id).first()?;
+    read_u32(&format!("/sys/devices/system/cpu/cpu{}/cpu_capacity", first_cpu))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// 临时目录下的假 cpufreq 树，结构与 `--sysfs-root` 所用的相同
+    struct Fixture(PathBuf);
+
+    /// (policy, cpus, cpu_capacity, scaling_available_frequencies)
+    type Policy<'a> = (i32, &'a [usize], Option<u32>, &'a [u32]);
+
+    fn list<T: ToString>(values: &[T]) -> String {
+        values.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+    }
+
+    impl Fixture {
+        fn new(name: &str, policies: &[Policy]) -> Self {
+            let root = std::env::temp_dir().join(format!("yumi-topology-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&root);
+            let cpu_dir = root.join("sys/devices/system/cpu");
+            for &(policy, cpus, capacity, freqs) in policies {
+                let dir = cpu_dir.join(format!("cpufreq/policy{}", policy));
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(dir.join("related_cpus"), list(cpus)).unwrap();
+                if let (Some(min), Some(max)) = (freqs.first(), freqs.last()) {
+                    fs::write(dir.join("scaling_available_frequencies"), list(freqs)).unwrap();
+                    fs::write(dir.join("cpuinfo_min_freq"), min.to_string()).unwrap();
+                    fs::write(dir.join("cpuinfo_max_freq"), max.to_string()).unwrap();
+                }
+                for cpu in cpus {
+                    let cpu_node = cpu_dir.join(format!("cpu{}", cpu));
+                    fs::create_dir_all(&cpu_node).unwrap();
+                    if let Some(capacity) = capacity {
+                        fs::write(cpu_node.join("cpu_capacity"), capacity.to_string()).unwrap();
+                    }
+                }
+            }
+            Self(root)
+        }
+
+        fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+            crate::utils::with_sysfs_root(&self.0, f)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn two_clusters_fill_small_and_medium() {
+        let fixture = Fixture::new("two", &[
+            (4, &[4, 5, 6, 7], Some(1024), &[500_000, 1_800_000, 2_400_000]),
+            (0, &[0, 1, 2, 3], Some(400), &[300_000, 1_800_000]),
+        ]);
+        let topology = fixture.run(Topology::discover);
+        assert_eq!(topology.tiers(), [0, 4, -1, -1]);
+        let small = &topology.clusters[0];
+        assert_eq!((small.cpus.as_slice(), small.capacity, small.min_freq, small.max_freq), (&[0, 1, 2, 3][..], Some(400), 300_000, 1_800_000));
+    }
+
+    #[test]
+    fn three_clusters_are_ordered_by_capacity() {
+        // policy 编号与算力顺序不一致时以 cpu_capacity 为准
+        let fixture = Fixture::new("three", &[
+            (0, &[0, 1, 2, 3], Some(250), &[300_000, 2_000_000]),
+            (4, &[4, 5, 6], Some(1024), &[600_000, 2_800_000]),
+            (7, &[7], Some(870), &[600_000, 3_000_000]),
+        ]);
+        assert_eq!(fixture.run(Topology::discover).tiers(), [0, 7, 4, -1]);
+    }
+
+    #[test]
+    fn four_clusters_without_capacity_fall_back_to_max_freq() {
+        let fixture = Fixture::new("four", &[
+            (0, &[0, 1], None, &[300_000, 1_800_000]),
+            (2, &[2, 3, 4], None, &[400_000, 2_500_000]),
+            (5, &[5, 6], None, &[400_000, 2_800_000]),
+            (7, &[7], None, &[500_000, 3_200_000]),
+        ]);
+        let topology = fixture.run(Topology::discover);
+        assert_eq!(topology.tiers(), [0, 2, 5, 7]);
+        assert!(topology.clusters.iter().all(|c| c.capacity.is_none()));
+    }
+
+    #[test]
+    fn policy_without_frequency_table() {
+        let fixture = Fixture::new("no-table", &[
+            (0, &[0, 1, 2, 3], Some(400), &[300_000, 1_800_000]),
+            (4, &[4, 5, 6, 7], Some(1024), &[]),
+        ]);
+        let (topology, freqs) = fixture.run(|| (Topology::discover(), available_freqs(4)));
+        assert!(freqs.is_empty());
+        assert_eq!(topology.tiers(), [0, 4, -1, -1]);
+        let big = &topology.clusters[1];
+        assert_eq!((big.min_freq, big.max_freq), (0, 0));
+        assert_eq!(fixture.run(|| probe_capacity(4)), Some(1024));
+    }
+
+    #[test]
+    fn missing_cpufreq_dir_is_empty() {
+        let fixture = Fixture::new("empty", &[]);
+        let topology = fixture.run(Topology::discover);
+        assert!(topology.clusters.is_empty());
+        assert_eq!(topology.tiers(), [-1; 4]);
+    }
+}
@@ -0,0 +1,189 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! config.yaml / rules.yaml 的 JSON Schema
+//!
+//! 直接由 serde 类型生成，字段名、别名、默认值和 doc 注释与守护进程实际的解析保持一致，
+//! 供 WebUI 与编辑器 (yaml-language-server 等) 做校验和补全。
+//! 输出 draft-07，这是各类编辑器插件与 ajv 默认支持的版本。
+//!
+//! device.yaml / user.yaml 只写要修改的键，另有一份覆盖层 schema：映射中的字段都不是必需的，
+//! 值可以写 null (删除下层的同名键)；列表整体替换，列表项仍按完整的类型校验。
+
+use schemars::generate::SchemaSettings;
+use schemars::transform::RecursiveTransform;
+use schemars::{JsonSchema, Schema};
+use serde_json::{json, Map, Value};
+
+use crate::monitor::config::RulesConfig;
+use crate::scheduler::config::Config;
+
+pub fn config_schema() -> Schema {
+    generate::<Config>("yumi config.yaml")
+}
+
+pub fn config_overlay_schema() -> Schema {
+    let mut root = config_schema().to_value();
+    let defs = root.get("definitions").and_then(Value::as_object).cloned().unwrap_or_default();
+    let mut overlay_defs = Map::new();
+    make_partial(&mut root, &defs, &mut overlay_defs);
+    if let Some(Value::Object(root_defs)) = root.get_mut("definitions") {
+        root_defs.extend(overlay_defs);
+    }
+    root["title"] = "yumi config overlay (device.yaml / user.yaml)".into();
+    Schema::try_from(root).expect("root schema is an object")
+}
+
+pub fn rules_schema() -> Schema {
+    generate::<RulesConfig>("yumi rules.yaml")
+}
+
+fn generate<T: JsonSchema>(title: &str) -> Schema {
+    let mut schema = SchemaSettings::draft07()
+        .for_deserialize()
+        .with_transform(RecursiveTransform(expand_aliases))
+        .with_transform(RecursiveTransform(tidy_default))
+        .with_transform(RecursiveTransform(allow_numeric_strings))
+        .into_generator()
+        .into_root_schema_for::<T>();
+    schema.insert("title".into(), title.into());
+    schema
+}
+
+/// 字段上的 serde alias 以 `x-aliases` 标出；再把每个别名展开成约束相同的属性，
+/// 按别名书写的键 (如 `Loglevel`) 也能得到补全和类型检查
+fn expand_aliases(schema: &mut Schema) {
+    let Some(Value::Object(props)) = schema.get_mut("properties") else { return };
+    let mut expanded = Vec::new();
+    for (name, prop) in props.iter() {
+        let Some(aliases) = prop.get("x-aliases").and_then(Value::as_array) else { continue };
+        for alias in aliases.iter().filter_map(Value::as_str) {
+            let mut copy = prop.clone();
+            if let Some(obj) = copy.as_object_mut() {
+                obj.remove("x-aliases");
+                obj.insert("x-alias-of".into(), name.as_str().into());
+            }
+            expanded.push((alias.to_string(), copy));
+        }
+    }
+    for (alias, copy) in expanded {
+        props.entry(alias).or_insert(copy);
+    }
+}
+
+/// serde_yaml 把未加引号的标量原样读进 String 字段，`fps_margin: 3.0`、
+/// `UclampTopAppMax: 100` 这类写法都是合法的，schema 里也应接受数字
+fn allow_numeric_strings(schema: &mut Schema) {
+    if schema.get("type").and_then(Value::as_str) == Some("string") {
+        schema.insert("type".into(), serde_json::json!(["string", "number"]));
+    }
+}
+
+/// 整理 `default`：去掉可选字段的 null 默认值 (省略即可，写 null 反而不被接受)，
+/// 并把 f32 默认值还原成配置里的写法 (0.15 而不是 0.15000000596046448)
+fn tidy_default(schema: &mut Schema) {
+    if schema.get("default").is_some_and(Value::is_null) {
+        schema.remove("default");
+    }
+    if let Some(default) = schema.get_mut("default") {
+        shorten_floats(default);
+    }
+}
+
+fn shorten_floats(value: &mut Value) {
+    match value {
+        Value::Number(n) if n.is_f64() => {
+            let short = n.as_f64().map(|f| (f as f32).to_string());
+            if let Some(v) = short.and_then(|s| s.parse::<f64>().ok()).and_then(serde_json::Number::from_f64) {
+                *n = v;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(shorten_floats),
+        Value::Object(map) => map.values_mut().for_each(shorten_floats),
+        _ => {}
+    }
+}
+
+/// 映射的 schema 改为覆盖层写法：去掉 required，每个键的值都可以写 null；
+/// `$ref` 指向的定义改为引用它的覆盖层副本 `<名称>Overlay` (列表项等其余引用处保持不变)
+fn make_partial(schema: &mut Value, defs: &Map<String, Value>, overlay_defs: &mut Map<String, Value>) {
+    let Some(obj) = schema.as_object_mut() else { return };
+    obj.remove("required");
+    let overlay = obj.get("$ref").and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix("#/definitions/"))
+        .and_then(|name| overlay_ref(name, defs, overlay_defs));
+    if let Some(overlay) = overlay {
+        obj.insert("$ref".into(), format!("#/definitions/{}", overlay).into());
+    }
+    if let Some(Value::Object(props)) = obj.get_mut("properties") {
+        for prop in props.values_mut() {
+            make_nullable(prop, defs, overlay_defs);
+        }
+    }
+    if let Some(extra) = obj.get_mut("additionalProperties").filter(|v| v.is_object()) {
+        make_nullable(extra, defs, overlay_defs);
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(key) {
+            branches.iter_mut().for_each(|b| make_partial(b, defs, overlay_defs));
+        }
+    }
+}
+
+fn make_nullable(schema: &mut Value, defs: &Map<String, Value>, overlay_defs: &mut Map<String, Value>) {
+    make_partial(schema, defs, overlay_defs);
+    *schema = json!({ "anyOf": [schema.take(), { "type": "null" }] });
+}
+
+/// 返回定义的覆盖层副本名；覆盖层写法与原定义相同 (如枚举) 时返回 None，直接引用原定义
+fn overlay_ref(name: &str, defs: &Map<String, Value>, overlay_defs: &mut Map<String, Value>) -> Option<String> {
+    let overlay = format!("{}Overlay", name);
+    if overlay_defs.contains_key(&overlay) {
+        return Some(overlay);
+    }
+    let original = defs.get(name)?;
+    // 先占位，自引用的类型不会无限递归
+    overlay_defs.insert(overlay.clone(), original.clone());
+    let mut partial = original.clone();
+    make_partial(&mut partial, defs, overlay_defs);
+    if partial == *original {
+        overlay_defs.remove(&overlay);
+        return None;
+    }
+    overlay_defs.insert(overlay.clone(), partial);
+    Some(overlay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_schema_makes_mappings_partial_but_keeps_list_items_strict() {
+        let strict = config_schema().to_value();
+        let overlay = config_overlay_schema().to_value();
+        let defs = &overlay["definitions"];
+
+        assert!(strict["definitions"]["FunctionToggles"].get("required").is_some());
+        assert!(overlay.get("required").is_none());
+        assert!(defs["FunctionTogglesOverlay"].get("required").is_none());
+        assert!(defs["ClusterDef"].get("required").is_some());
+
+        let toggle = &defs["FunctionTogglesOverlay"]["properties"]["EnableFeas"];
+        assert_eq!(toggle["anyOf"][1], json!({ "type": "null" }));
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 闭环设备仿真 (`yumi simulate`)
+//!
+//! 用合成的游戏负载端到端驱动 FasController / CpuLoadGovernor：控制器通过
+//! mock policy 写入的频率决定下一帧的耗时，帧间隔与负载采样再喂回控制器。
+//! 每组 (rules 文件, 调频器) 输出 jank 率与平均频率，用于在 CI 中比较
+//! PID 系数和 fas_rules 预设，而不是在一台手机上凭体感调参。
+
+pub mod scenario;
+pub mod workload;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::clock;
+use crate::migrate::{self, ConfigKind};
+use crate::monitor::config::{self as rules_config, ClusterProfile, RulesConfig};
+use crate::scheduler::config::ClusterDef;
+use crate::scheduler::cpu_load_governor::CpuLoadGovernor;
+use crate::scheduler::fas::FasController;
+use scenario::{PhaseParams, Scenario};
+use workload::Workload;
+
+/// 超过帧率上限间隔的这么多倍记为 jank
+const JANK_FACTOR: f64 = 1.5;
+/// 超过这么多倍记为严重卡顿
+const BIG_JANK_FACTOR: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GovernorKind {
+    Fas,
+    Clg,
+}
+
+impl GovernorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            GovernorKind::Fas => "fas",
+            GovernorKind::Clg => "clg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct SimOptions {
+    pub scenario: PathBuf,
+    /// 每个 rules 文件单独跑一遍；为空时使用模块目录下的 rules.yaml
+    pub rules: Vec<PathBuf>,
+    pub governors: Vec<GovernorKind>,
+    pub format: ReportFormat,
+    /// 任意一组结果的 jank 率 (%) 超过此值时以非零状态退出
+    pub max_jank_pct: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ClusterReport {
+    pub policy_id: usize,
+    /// 时间加权的平均频率
+    pub avg_mhz: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SimReport {
+    pub rules: String,
+    pub governor: GovernorKind,
+    /// 不含加载阶段
+    pub frames: u64,
+    pub jank_frames: u64,
+    pub big_jank_frames: u64,
+    pub jank_pct: f64,
+    pub avg_fps: f64,
+    /// 各 cluster 平均频率 / 最高频率 的均值，越低越省电
+    pub avg_freq_ratio: f64,
+    pub clusters: Vec<ClusterReport>,
+}
+
+/// 被仿真的调频器
+enum Governor {
+    Fas(Box<FasController>),
+    Clg(CpuLoadGovernor),
+}
+
+impl Governor {
+    fn new(kind: GovernorKind, rules: &RulesConfig, scenario: &Scenario) -> Self {
+        match kind {
+            GovernorKind::Fas => {
+                let tables: Vec<(usize, Vec<u32>, ClusterProfile)> = scenario.clusters.iter().enumerate()
+                    .map(|(idx, c)| {
+                        let profile = rules.fas_rules.cluster_profiles.get(&ClusterDef::positional(idx, c.policy_id as i32));
+                        (c.policy_id, c.freqs.clone(), profile)
+                    })
+                    .collect();
+                let mut fas = Box::new(FasController::new());
+                fas.load_mock_policies(&rules.fas_rules, &tables);
+                fas.set_game(0, &scenario.package);
+                Governor::Fas(fas)
+            }
+            GovernorKind::Clg => {
+                let tables: Vec<(i32, Vec<usize>, Vec<u32>)> = scenario.clusters.iter()
+                    .map(|c| (c.policy_id as i32, c.cpus.clone(), c.freqs.clone()))
+                    .collect();
+                let mut clg = CpuLoadGovernor::new();
+                clg.load_mock_policies(&rules.cpu_load_governor, &tables);
+                Governor::Clg(clg)
+            }
+        }
+    }
+
+    /// 控制器写入 scaling_max_freq 的值；尚未写入时为 None (不限频)
+    fn written_freq(&self, policy_id: usize) -> Option<u32> {
+        match self {
+            Governor::Fas(fas) => fas.policies.iter()
+                .find(|p| p.policy_id == policy_id)
+                .and_then(|p| p.freq.applied())
+                .map(|(_, max)| max),
+            Governor::Clg(clg) => clg.written_freq(policy_id as i32),
+        }
+    }
+
+    fn on_frame(&mut self, frame_ns: u64) {
+        if let Governor::Fas(fas) = self {
+            fas.update_frame(frame_ns);
+        }
+    }
+
+    fn on_load(&mut self, core_utils: &[f32], fg_util: f32) {
+        match self {
+            Governor::Fas(fas) => {
+                fas.update_cpu_util(fg_util);
+                fas.update_core_utils(core_utils);
+            }
+            Governor::Clg(clg) => clg.on_load_update(core_utils),
+        }
+    }
+}
+
+/// 与守护进程不同，解析失败直接报错，避免拿默认值跑出一份看似正常的结果
+fn load_rules(path: &Path) -> Result<RulesConfig> {
+    let content = migrate::read(ConfigKind::Rules, path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content).map_err(|e| anyhow!("{}: {}", path.display(), e))
+}
+
+/// 把帧序列折算成 jank 率、平均帧率与各 cluster 的平均频率
+struct Scorer {
+    policy_ids: Vec<usize>,
+    max_freqs: Vec<u32>,
+    /// 各 cluster 频率 × 时间的累计
+    freq_time: Vec<f64>,
+    total_ns: u64,
+    frames: u64,
+    jank: u64,
+    big_jank: u64,
+    /// 计入统计的帧 (非加载阶段) 的总时长
+    counted_ns: u64,
+}
+
+impl Scorer {
+    fn new(scenario: &Scenario) -> Self {
+        Self {
+            policy_ids: scenario.clusters.iter().map(|c| c.policy_id).collect(),
+            max_freqs: scenario.clusters.iter().map(|c| c.freqs.iter().copied().max().unwrap_or(1)).collect(),
+            freq_time: vec![0.0; scenario.clusters.len()],
+            total_ns: 0,
+            frames: 0,
+            jank: 0,
+            big_jank: 0,
+            counted_ns: 0,
+        }
+    }
+
+    /// 记录一帧，`freqs` 为这一帧期间各 cluster 生效的频率
+    fn frame(&mut self, frame_ns: u64, params: &PhaseParams, freqs: &[u32]) {
+        self.total_ns += frame_ns;
+        for (acc, freq) in self.freq_time.iter_mut().zip(freqs) {
+            *acc += *freq as f64 * frame_ns as f64;
+        }
+        if params.loading {
+            return;
+        }
+        let period_ns = 1e9 / params.fps_cap as f64;
+        self.frames += 1;
+        self.counted_ns += frame_ns;
+        if frame_ns as f64 > period_ns * BIG_JANK_FACTOR {
+            self.big_jank += 1;
+        }
+        if frame_ns as f64 > period_ns * JANK_FACTOR {
+            self.jank += 1;
+        }
+    }
+
+    fn report(&self, rules_name: &str, kind: GovernorKind) -> SimReport {
+        let total_ns = self.total_ns.max(1) as f64;
+        let clusters: Vec<ClusterReport> = self.policy_ids.iter().zip(&self.freq_time)
+            .map(|(&policy_id, acc)| ClusterReport { policy_id, avg_mhz: acc / total_ns / 1000.0 })
+            .collect();
+        let avg_freq_ratio = clusters.iter().zip(&self.max_freqs)
+            .map(|(c, max)| c.avg_mhz * 1000.0 / *max as f64)
+            .sum::<f64>() / clusters.len().max(1) as f64;
+        let frames = self.frames;
+
+        SimReport {
+            rules: rules_name.to_string(),
+            governor: kind,
+            frames,
+            jank_frames: self.jank,
+            big_jank_frames: self.big_jank,
+            jank_pct: if frames > 0 { self.jank as f64 * 100.0 / frames as f64 } else { 0.0 },
+            avg_fps: if self.counted_ns > 0 { frames as f64 * 1e9 / self.counted_ns as f64 } else { 0.0 },
+            avg_freq_ratio,
+            clusters,
+        }
+    }
+}
+
+/// 跑一组仿真
+pub fn simulate(scenario: &Scenario, rules: &RulesConfig, rules_name: &str, kind: GovernorKind) -> SimReport {
+    // 必须在创建控制器之前切换，FAS 的冷启动窗口取自虚拟时钟
+    clock::use_virtual();
+    let mut governor = Governor::new(kind, rules, scenario);
+    let mut workload = Workload::new(scenario);
+    let mut scorer = Scorer::new(scenario);
+
+    let mut freqs: Vec<u32> = scorer.max_freqs.clone();
+    let duration_ns = (scenario.duration_s * 1e9) as u64;
+    let mut t_ns = 0u64;
+
+    while t_ns < duration_ns {
+        let params = scenario.params_at(t_ns as f64 / 1e9);
+        for (freq, (cluster, max)) in freqs.iter_mut().zip(scenario.clusters.iter().zip(&scorer.max_freqs)) {
+            *freq = governor.written_freq(cluster.policy_id).unwrap_or(*max).min(*max);
+        }
+
+        let frame_ns = workload.next_frame(&params, &freqs);
+        t_ns += frame_ns;
+        clock::advance_to(t_ns);
+        scorer.frame(frame_ns, &params, &freqs);
+
+        governor.on_frame(frame_ns);
+        if let Some((core_utils, fg_util)) = workload.take_load_sample() {
+            governor.on_load(&core_utils, fg_util);
+        }
+    }
+
+    scorer.report(rules_name, kind)
+}
+
+pub fn run(opts: &SimOptions) -> Result<()> {
+    let scenario = Scenario::load(&opts.scenario)?;
+    let rules_paths = if opts.rules.is_empty() { vec![rules_config::get_rules_path()] } else { opts.rules.clone() };
+
+    let mut reports = Vec::new();
+    for path in &rules_paths {
+        let rules = load_rules(path)?;
+        for &kind in &opts.governors {
+            reports.push(simulate(&scenario, &rules, &path.display().to_string(), kind));
+        }
+    }
+
+    match opts.format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+        ReportFormat::Text => {
+            println!("{:<32} {:<4} {:>7} {:>7} {:>6} {:>7} {:>6}  avg MHz",
+                "rules", "gov", "frames", "jank%", "big", "fps", "freq%");
+            for r in &reports {
+                let mhz: Vec<String> = r.clusters.iter()
+                    .map(|c| format!("p{}={:.0}", c.policy_id, c.avg_mhz))
+                    .collect();
+                println!("{:<32} {:<4} {:>7} {:>7.2} {:>6} {:>7.2} {:>6.1}  {}",
+                    r.rules, r.governor.name(), r.frames, r.jank_pct, r.big_jank_frames,
+                    r.avg_fps, r.avg_freq_ratio * 100.0, mhz.join(" "));
+            }
+        }
+    }
+
+    if let Some(limit) = opts.max_jank_pct {
+        let failed: Vec<String> = reports.iter()
+            .filter(|r| r.jank_pct > limit)
+            .map(|r| format!("{} [{}] {:.2}%", r.rules, r.governor.name(), r.jank_pct))
+            .collect();
+        if !failed.is_empty() {
+            bail!("jank rate above {:.2}%: {}", limit, failed.join(", "));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+package: com.example.game
+duration_s: 20
+seed: 7
+fps_cap: 60
+clusters:
+  - { policy_id: 0, cpus: [0, 1, 2, 3], capacity: 0.4, freqs: [300000, 1000000, 2000000] }
+  - { policy_id: 4, cpus: [4, 5, 6, 7], capacity: 1.0, freqs: [500000, 1000000, 2000000] }
+workload:
+  cost_ms: 14.0
+  share: [0.3, 1.0]
+  noise: 0.1
+  spike_chance: 0.02
+phases:
+  - { start_s: 0, duration_s: 4, loading: true, cost_scale: 2.0, fps_cap: 30 }
+"#;
+
+    fn scenario() -> Scenario {
+        serde_yaml::from_str(SCENARIO).unwrap()
+    }
+
+    fn params(loading: bool) -> PhaseParams {
+        PhaseParams { cost_scale: 1.0, fps_cap: 60.0, noise: 0.0, loading }
+    }
+
+    #[test]
+    fn scorer_on_known_frames() {
+        let mut scorer = Scorer::new(&scenario());
+        // 加载阶段：只计入平均频率
+        scorer.frame(100_000_000, &params(true), &[2_000_000, 2_000_000]);
+        for _ in 0..6 {
+            scorer.frame(16_666_667, &params(false), &[1_000_000, 500_000]);
+        }
+        // 60 fps 下超过 25ms 为 jank，超过 50ms 为严重卡顿
+        scorer.frame(30_000_000, &params(false), &[1_000_000, 500_000]);
+        scorer.frame(60_000_000, &params(false), &[1_000_000, 500_000]);
+
+        let report = scorer.report("known", GovernorKind::Fas);
+        assert_eq!(report.frames, 8);
+        assert_eq!(report.jank_frames, 2);
+        assert_eq!(report.big_jank_frames, 1);
+        assert_eq!(report.jank_pct, 25.0);
+        assert!((report.avg_fps - 8e9 / 190_000_002.0).abs() < 1e-9, "{}", report.avg_fps);
+
+        let total = 290_000_002.0;
+        let mhz = [
+            (2000.0 * 100_000_000.0 + 1000.0 * 190_000_002.0) / total,
+            (2000.0 * 100_000_000.0 + 500.0 * 190_000_002.0) / total,
+        ];
+        let clusters: Vec<usize> = report.clusters.iter().map(|c| c.policy_id).collect();
+        assert_eq!(clusters, [0, 4]);
+        for (c, expected) in report.clusters.iter().zip(mhz) {
+            assert!((c.avg_mhz - expected).abs() < 1e-6, "p{}: {} != {}", c.policy_id, c.avg_mhz, expected);
+        }
+        assert!((report.avg_freq_ratio - (mhz[0] + mhz[1]) / 2.0 / 2000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulation_is_deterministic() {
+        let scenario = scenario();
+        let rules = RulesConfig::default();
+        for kind in [GovernorKind::Fas, GovernorKind::Clg] {
+            let first = simulate(&scenario, &rules, "default", kind);
+            let second = simulate(&scenario, &rules, "default", kind);
+            assert!(first.frames > 0);
+            assert_eq!(first, second, "{}", kind.name());
+
+            // 不同 seed 的工作负载不同
+            let reseeded = Scenario { seed: 8, ..scenario.clone() };
+            assert_ne!(simulate(&reseeded, &rules, "default", kind), first, "{}", kind.name());
+        }
+    }
+}
@@ -0,0 +1,172 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 仿真场景定义 (YAML)
+//!
+//! ```yaml
+//! package: com.example.game
+//! duration_s: 120
+//! seed: 1
+//! fps_cap: 60
+//! clusters:
+//!   - { policy_id: 0, cpus: [0, 1, 2, 3], capacity: 0.4, freqs: [300000, 1000000, 1800000] }
+//!   - { policy_id: 4, cpus: [4, 5, 6, 7], capacity: 1.0, freqs: [600000, 1800000, 2800000] }
+//! workload:
+//!   cost_ms: 14.0
+//!   share: [0.3, 1.0]
+//!   noise: 0.08
+//! phases:
+//!   - { start_s: 0, duration_s: 10, loading: true, cost_scale: 2.5, fps_cap: 30 }
+//!   - { start_s: 60, duration_s: 20, cost_scale: 1.4 }
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+fn one() -> f32 { 1.0 }
+fn d_seed() -> u64 { 1 }
+fn d_package() -> String { "com.yumi.sim".to_string() }
+fn d_spike_scale() -> f32 { 3.0 }
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default = "d_package")]
+    pub package: String,
+    pub duration_s: f64,
+    /// 同一 seed 的结果完全可复现
+    #[serde(default = "d_seed")]
+    pub seed: u64,
+    /// 游戏自身的帧率上限 (帧率锁 / vsync)
+    pub fps_cap: f32,
+    pub clusters: Vec<SimCluster>,
+    pub workload: Workload,
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimCluster {
+    pub policy_id: usize,
+    pub cpus: Vec<usize>,
+    /// 可用频率 (kHz)
+    pub freqs: Vec<u32>,
+    /// 相对单核算力 (同频下)，大核为 1.0
+    #[serde(default = "one")]
+    pub capacity: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workload {
+    /// 每帧工作量：capacity 1.0 的核心在 1 GHz 下需要的毫秒数
+    pub cost_ms: f32,
+    /// 各 cluster 上渲染相关线程承担的比例，与 clusters 一一对应；
+    /// 各线程并行执行，帧时间取最慢的那个
+    pub share: Vec<f32>,
+    /// 每帧工作量的相对标准差
+    #[serde(default)]
+    pub noise: f32,
+    /// 每帧出现尖峰 (着色器编译、GC 等) 的概率
+    #[serde(default)]
+    pub spike_chance: f32,
+    /// 尖峰帧的工作量倍数
+    #[serde(default = "d_spike_scale")]
+    pub spike_scale: f32,
+    /// 与游戏无关的后台负载，叠加到每个核心的利用率上 (0~1)
+    #[serde(default)]
+    pub background_util: f32,
+}
+
+/// 时间段覆盖，后定义的优先
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    pub start_s: f64,
+    pub duration_s: f64,
+    #[serde(default = "one")]
+    pub cost_scale: f32,
+    #[serde(default)]
+    pub fps_cap: Option<f32>,
+    #[serde(default)]
+    pub noise: Option<f32>,
+    /// 加载阶段：期间的帧不计入 jank 与平均帧率
+    #[serde(default)]
+    pub loading: bool,
+}
+
+/// 某一时刻生效的工作负载参数
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseParams {
+    pub cost_scale: f32,
+    pub fps_cap: f32,
+    pub noise: f32,
+    pub loading: bool,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let scenario: Scenario = serde_yaml::from_str(&content)
+            .with_context(|| format!("{}: invalid scenario", path.display()))?;
+        scenario.check().with_context(|| format!("{}: invalid scenario", path.display()))?;
+        Ok(scenario)
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.duration_s <= 0.0 {
+            bail!("duration_s must be positive");
+        }
+        if self.fps_cap <= 0.0 || self.phases.iter().any(|p| p.fps_cap.is_some_and(|f| f <= 0.0)) {
+            bail!("fps_cap must be positive");
+        }
+        if self.clusters.is_empty() {
+            bail!("at least one cluster is required");
+        }
+        if let Some(c) = self.clusters.iter().find(|c| c.freqs.is_empty() || c.cpus.is_empty() || c.capacity <= 0.0) {
+            bail!("cluster policy{} needs cpus, freqs and a positive capacity", c.policy_id);
+        }
+        if self.workload.share.len() != self.clusters.len() {
+            bail!("workload.share has {} entries but there are {} clusters",
+                self.workload.share.len(), self.clusters.len());
+        }
+        if self.workload.cost_ms <= 0.0 {
+            bail!("workload.cost_ms must be positive");
+        }
+        Ok(())
+    }
+
+    pub fn params_at(&self, t_s: f64) -> PhaseParams {
+        let base = PhaseParams {
+            cost_scale: 1.0,
+            fps_cap: self.fps_cap,
+            noise: self.workload.noise,
+            loading: false,
+        };
+        self.phases.iter()
+            .filter(|p| t_s >= p.start_s && t_s < p.start_s + p.duration_s)
+            .fold(base, |_, p| PhaseParams {
+                cost_scale: p.cost_scale,
+                fps_cap: p.fps_cap.unwrap_or(self.fps_cap),
+                noise: p.noise.unwrap_or(self.workload.noise),
+                loading: p.loading,
+            })
+    }
+}
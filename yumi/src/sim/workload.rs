@@ -0,0 +1,128 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 帧时间与 CPU 利用率模型
+//!
+//! 每帧工作量按 share 分到各 cluster，在当前写入的频率与 cluster 算力下
+//! 换算成执行时间；帧时间取最慢的线程，且不短于帧率上限对应的间隔。
+//! 线程忙碌时间在 200ms 采样窗口内累计成各核心利用率，与 eBPF 负载
+//! 采样的口径一致。
+
+use super::scenario::{PhaseParams, Scenario};
+
+/// 与 cpu_monitor 相同的负载采样周期
+pub const LOAD_INTERVAL_NS: u64 = 200_000_000;
+
+/// 可复现的伪随机数 (SplitMix64)
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self { Self(seed) }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// 标准正态分布 (Box-Muller)
+    pub fn gaussian(&mut self) -> f64 {
+        let u1 = self.uniform().max(f64::MIN_POSITIVE);
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
+
+pub struct Workload<'a> {
+    scenario: &'a Scenario,
+    rng: Rng,
+    /// 当前采样窗口内各 cluster 的累计忙碌时间
+    window_busy_ns: Vec<u64>,
+    window_ns: u64,
+}
+
+impl<'a> Workload<'a> {
+    pub fn new(scenario: &'a Scenario) -> Self {
+        Self {
+            scenario,
+            rng: Rng::new(scenario.seed),
+            window_busy_ns: vec![0; scenario.clusters.len()],
+            window_ns: 0,
+        }
+    }
+
+    /// 用 `freq_khz[i]` (第 i 个 cluster 当前生效的频率) 计算下一帧，返回帧间隔
+    pub fn next_frame(&mut self, params: &PhaseParams, freq_khz: &[u32]) -> u64 {
+        let w = &self.scenario.workload;
+        let mut scale = params.cost_scale as f64 * (1.0 + params.noise as f64 * self.rng.gaussian()).max(0.05);
+        if w.spike_chance > 0.0 && self.rng.uniform() < w.spike_chance as f64 {
+            scale *= w.spike_scale as f64;
+        }
+        let cost_ms = w.cost_ms as f64 * scale;
+
+        // 各 cluster 上渲染线程的忙碌时间
+        let busy_ns: Vec<u64> = self.scenario.clusters.iter().zip(&w.share).zip(freq_khz)
+            .map(|((cluster, &share), &freq)| {
+                let ghz = freq.max(1) as f64 / 1_000_000.0;
+                (cost_ms * share as f64 / (ghz * cluster.capacity as f64) * 1e6) as u64
+            })
+            .collect();
+
+        let cap_ns = (1e9 / params.fps_cap as f64) as u64;
+        let frame_ns = busy_ns.iter().copied().max().unwrap_or(0).max(cap_ns);
+
+        for (acc, busy) in self.window_busy_ns.iter_mut().zip(&busy_ns) {
+            *acc += busy;
+        }
+        self.window_ns += frame_ns;
+        frame_ns
+    }
+
+    /// 采样窗口已满时返回 (core_utils, foreground_max_util) 并开始新窗口
+    pub fn take_load_sample(&mut self) -> Option<(Vec<f32>, f32)> {
+        if self.window_ns < LOAD_INTERVAL_NS {
+            return None;
+        }
+        let background = self.scenario.workload.background_util;
+        let ncpus = self.scenario.clusters.iter()
+            .flat_map(|c| c.cpus.iter().copied())
+            .max()
+            .map_or(0, |m| m + 1);
+        let mut core_utils = vec![background.clamp(0.0, 1.0); ncpus];
+        let mut fg_util = 0.0f32;
+
+        for (cluster, busy) in self.scenario.clusters.iter().zip(&self.window_busy_ns) {
+            // 渲染线程固定在 cluster 的第一个核心上
+            let util = (*busy as f64 / self.window_ns as f64).min(1.0) as f32;
+            fg_util = fg_util.max(util);
+            if let Some(&cpu) = cluster.cpus.first() {
+                core_utils[cpu] = (util + background).min(1.0);
+            }
+        }
+
+        self.window_busy_ns.iter_mut().for_each(|b| *b = 0);
+        self.window_ns = 0;
+        Some((core_utils, fg_util))
+    }
+}
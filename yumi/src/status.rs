@@ -0,0 +1,128 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 运行时状态快照
+//!
+//! scheduler_ipc 线程每秒以及每次状态迁移时把 [`DaemonStatus`] 写入模块根目录的
+//! `state.json`，供 WebUI 与监控脚本读取。写入采用 "临时文件 + rename"，
+//! 读取方不会看到写了一半的内容。
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::common;
+use crate::monitor::app_detect::ModeOverride;
+use crate::monitor::supervisor::SourceStatus;
+use crate::scheduler::cpu_load_governor::ClgSnapshot;
+use crate::scheduler::fas::FasSnapshot;
+
+/// 周期发布间隔
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 屏幕状态由 app_detect 更新，scheduler 侧只读
+static SCREEN_ON: AtomicBool = AtomicBool::new(true);
+
+lazy_static::lazy_static! {
+    /// 配置文件名 -> 最近一次被拒绝的加载原因，该文件成功加载后清除
+    static ref CONFIG_ERRORS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+}
+
+pub fn get_state_path() -> PathBuf { common::get_module_root().join("state.json") }
+
+pub fn set_screen_on(on: bool) { SCREEN_ON.store(on, Ordering::Relaxed); }
+
+pub fn is_screen_on() -> bool { SCREEN_ON.load(Ordering::Relaxed) }
+
+pub fn set_config_error(file: &str, error: Option<String>) {
+    let mut errors = CONFIG_ERRORS.lock().unwrap();
+    match error {
+        Some(e) => { errors.insert(file.to_string(), e); }
+        None => { errors.remove(file); }
+    }
+}
+
+pub fn config_errors() -> BTreeMap<String, String> { CONFIG_ERRORS.lock().unwrap().clone() }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    /// 快照生成时间 (Unix 毫秒)
+    pub timestamp_ms: u64,
+    pub daemon_pid: u32,
+    /// rules.yaml 中 yumi_scheduler 总开关
+    pub enabled: bool,
+    /// 以 `--dry-run` 运行，不会真正写入任何节点
+    pub dry_run: bool,
+    /// 被拒绝的配置文件及原因，守护进程仍在使用上一份有效配置
+    pub config_errors: BTreeMap<String, String>,
+    pub mode: String,
+    pub package: String,
+    pub pid: i32,
+    pub screen_on: bool,
+    /// 当前生效的临时模式覆盖
+    pub mode_override: Option<ModeOverride>,
+    /// CPU 温度 (°C)，找不到传感器时为 None
+    pub temperature: Option<f64>,
+    pub boosting: bool,
+    pub fas_suspended: bool,
+    /// 仅在 FAS 持有 policy 时存在
+    pub fas: Option<FasSnapshot>,
+    /// 仅在负载调频器激活时存在
+    pub clg: Option<ClgSnapshot>,
+    /// eBPF 数据源健康状态
+    pub sources: Vec<SourceStatus>,
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 负责节流与原子写入 state.json
+pub struct StatusPublisher {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    last_publish: Option<Instant>,
+}
+
+impl StatusPublisher {
+    pub fn new() -> Self {
+        let path = get_state_path();
+        let tmp_path = path.with_extension("json.tmp");
+        Self { path, tmp_path, last_publish: None }
+    }
+
+    /// 距上次发布是否已超过 [`PUBLISH_INTERVAL`]
+    pub fn is_due(&self) -> bool {
+        self.last_publish.is_none_or(|t| t.elapsed() >= PUBLISH_INTERVAL)
+    }
+
+    pub fn publish(&mut self, status: &DaemonStatus) -> Result<()> {
+        self.last_publish = Some(Instant::now());
+        let json = serde_json::to_vec(status)?;
+        fs::write(&self.tmp_path, json)?;
+        fs::rename(&self.tmp_path, &self.path)?;
+        Ok(())
+    }
+}
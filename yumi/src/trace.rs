@@ -0,0 +1,129 @@
+/*
+ * Copyright (C) 2026 yuki
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! 帧 trace 文件格式
+//!
+//! JSONL，每行一条记录。第一行为 header，描述采集设备的 cpufreq policy
+//! 频率表；之后按时间顺序排列帧间隔、负载采样与温度，`t_ns` 为相对
+//! trace 起点的纳秒数。
+//!
+//! ```text
+//! {"type":"header","version":1,"package":"com.example.game","policies":[{"id":0,"freqs":[300000,...]}]}
+//! {"type":"frame","t_ns":16666667,"delta_ns":16666667}
+//! {"type":"load","t_ns":200000000,"core_utils":[0.31,0.12,...],"fg_util":0.64}
+//! {"type":"temp","t_ns":3000000000,"celsius":41.5}
+//! {"type":"mode","t_ns":9000000000,"mode":"balance"}
+//! ```
+//!
+//! 守护进程的录制器 ([`crate::recorder`]) 与 `yumi replay` 共用这一格式。
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+pub const TRACE_VERSION: u32 = 1;
+
+/// 一个 cpufreq policy 的频率表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracePolicy {
+    pub id: usize,
+    /// 可用频率 (kHz)
+    pub freqs: Vec<u32>,
+    /// 缺省时使用 fas_rules.cluster_profiles 中的权重 (按 policyN，或按位置对应 SmallCore ... SuperBigCore)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity_weight: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceHeader {
+    pub version: u32,
+    #[serde(default)]
+    pub package: String,
+    #[serde(default)]
+    pub policies: Vec<TracePolicy>,
+    /// 录制开始时间 (Unix 毫秒)，手写的 trace 可以省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceRecord {
+    Header(TraceHeader),
+    Frame { t_ns: u64, delta_ns: u64 },
+    Load { t_ns: u64, core_utils: Vec<f32>, fg_util: f32 },
+    Temp { t_ns: u64, celsius: f64 },
+    /// 调度模式变化，回放时仅作标注
+    Mode { t_ns: u64, mode: String },
+}
+
+impl TraceRecord {
+    pub fn t_ns(&self) -> u64 {
+        match self {
+            TraceRecord::Header(_) => 0,
+            TraceRecord::Frame { t_ns, .. }
+            | TraceRecord::Load { t_ns, .. }
+            | TraceRecord::Temp { t_ns, .. }
+            | TraceRecord::Mode { t_ns, .. } => *t_ns,
+        }
+    }
+}
+
+pub struct Trace {
+    pub header: TraceHeader,
+    /// 已按 t_ns 稳定排序，不含 header
+    pub records: Vec<TraceRecord>,
+}
+
+/// 读取整个 trace；空行与 `#` 开头的行被忽略
+pub fn read_trace(path: &Path) -> Result<Trace> {
+    let file = File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    let mut header = None;
+    let mut records = Vec::new();
+
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let record: TraceRecord = serde_json::from_str(line)
+            .with_context(|| format!("{}:{}: invalid trace record", path.display(), idx + 1))?;
+        match record {
+            TraceRecord::Header(h) => {
+                if header.is_some() {
+                    bail!("{}:{}: duplicate header", path.display(), idx + 1);
+                }
+                if h.version > TRACE_VERSION {
+                    bail!("{}: trace version {} is newer than supported version {}",
+                        path.display(), h.version, TRACE_VERSION);
+                }
+                header = Some(h);
+            }
+            other => records.push(other),
+        }
+    }
+
+    let header = header.unwrap_or(TraceHeader {
+        version: TRACE_VERSION,
+        package: String::new(),
+        policies: Vec::new(),
+        started_ms: None,
+    });
+    records.sort_by_key(TraceRecord::t_ns);
+    Ok(Trace { header, records })
+}
@@ -17,30 +17,145 @@
 
 use anyhow::{Result};
 use log;
+use once_cell::sync::OnceCell;
+use std::borrow::Cow;
 use std::fs::{self, File};
 use std::io::Read;
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use nix::unistd::{access, AccessFlags};
 
+/// 设置后 /sys、/proc、/dev 下的所有访问都重定向到该目录，
+/// 用于在普通 Linux 上对着生成的假设备树运行整个守护进程
+pub const SYSFS_ROOT_ENV: &str = "YUMI_SYSFS_ROOT";
+const REDIRECTED_ROOTS: &[&str] = &["/sys", "/proc", "/dev"];
+
+static SYSFS_ROOT: OnceCell<Option<PathBuf>> = OnceCell::new();
+
+/// 由 `--sysfs-root` 设置，必须在第一次访问设备节点之前调用；未设置时读取环境变量
+pub fn set_sysfs_root(root: Option<PathBuf>) {
+    let root = root.or_else(|| std::env::var_os(SYSFS_ROOT_ENV).filter(|v| !v.is_empty()).map(PathBuf::from));
+    if SYSFS_ROOT.set(root).is_err() {
+        log::warn!("sysfs root already initialised, ignoring late override");
+    }
+}
+
+pub fn sysfs_root() -> Option<&'static Path> {
+    SYSFS_ROOT
+        .get_or_init(|| std::env::var_os(SYSFS_ROOT_ENV).filter(|v| !v.is_empty()).map(PathBuf::from))
+        .as_deref()
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 测试用的重定向目录，只对当前线程生效并优先于进程级设置 (测试并行运行，各用各的假设备树)
+    static THREAD_SYSFS_ROOT: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// 测试用：在当前线程内把 /sys、/proc、/dev 重定向到 `root` 后执行 `f`
+#[cfg(test)]
+pub fn with_sysfs_root<T>(root: &Path, f: impl FnOnce() -> T) -> T {
+    THREAD_SYSFS_ROOT.with(|r| *r.borrow_mut() = Some(root.to_path_buf()));
+    let result = f();
+    THREAD_SYSFS_ROOT.with(|r| *r.borrow_mut() = None);
+    result
+}
+
+/// 把逻辑路径 (如 `/sys/devices/...`) 映射为实际访问的路径
+///
+/// 代码中的路径、日志与 restore.json 一律保持逻辑路径，只在真正访问文件时调用。
+pub fn host_path<P: AsRef<Path> + ?Sized>(path: &P) -> Cow<'_, Path> {
+    let path = path.as_ref();
+    #[cfg(test)]
+    if let Some(root) = THREAD_SYSFS_ROOT.with(|r| r.borrow().clone()) {
+        return redirect(&root, path);
+    }
+    let Some(root) = sysfs_root() else { return Cow::Borrowed(path) };
+    redirect(root, path)
+}
+
+fn redirect<'a>(root: &Path, path: &'a Path) -> Cow<'a, Path> {
+    let redirected = REDIRECTED_ROOTS.iter().any(|r| path.starts_with(r));
+    match path.strip_prefix("/") {
+        Ok(relative) if redirected => Cow::Owned(root.join(relative)),
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// dry-run：所有对设备节点的写入、cgroup 目录创建与 umount2 只打印日志，
+/// 监控与调度决策照常进行。用于在测试机上预览新配置会做什么
+pub const DRY_RUN_ENV: &str = "YUMI_DRY_RUN";
+
+static DRY_RUN: OnceCell<bool> = OnceCell::new();
+
+fn dry_run_from_env() -> bool {
+    std::env::var_os(DRY_RUN_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// 由 `--dry-run` 设置，必须在第一次写入之前调用；未设置时读取环境变量
+pub fn set_dry_run(enabled: bool) {
+    if DRY_RUN.set(enabled || dry_run_from_env()).is_err() {
+        log::warn!("dry-run already initialised, ignoring late override");
+    }
+}
+
+pub fn is_dry_run() -> bool {
+    *DRY_RUN.get_or_init(dry_run_from_env)
+}
+
+/// dry-run 只拦截内核节点 ([`restore::TRACKED_PREFIXES`](crate::restore::TRACKED_PREFIXES))，
+/// 模块自己的文件 (current_mode.txt、备份等) 照常写入
+fn dry_run_intercepts(path: &Path) -> bool {
+    is_dry_run() && crate::restore::is_device_path(path)
+}
+
+/// 记录一次被 dry-run 拦截的写入
+pub fn log_dry_write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C) {
+    log::info!("[dry-run] write {} = {:?}",
+        path.as_ref().display(), String::from_utf8_lossy(content.as_ref()).trim_end());
+}
+
+/// 创建 cgroup 等目录 (dry-run 时只打日志)
+pub fn create_dir<P: AsRef<Path>>(path: P, mode: u32) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    if dry_run_intercepts(path.as_ref()) {
+        log::info!("[dry-run] mkdir {} (mode {:o})", path.as_ref().display(), mode);
+        return Ok(());
+    }
+    fs::DirBuilder::new().mode(mode).recursive(true).create(host_path(path.as_ref()))?;
+    Ok(())
+}
+
 /// 向文件写入内容，并处理可能的错误
 pub fn write_to_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C) -> Result<()> {
-    let path = path.as_ref();
+    if crate::restore::is_shutting_down() { return Ok(()); }
+    if dry_run_intercepts(path.as_ref()) {
+        log_dry_write(path, content);
+        return Ok(());
+    }
+    crate::restore::record_original(path.as_ref());
+    let host = host_path(path.as_ref());
 
     // 尝试修改权限以便写入
-    if path.exists() {
-        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o664));
+    if host.exists() {
+        let _ = fs::set_permissions(&host, fs::Permissions::from_mode(0o664));
     }
 
-    fs::write(path, content)?;
+    fs::write(&host, content)?;
     
     // 写完后设为只读
-    let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o444));
+    let _ = fs::set_permissions(&host, fs::Permissions::from_mode(0o444));
     Ok(())
 }
 
 pub fn write_to_file_no_perm_change<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C) -> Result<()> {
-    fs::write(path.as_ref(), content)?;
+    if crate::restore::is_shutting_down() { return Ok(()); }
+    if dry_run_intercepts(path.as_ref()) {
+        log_dry_write(path, content);
+        return Ok(());
+    }
+    crate::restore::record_original(path.as_ref());
+    fs::write(host_path(path.as_ref()), content)?;
     Ok(())
 }
 
@@ -60,9 +175,10 @@ pub fn try_write_file_no_perm<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content:
 }
 
 pub fn enable_perm <P: AsRef<Path>>(path: P) -> Result<()> {
-    let path = path.as_ref();
+    if dry_run_intercepts(path.as_ref()) { return Ok(()); }
+    let path = host_path(path.as_ref());
     if path.exists() {
-        fs::set_permissions(path, fs::Permissions::from_mode(0o664))?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o664))?;
     }
     Ok(())
 }
@@ -72,7 +188,7 @@ pub fn watch_path<P: AsRef<Path>>(path_to_watch: P) -> Result<()> {
     use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
     
     let inotify = Inotify::init(InitFlags::empty())?;
-    inotify.add_watch(path_to_watch.as_ref(), AddWatchFlags::IN_CLOSE_WRITE)?;
+    inotify.add_watch(host_path(path_to_watch.as_ref()).as_ref(), AddWatchFlags::IN_CLOSE_WRITE)?;
     
     let _buffer = [0u8; 1024];
     let _events = inotify.read_events()?;
@@ -83,10 +199,46 @@ pub fn watch_path<P: AsRef<Path>>(path_to_watch: P) -> Result<()> {
     Ok(())
 }
 
+/// 监控目录内指定文件的写入、新建 (含 rename 进来) 与删除
+///
+/// 配置目录下的覆盖层可能被新建或删除 (WebUI 与编辑器常用先写临时文件再 rename 的方式保存)，
+/// 只监听 IN_CLOSE_WRITE 会漏掉这些变化。inotify 实例只创建一次，两次 [`DirWatcher::wait`]
+/// 之间发生的变化不会丢失；临时文件、备份等其他文件的事件被忽略。
+pub struct DirWatcher {
+    inotify: nix::sys::inotify::Inotify,
+    dir: PathBuf,
+    names: &'static [&'static str],
+}
+
+impl DirWatcher {
+    pub fn new<P: AsRef<Path>>(dir: P, names: &'static [&'static str]) -> Result<Self> {
+        use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+
+        let inotify = Inotify::init(InitFlags::empty())?;
+        let flags = AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_MOVED_TO | AddWatchFlags::IN_DELETE;
+        inotify.add_watch(host_path(dir.as_ref()).as_ref(), flags)?;
+        Ok(Self { inotify, dir: dir.as_ref().to_path_buf(), names })
+    }
+
+    /// 阻塞直到 `names` 中的某个文件发生变化
+    pub fn wait(&self) -> Result<()> {
+        loop {
+            let events = self.inotify.read_events()?;
+            let changed = events.iter()
+                .filter_map(|e| e.name.as_ref())
+                .find(|name| self.names.iter().any(|n| name.as_os_str() == *n));
+            if let Some(name) = changed {
+                log::debug!("Detected change of {:?} in {:?}, re-evaluating...", name, self.dir);
+                return Ok(());
+            }
+        }
+    }
+}
+
 // 通用的读取文件为 f64 的函数
 pub fn read_f64_from_file(path: &str) -> Result<f64> {
     let mut content = String::new();
-    File::open(path)?.read_to_string(&mut content)?;
+    File::open(host_path(path))?.read_to_string(&mut content)?;
     let val: f64 = content.trim().parse()?;
     Ok(val)
 }
@@ -94,23 +246,24 @@ pub fn read_f64_from_file(path: &str) -> Result<f64> {
 // 辅助函数：读取文件内容为 String
 pub fn read_file_content(path: &str) -> Result<String> {
     let mut content = String::new();
-    File::open(path)?.read_to_string(&mut content)?;
+    File::open(host_path(path))?.read_to_string(&mut content)?;
     Ok(content.trim().to_string())
 }
 
 // 查找 CPU 温度路径的逻辑
 pub fn find_cpu_temp_path() -> Result<String> {
     let thermal_path = "/sys/class/thermal";
-    let thermal_dir = Path::new(thermal_path);
+    let thermal_dir = host_path(thermal_path);
     
     if !thermal_dir.exists() {
          return Err(anyhow::anyhow!("Thermal directory not found"));
     }
 
-    for entry in fs::read_dir(thermal_dir)? {
+    for entry in fs::read_dir(&thermal_dir)? {
         let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
+        // 返回逻辑路径，调用方读取时再经 host_path 映射
+        let path = Path::new(thermal_path).join(entry.file_name());
+        if entry.path().is_dir() {
             if let Some(dir_name) = path.file_name().and_then(|s| s.to_str()) {
                 if dir_name.starts_with("thermal_zone") {
                     let type_path = path.join("type");
@@ -122,7 +275,7 @@ pub fn find_cpu_temp_path() -> Result<String> {
                            || type_content.contains("cpu-0-0-usr") {
                             
                             let temp_path = path.join("temp");
-                            if temp_path.exists() {
+                            if host_path(&temp_path).exists() {
                                 return Ok(temp_path.to_str().unwrap().to_string());
                             }
                         }
@@ -177,6 +330,6 @@ impl SysPathExist {
     }
 
     fn path_exists(path: &str) -> bool {
-        access(path, AccessFlags::F_OK).is_ok()
+        access(host_path(path).as_ref(), AccessFlags::F_OK).is_ok()
     }
 }
\ No newline at end of file
@@ -5,6 +5,8 @@ scheduler-module-start-failed = Failed to start scheduler module: { $error }
 monitor-module-crashed = Monitor module crashed: { $error }
 monitor-module-started = Monitor module started.
 monitor-starting = Starting yumi-monitor module...
+monitor-source-failed = [Monitor] Data source { $source } failed: { $error } (restarting in { $secs }s)
+monitor-source-restarting = [Monitor] Restarting data source { $source }...
 
 # Boot
 boot-scripts-running = [Boot] Running boot scripts...
@@ -30,12 +32,14 @@ power-status-read-failed = [Power] Failed to read charging status: { $error }
 app-detect-config-watch = [AppDetect] Started watching config file: { $path }
 app-detect-change-detected = [AppDetect] Change detected, debouncing for 100ms...
 app-detect-reloading = [AppDetect] Debounce finished. Reloading config...
-app-detect-load-failed = [AppDetect] Failed: { $error }. Using default.
+app-detect-reload-rejected = [AppDetect] Reload rejected, keeping the previous rules: { $error }
 app-detect-reload-success = [AppDetect] Config reloaded successfully.
 app-detect-loop-started = [AppDetect] App detection loop started (3000ms poll).
 app-detect-screen-changed = [AppDetect] Screen changed: { $old } -> { $new }
 app-detect-mode-change = [AppDetect] Mode change: { $old } -> { $new }
 app-detect-mode-change-pkg = [AppDetect] Mode change: { $old } -> { $new } ({ $pkg })
+app-detect-override-set = [AppDetect] Mode override set: { $mode }
+app-detect-override-ended = [AppDetect] Mode override { $mode } ended ({ $reason })
 
 # ScreenDetect
 screen-state-change-detected = [Screen] State change detected via '{ $source }'.
@@ -52,7 +56,7 @@ scheduler-channel-closed = [Scheduler] Channel closed! Thread exiting.
 # --- Scheduler: Config Watcher (added missing keys) ---
 config-reloading = [Config] Config file change detected, reloading...
 config-reloaded-success = [Config] Config reloaded successfully.
-config-reload-fail = [Config] Config reload failed: { $error }
+config-reload-fail = [Config] Config reload rejected, keeping the current config: { $error }
 config-watch-error = [Config] Failed to watch config directory: { $error }
 config-apply-mode-failed = [Config] Failed to apply reloaded mode settings: { $error }
 config-apply-tweaks-failed = [Config] Failed to apply reloaded system tweaks: { $error }
@@ -90,3 +94,6 @@ attempted-to-disable-eas-scheduler = Attempted to disable EAS scheduler.
 
 # --- Logger ---
 log-level-updated = Log level updated to: { $level }
+
+# --- Control ---
+control-server-started = [Control] Listening on { $path }
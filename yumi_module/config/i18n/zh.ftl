@@ -5,6 +5,8 @@ scheduler-module-start-failed = 启动调度器模块失败: { $error }
 monitor-module-crashed = 监控模块崩溃: { $error }
 monitor-module-started = 监控模块已启动
 monitor-starting = 正在启动 yumi-monitor 模块...
+monitor-source-failed = [Monitor] 数据源 { $source } 失败: { $error } ({ $secs } 秒后重启)
+monitor-source-restarting = [Monitor] 正在重启数据源 { $source }...
 
 # Boot
 boot-scripts-running = [Boot] 正在运行启动脚本...
@@ -30,12 +32,14 @@ power-status-read-failed = [Power] 读取充电状态失败: { $error }
 app-detect-config-watch = [AppDetect] 开始监控配置文件: { $path }
 app-detect-change-detected = [AppDetect] 检测到变更，正在防抖 (100ms)...
 app-detect-reloading = [AppDetect] 防抖结束。正在重载配置...
-app-detect-load-failed = [AppDetect] 失败: { $error }。使用默认值
+app-detect-reload-rejected = [AppDetect] 重载被拒绝，继续使用之前的规则: { $error }
 app-detect-reload-success = [AppDetect] 配置重载成功
 app-detect-loop-started = [AppDetect] 应用检测循环已启动 (3000ms 轮询)
 app-detect-screen-changed = [AppDetect] 屏幕状态变更: { $old } -> { $new }
 app-detect-mode-change = [AppDetect] 模式变更: { $old } -> { $new }
 app-detect-mode-change-pkg = [AppDetect] 模式变更: { $old } -> { $new } ({ $pkg })
+app-detect-override-set = [AppDetect] 已设置临时模式覆盖: { $mode }
+app-detect-override-ended = [AppDetect] 临时模式覆盖 { $mode } 已结束 ({ $reason })
 
 # ScreenDetect
 screen-state-change-detected = [Screen] 通过 '{ $source }' 检测到状态变更
@@ -52,7 +56,7 @@ scheduler-channel-closed = [Scheduler] 通道已关闭！线程退出
 # --- Scheduler: Config Watcher (新增缺失 key) ---
 config-reloading = [Config] 检测到配置文件变更，正在重载...
 config-reloaded-success = [Config] 配置重载成功
-config-reload-fail = [Config] 配置重载失败: { $error }
+config-reload-fail = [Config] 配置重载被拒绝，继续使用当前配置: { $error }
 config-watch-error = [Config] 监控配置目录失败: { $error }
 config-apply-mode-failed = [Config] 应用重载的模式设置失败: { $error }
 config-apply-tweaks-failed = [Config] 应用重载的系统微调失败: { $error }
@@ -90,3 +94,6 @@ attempted-to-disable-eas-scheduler = 已尝试禁用 EAS 调度器
 
 # --- Logger ---
 log-level-updated = 日志级别已更新为: { $level }
+
+# --- Control ---
+control-server-started = [Control] 控制 socket 已开始监听: { $path }
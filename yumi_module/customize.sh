@@ -12,6 +12,12 @@ CONFIG_DIR="$MODPATH/config"
 FINAL_CONFIG_PATH="$CONFIG_DIR/config.yaml"
 # 本地可选配置文件目录
 LOCAL_CONFIGS_DIR="$MODPATH/configs"
+# 已安装版本的配置目录 (更新模块时从这里保留设备层与用户覆盖层)
+INSTALLED_CONFIG_DIR="/data/adb/modules/yumi/config"
+# 新模块自带的 yumi，用于把旧版改过的 config.yaml 转换为 user.yaml
+YUMI_BIN="$MODPATH/core/bin/yumi"
+# 安装时基础配置的原样副本，下次更新时用来找出用户对 config.yaml 的直接修改
+BASE_ORIG_NAME="base.orig.yaml"
 # 临时下载位置
 TEMP_DOWNLOAD_FILE="/data/local/tmp/yumi_config_download.yaml"
 
@@ -69,6 +75,13 @@ MSG_DOWNLOAD_APPLY_SUCCESS="-> ✔ Successfully applied SoC-specific config:"
 MSG_DOWNLOAD_APPLY_PATH="   - Specific config replaced at:"
 MSG_DOWNLOAD_FAIL="-> ❌ Failed to find or download specific config."
 MSG_DOWNLOAD_FALLBACK="-> Module will use the built-in default config.yaml."
+MSG_KEEP_LAYERS="-> 5. Keeping device / user overrides from the installed module..."
+MSG_KEEP_LAYER="   - ✔ Kept:"
+MSG_MIGRATE_LAYERS="-> 5. Moving your edits of the installed config.yaml into user.yaml..."
+MSG_MIGRATE_DONE="   - ✔ Written:"
+MSG_MIGRATE_NONE="   - No edits found."
+MSG_MIGRATE_FAIL="   - ❌ Could not compare the configs. The old config is kept as:"
+MSG_MIGRATE_NO_BASE="   - The installed version did not keep its original config, so edits cannot be told apart. Move them into user.yaml by hand from:"
 MSG_CONFIG_READY="-> Configuration files are ready."
 
 # 检查是否包含 zh
@@ -101,6 +114,13 @@ if echo "$CURRENT_LOCALE" | $BUSYBOX grep -qi "zh"; then
   MSG_DOWNLOAD_APPLY_PATH="   - 专用配置已替换:"
   MSG_DOWNLOAD_FAIL="-> ❌ 专用配置获取失败。"
   MSG_DOWNLOAD_FALLBACK="-> 模块将使用已内置的默认 config.yaml。"
+  MSG_KEEP_LAYERS="-> 5. 正在保留已安装模块中的设备层 / 用户覆盖配置..."
+  MSG_KEEP_LAYER="   - ✔ 已保留:"
+  MSG_MIGRATE_LAYERS="-> 5. 正在把已安装版本 config.yaml 中的改动转移到 user.yaml..."
+  MSG_MIGRATE_DONE="   - ✔ 已写入:"
+  MSG_MIGRATE_NONE="   - 没有发现改动。"
+  MSG_MIGRATE_FAIL="   - ❌ 无法比较新旧配置，旧配置已保存为:"
+  MSG_MIGRATE_NO_BASE="   - 已安装版本没有保留原始配置，无法区分改动，请手动将改动写入 user.yaml，旧配置已保存为:"
   MSG_CONFIG_READY="-> 配置文件准备完成。"
 fi
 # --- 语言定义结束 ---
@@ -342,5 +362,41 @@ if [ -d "$LOCAL_CONFIGS_DIR" ]; then
     $BUSYBOX rm -rf "$LOCAL_CONFIGS_DIR"
 fi
 
+# 步骤 7: 保留设备层与用户覆盖层 (只包含改动过的键，叠加在新的 config.yaml 之上)
+if [ -f "$INSTALLED_CONFIG_DIR/device.yaml" ] || [ -f "$INSTALLED_CONFIG_DIR/user.yaml" ]; then
+    ui_print " "
+    ui_print "$MSG_KEEP_LAYERS"
+    for layer in device.yaml user.yaml; do
+        if [ -f "$INSTALLED_CONFIG_DIR/$layer" ]; then
+            $BUSYBOX cp -f "$INSTALLED_CONFIG_DIR/$layer" "$CONFIG_DIR/$layer"
+            ui_print " $MSG_KEEP_LAYER $layer"
+        fi
+    done
+elif [ -f "$INSTALLED_CONFIG_DIR/config.yaml" ]; then
+    # 没有覆盖层时用户直接修改 config.yaml：与已安装版本自带的基础配置比较，差异写入 user.yaml
+    # (与新基础配置比较会把上游改动的默认值当作用户改动固定下来)
+    ui_print " "
+    ui_print "$MSG_MIGRATE_LAYERS"
+    chmod 755 "$YUMI_BIN"
+    if [ ! -f "$INSTALLED_CONFIG_DIR/$BASE_ORIG_NAME" ]; then
+        $BUSYBOX cp -f "$INSTALLED_CONFIG_DIR/config.yaml" "$CONFIG_DIR/config.yaml.old"
+        ui_print " $MSG_MIGRATE_NO_BASE config.yaml.old"
+    elif "$YUMI_BIN" diff-config "$INSTALLED_CONFIG_DIR/$BASE_ORIG_NAME" "$INSTALLED_CONFIG_DIR/config.yaml" > "$CONFIG_DIR/user.yaml.tmp"; then
+        if [ -s "$CONFIG_DIR/user.yaml.tmp" ]; then
+            $BUSYBOX mv -f "$CONFIG_DIR/user.yaml.tmp" "$CONFIG_DIR/user.yaml"
+            ui_print " $MSG_MIGRATE_DONE user.yaml"
+        else
+            ui_print " $MSG_MIGRATE_NONE"
+        fi
+    else
+        $BUSYBOX cp -f "$INSTALLED_CONFIG_DIR/config.yaml" "$CONFIG_DIR/config.yaml.old"
+        ui_print " $MSG_MIGRATE_FAIL config.yaml.old"
+    fi
+    $BUSYBOX rm -f "$CONFIG_DIR/user.yaml.tmp"
+fi
+
+# 保存本次安装的基础配置原样副本 (daemon 不读取它)
+$BUSYBOX cp -f "$FINAL_CONFIG_PATH" "$CONFIG_DIR/$BASE_ORIG_NAME"
+
 ui_print " "
 ui_print "$MSG_CONFIG_READY"
\ No newline at end of file
@@ -34,8 +34,14 @@ LOG_DIR="$MODDIR/logs"
 # 确保日志目录存在
 mkdir -p "$LOG_DIR"
 
-# 3. 清理旧进程
-killall -9 yumi > /dev/null 2>&1
+# 3. 清理旧进程 (先 TERM 让其恢复原始节点值，超时再强杀)
+if killall yumi > /dev/null 2>&1; then
+  for _ in 1 2 3 4 5; do
+    pidof yumi > /dev/null 2>&1 || break
+    sleep 1
+  done
+  killall -9 yumi > /dev/null 2>&1
+fi
 
 # 4. 设置权限
 chmod 755 "$DAEMON_PATH"
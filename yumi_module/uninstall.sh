@@ -1,5 +1,8 @@
 #!/system/bin/sh
 
+# 若守护进程仍在运行，发送 TERM 让其把写过的节点恢复为原值
+killall yumi >/dev/null 2>&1
+
 # 恢复 OPPO/OnePlus/Realme 的 Oiface
 if [ -n "$(getprop persist.sys.oiface.enable)" ]; then
   setprop persist.sys.oiface.enable 1